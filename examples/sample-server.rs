@@ -3,5 +3,9 @@ use anyhow::Result;
 #[tokio::main]
 async fn main() -> Result<()> {
     let port = 3003;
-    sample_server::start_grpc_server(port).await
+    if std::env::args().any(|arg| arg == "--plaintext") {
+        sample_server::start_plaintext_grpc_server(port).await
+    } else {
+        sample_server::start_grpc_server(port).await
+    }
 }
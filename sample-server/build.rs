@@ -8,7 +8,10 @@ fn main() {
         .file_descriptor_set_path("src/pb/example.bin")
         .compile_protos_with_config(
             config,
-            &["../fixtures/protos/example.proto"],
+            &[
+                "../fixtures/protos/example.proto",
+                "../fixtures/protos/grpc/health/v1/health.proto",
+            ],
             &["../fixtures/protos"],
         )
         .unwrap();
@@ -0,0 +1,109 @@
+use crate::pb::health::{
+    HealthCheckRequest, HealthCheckResponse,
+    health_check_response::ServingStatus,
+    health_server::{Health, HealthServer},
+};
+use std::{collections::HashMap, pin::Pin};
+use tokio::sync::{mpsc, watch};
+use tokio_stream::{Stream, wrappers::ReceiverStream};
+use tonic::{Request, Response, Status};
+
+/// Sets the serving status reported by the health service. The empty service
+/// name stands for the server as a whole.
+#[derive(Debug, Clone)]
+pub struct HealthReporter {
+    statuses: watch::Sender<HashMap<String, ServingStatus>>,
+}
+
+impl Default for HealthReporter {
+    /// The server and the user service are serving
+    fn default() -> Self {
+        let statuses = HashMap::from([
+            (String::new(), ServingStatus::Serving),
+            ("example.UserService".to_string(), ServingStatus::Serving),
+        ]);
+        Self {
+            statuses: watch::Sender::new(statuses),
+        }
+    }
+}
+
+impl HealthReporter {
+    pub fn set_serving_status(&self, service: &str, status: ServingStatus) {
+        self.statuses.send_modify(|statuses| {
+            statuses.insert(service.to_string(), status);
+        });
+    }
+
+    /// Health service backed by this reporter
+    pub fn service(&self) -> HealthServer<HealthService> {
+        HealthServer::new(HealthService {
+            reporter: self.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthService {
+    reporter: HealthReporter,
+}
+
+fn response(status: ServingStatus) -> HealthCheckResponse {
+    HealthCheckResponse {
+        status: status as i32,
+    }
+}
+
+#[tonic::async_trait]
+impl Health for HealthService {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service = request.into_inner().service;
+        match self.reporter.statuses.borrow().get(&service) {
+            Some(status) => Ok(Response::new(response(*status))),
+            None => Err(Status::not_found(format!("Unknown service: {}", service))),
+        }
+    }
+
+    type WatchStream = Pin<Box<dyn Stream<Item = Result<HealthCheckResponse, Status>> + Send>>;
+
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service = request.into_inner().service;
+        let mut statuses = self.reporter.statuses.subscribe();
+        let (tx, rx) = mpsc::channel(4);
+
+        // Send the current status, then one message per change
+        tokio::spawn(async move {
+            let mut last = None;
+            loop {
+                let status = statuses
+                    .borrow_and_update()
+                    .get(&service)
+                    .copied()
+                    .unwrap_or(ServingStatus::ServiceUnknown);
+                if last != Some(status) {
+                    if tx.send(Ok(response(status))).await.is_err() {
+                        break;
+                    }
+                    last = Some(status);
+                }
+
+                tokio::select! {
+                    changed = statuses.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                    _ = tx.closed() => break,
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
@@ -9,11 +9,18 @@ use tokio::sync::mpsc;
 use tokio_stream::{Stream, StreamExt as _, wrappers::ReceiverStream};
 use tonic::{
     Request, Response, Status, Streaming,
+    service::Routes,
     transport::{Identity, Server, ServerTlsConfig},
 };
 use tracing::info;
 
+mod health;
 pub mod pb;
+mod reflection;
+
+pub use health::{HealthReporter, HealthService};
+pub use pb::health::health_check_response::ServingStatus;
+pub use reflection::RawReflectionService;
 
 #[derive(Debug, Clone, Default)]
 pub struct ExampleService;
@@ -23,29 +30,57 @@ pub async fn start_grpc_server(port: u16) -> Result<()> {
         .install_default()
         .unwrap();
 
-    let reflection = tonic_reflection::server::Builder::configure()
-        .register_encoded_file_descriptor_set(include_bytes!("../src/pb/example.bin"))
-        .build_v1()
-        .unwrap();
-
     let addr = SocketAddr::from(([127, 0, 0, 1], port));
-    let service = ExampleService;
 
     info!("Starting server on {}", addr);
 
     let cert = fs::read_to_string("fixtures/certs/grpc.acme.com.crt")?;
     let key = fs::read_to_string("fixtures/certs/grpc.acme.com.key")?;
     let identity = Identity::from_pem(cert, key);
+    let mut server = Server::builder().tls_config(ServerTlsConfig::new().identity(identity))?;
+    server
+        .add_routes(build_routes(HealthReporter::default())?)
+        .serve(addr)
+        .await?;
+
+    Ok(())
+}
+
+/// Start the sample server without TLS, useful for local testing
+pub async fn start_plaintext_grpc_server(port: u16) -> Result<()> {
+    start_plaintext_grpc_server_with_health(port, HealthReporter::default()).await
+}
+
+/// Start the sample server without TLS, reporting health through `health`
+pub async fn start_plaintext_grpc_server_with_health(
+    port: u16,
+    health: HealthReporter,
+) -> Result<()> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+
+    info!("Starting plaintext server on {}", addr);
+
     Server::builder()
-        .tls_config(ServerTlsConfig::new().identity(identity))?
-        .add_service(reflection)
-        .add_service(UserServiceServer::new(service))
+        .add_routes(build_routes(health)?)
         .serve(addr)
         .await?;
 
     Ok(())
 }
 
+fn build_routes(health: HealthReporter) -> Result<Routes> {
+    let reflection =
+        RawReflectionService::new(include_bytes!("../src/pb/example.bin"))?.into_server();
+
+    let mut routes = Routes::builder();
+    routes
+        .add_service(reflection)
+        .add_service(health.service())
+        .add_service(UserServiceServer::new(ExampleService));
+
+    Ok(routes.routes())
+}
+
 #[tonic::async_trait]
 impl UserService for ExampleService {
     type GetUserStream = Pin<Box<dyn Stream<Item = Result<User, Status>> + Send + Sync>>;
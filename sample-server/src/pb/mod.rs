@@ -1,3 +1,5 @@
 mod example;
+#[path = "grpc.health.v1.rs"]
+pub mod health;
 
 pub use example::*;
@@ -0,0 +1,200 @@
+use anyhow::{Result, bail};
+use prost::Message;
+use prost::encoding::{WireType, decode_key, decode_varint};
+use prost_types::{DescriptorProto, EnumDescriptorProto, FileDescriptorProto};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt as _, wrappers::ReceiverStream};
+use tonic::{Code, Request, Response, Status, Streaming};
+use tonic_reflection::pb::v1::{
+    ErrorResponse, ExtensionNumberResponse, FileDescriptorResponse, ListServiceResponse,
+    ServerReflectionRequest, ServerReflectionResponse, ServiceResponse,
+    server_reflection_request::MessageRequest,
+    server_reflection_response::MessageResponse,
+    server_reflection_server::{ServerReflection, ServerReflectionServer},
+};
+
+/// Reflection service answering with file descriptors exactly as `protoc`
+/// encoded them.
+///
+/// tonic-reflection decodes descriptors into `prost_types` and encodes them
+/// again, which drops field options `prost_types` doesn't know, such as
+/// `debug_redact`. Clients need those to mask sensitive fields.
+#[derive(Debug, Clone)]
+pub struct RawReflectionService {
+    state: Arc<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    services: Vec<String>,
+    /// Encoded file descriptors by file name
+    files: HashMap<String, Vec<u8>>,
+    /// File name by fully qualified symbol
+    symbols: HashMap<String, String>,
+}
+
+impl RawReflectionService {
+    /// Serve the files of an encoded `FileDescriptorSet`
+    pub fn new(encoded_file_descriptor_set: &[u8]) -> Result<Self> {
+        let mut state = State::default();
+        for raw in split_files(encoded_file_descriptor_set)? {
+            let file = FileDescriptorProto::decode(raw.as_slice())?;
+            let package = file.package();
+            for service in &file.service {
+                let name = qualify(package, service.name());
+                for method in &service.method {
+                    state.symbols.insert(
+                        format!("{}.{}", name, method.name()),
+                        file.name().to_string(),
+                    );
+                }
+                state.services.push(name.clone());
+                state.symbols.insert(name, file.name().to_string());
+            }
+            for message in &file.message_type {
+                index_message(&mut state.symbols, package, message, file.name());
+            }
+            for enum_type in &file.enum_type {
+                index_enum(&mut state.symbols, package, enum_type, file.name());
+            }
+            state.files.insert(file.name().to_string(), raw);
+        }
+        Ok(Self {
+            state: Arc::new(state),
+        })
+    }
+
+    pub fn into_server(self) -> ServerReflectionServer<Self> {
+        ServerReflectionServer::new(self)
+    }
+}
+
+impl State {
+    fn respond(&self, request: MessageRequest) -> MessageResponse {
+        let file = |name: Option<&String>, missing: String| match name
+            .and_then(|name| self.files.get(name))
+        {
+            Some(raw) => MessageResponse::FileDescriptorResponse(FileDescriptorResponse {
+                file_descriptor_proto: vec![raw.clone()],
+            }),
+            None => not_found(missing),
+        };
+        match request {
+            MessageRequest::FileByFilename(name) => file(Some(&name), name.clone()),
+            MessageRequest::FileContainingSymbol(symbol) => {
+                file(self.symbols.get(&symbol), symbol.clone())
+            }
+            MessageRequest::FileContainingExtension(_) => {
+                not_found("extensions are not supported".to_string())
+            }
+            MessageRequest::AllExtensionNumbersOfType(_) => {
+                MessageResponse::AllExtensionNumbersResponse(ExtensionNumberResponse::default())
+            }
+            MessageRequest::ListServices(_) => {
+                MessageResponse::ListServicesResponse(ListServiceResponse {
+                    service: self
+                        .services
+                        .iter()
+                        .map(|name| ServiceResponse { name: name.clone() })
+                        .collect(),
+                })
+            }
+        }
+    }
+}
+
+fn not_found(message: String) -> MessageResponse {
+    MessageResponse::ErrorResponse(ErrorResponse {
+        error_code: Code::NotFound as i32,
+        error_message: message,
+    })
+}
+
+type ResponseStream = Pin<Box<dyn Stream<Item = Result<ServerReflectionResponse, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl ServerReflection for RawReflectionService {
+    type ServerReflectionInfoStream = ResponseStream;
+
+    async fn server_reflection_info(
+        &self,
+        request: Request<Streaming<ServerReflectionRequest>>,
+    ) -> Result<Response<Self::ServerReflectionInfoStream>, Status> {
+        let mut requests = request.into_inner();
+        let (tx, rx) = mpsc::channel(4);
+        let state = self.state.clone();
+
+        tokio::spawn(async move {
+            while let Some(Ok(request)) = requests.next().await {
+                let Some(message) = request.message_request.clone() else {
+                    let _ = tx
+                        .send(Err(Status::invalid_argument("invalid MessageRequest")))
+                        .await;
+                    break;
+                };
+                let response = ServerReflectionResponse {
+                    valid_host: request.host.clone(),
+                    message_response: Some(state.respond(message)),
+                    original_request: Some(request),
+                };
+                if tx.send(Ok(response)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Encoded files of a `FileDescriptorSet`, without decoding them
+fn split_files(mut set: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut files = Vec::new();
+    while !set.is_empty() {
+        let (tag, wire_type) = decode_key(&mut set)?;
+        if tag != 1 || wire_type != WireType::LengthDelimited {
+            bail!("Unexpected field {} in FileDescriptorSet", tag);
+        }
+        let len = decode_varint(&mut set)? as usize;
+        if len > set.len() {
+            bail!("Truncated FileDescriptorSet");
+        }
+        files.push(set[..len].to_vec());
+        set = &set[len..];
+    }
+    Ok(files)
+}
+
+fn qualify(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+fn index_message(
+    symbols: &mut HashMap<String, String>,
+    scope: &str,
+    message: &DescriptorProto,
+    file: &str,
+) {
+    let name = qualify(scope, message.name());
+    for nested in &message.nested_type {
+        index_message(symbols, &name, nested, file);
+    }
+    for enum_type in &message.enum_type {
+        index_enum(symbols, &name, enum_type, file);
+    }
+    symbols.insert(name, file.to_string());
+}
+
+fn index_enum(
+    symbols: &mut HashMap<String, String>,
+    scope: &str,
+    enum_type: &EnumDescriptorProto,
+    file: &str,
+) {
+    symbols.insert(qualify(scope, enum_type.name()), file.to_string());
+}
@@ -0,0 +1,506 @@
+use crate::template::TemplateEngine;
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Tokens are refreshed this long before they expire, or halfway through
+/// their lifetime when that is shorter
+const REFRESH_MARGIN: Duration = Duration::from_secs(30);
+
+/// Authentication provider configuration, set per server or from the CLI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthConfig {
+    /// OAuth2 client-credentials grant
+    Oauth2 {
+        /// Token endpoint URL
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        #[serde(default)]
+        scopes: Vec<String>,
+        /// Optional audience parameter (used by some identity providers)
+        audience: Option<String>,
+    },
+    /// External command printing a token, like kubectl credential plugins
+    Exec {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+    },
+    /// JWT signed locally with a key file
+    Jwt {
+        /// Path to a PEM-encoded private key (RSA/EC) or a raw HMAC secret
+        key_file: String,
+        /// Signing algorithm, e.g. RS256, ES256 or HS256
+        #[serde(default = "default_jwt_algorithm")]
+        algorithm: String,
+        issuer: Option<String>,
+        subject: Option<String>,
+        audience: Option<String>,
+        /// Token lifetime in seconds
+        #[serde(default = "default_jwt_ttl")]
+        ttl_secs: u64,
+        /// Additional claims to include in the token
+        #[serde(default)]
+        claims: HashMap<String, Value>,
+    },
+}
+
+impl AuthConfig {
+    /// Expand template placeholders (e.g. `{{env.CLIENT_SECRET}}`) in all string settings
+    pub fn render(&self, template: &TemplateEngine) -> Result<Self> {
+        let value = serde_json::to_value(self).context("Failed to serialize auth config")?;
+        let value = template
+            .render_value(value)
+            .context("Failed to expand auth config templates")?;
+        serde_json::from_value(value).context("Invalid auth config")
+    }
+}
+
+fn default_jwt_algorithm() -> String {
+    "RS256".to_string()
+}
+
+fn default_jwt_ttl() -> u64 {
+    3600
+}
+
+/// Source of per-call credentials injected as request metadata
+#[async_trait]
+pub trait AuthProvider: Send + Sync + Debug {
+    /// Metadata entries to attach to the next call
+    async fn metadata(&self) -> Result<Vec<(String, String)>>;
+}
+
+/// Create an auth provider from its configuration
+pub fn create_auth_provider(config: &AuthConfig) -> Arc<dyn AuthProvider> {
+    match config {
+        AuthConfig::Oauth2 { .. } => Arc::new(OAuth2Provider::new(config.clone())),
+        AuthConfig::Exec { .. } => Arc::new(ExecProvider::new(config.clone())),
+        AuthConfig::Jwt { .. } => Arc::new(JwtProvider::new(config.clone())),
+    }
+}
+
+/// A bearer token with an optional expiry
+#[derive(Debug, Clone)]
+struct CachedToken {
+    token: String,
+    issued_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl CachedToken {
+    /// A token obtained just now
+    fn new(token: String, expires_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            token,
+            issued_at: Utc::now(),
+            expires_at,
+        }
+    }
+
+    fn is_fresh(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => {
+                let margin = chrono::Duration::from_std(REFRESH_MARGIN)
+                    .unwrap_or_default()
+                    .min((expires_at - self.issued_at) / 2);
+                Utc::now() + margin < expires_at
+            }
+            None => true,
+        }
+    }
+
+    fn bearer(&self) -> Vec<(String, String)> {
+        vec![(
+            "authorization".to_string(),
+            format!("Bearer {}", self.token),
+        )]
+    }
+}
+
+/// Cache that refreshes the token through the given closure when it expires
+#[derive(Debug, Default)]
+struct TokenCache {
+    token: Mutex<Option<CachedToken>>,
+}
+
+impl TokenCache {
+    async fn get_or_refresh<F, Fut>(&self, refresh: F) -> Result<CachedToken>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<CachedToken>>,
+    {
+        let mut guard = self.token.lock().await;
+        if let Some(token) = guard.as_ref().filter(|t| t.is_fresh()) {
+            return Ok(token.clone());
+        }
+
+        let token = refresh().await?;
+        *guard = Some(token.clone());
+        Ok(token)
+    }
+}
+
+/// OAuth2 client-credentials provider with token caching and refresh
+#[derive(Debug)]
+pub struct OAuth2Provider {
+    config: AuthConfig,
+    http: reqwest::Client,
+    cache: TokenCache,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<i64>,
+}
+
+impl OAuth2Provider {
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            cache: TokenCache::default(),
+        }
+    }
+
+    async fn fetch_token(&self) -> Result<CachedToken> {
+        let AuthConfig::Oauth2 {
+            token_url,
+            client_id,
+            client_secret,
+            scopes,
+            audience,
+        } = &self.config
+        else {
+            bail!("Invalid OAuth2 provider configuration");
+        };
+
+        let mut form = vec![
+            ("grant_type", "client_credentials".to_string()),
+            ("client_id", client_id.clone()),
+            ("client_secret", client_secret.clone()),
+        ];
+        if !scopes.is_empty() {
+            form.push(("scope", scopes.join(" ")));
+        }
+        if let Some(audience) = audience {
+            form.push(("audience", audience.clone()));
+        }
+
+        let response = self
+            .http
+            .post(token_url)
+            .form(&form)
+            .send()
+            .await
+            .with_context(|| format!("Failed to request OAuth2 token from {}", token_url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("OAuth2 token request failed ({}): {}", status, body);
+        }
+
+        let token: TokenResponse = response
+            .json()
+            .await
+            .context("Invalid OAuth2 token response")?;
+
+        Ok(CachedToken::new(
+            token.access_token,
+            token
+                .expires_in
+                .map(|secs| Utc::now() + chrono::Duration::seconds(secs)),
+        ))
+    }
+}
+
+#[async_trait]
+impl AuthProvider for OAuth2Provider {
+    async fn metadata(&self) -> Result<Vec<(String, String)>> {
+        let token = self.cache.get_or_refresh(|| self.fetch_token()).await?;
+        Ok(token.bearer())
+    }
+}
+
+/// Provider running an external command that prints a token.
+///
+/// The command may print either a raw token or a kubectl-style
+/// `ExecCredential` JSON document with `status.token` and
+/// `status.expirationTimestamp`.
+#[derive(Debug)]
+pub struct ExecProvider {
+    config: AuthConfig,
+    cache: TokenCache,
+}
+
+impl ExecProvider {
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            config,
+            cache: TokenCache::default(),
+        }
+    }
+
+    async fn run_command(&self) -> Result<CachedToken> {
+        let AuthConfig::Exec { command, args, env } = &self.config else {
+            bail!("Invalid exec provider configuration");
+        };
+
+        let output = tokio::process::Command::new(command)
+            .args(args)
+            .envs(env)
+            .output()
+            .await
+            .with_context(|| format!("Failed to run auth command: {}", command))?;
+
+        if !output.status.success() {
+            bail!(
+                "Auth command '{}' failed ({}): {}",
+                command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        parse_exec_output(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[async_trait]
+impl AuthProvider for ExecProvider {
+    async fn metadata(&self) -> Result<Vec<(String, String)>> {
+        let token = self.cache.get_or_refresh(|| self.run_command()).await?;
+        Ok(token.bearer())
+    }
+}
+
+fn parse_exec_output(output: &str) -> Result<CachedToken> {
+    let output = output.trim();
+
+    if let Ok(credential) = serde_json::from_str::<Value>(output)
+        && credential.is_object()
+    {
+        let status = &credential["status"];
+        let token = status["token"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Auth command output is missing status.token"))?;
+        let expires_at = status["expirationTimestamp"]
+            .as_str()
+            .map(DateTime::parse_from_rfc3339)
+            .transpose()
+            .context("Invalid status.expirationTimestamp in auth command output")?
+            .map(|t| t.with_timezone(&Utc));
+
+        return Ok(CachedToken::new(token.to_string(), expires_at));
+    }
+
+    if output.is_empty() {
+        bail!("Auth command produced no token");
+    }
+
+    Ok(CachedToken::new(output.to_string(), None))
+}
+
+/// Provider signing short-lived JWTs with a local key
+#[derive(Debug)]
+pub struct JwtProvider {
+    config: AuthConfig,
+    cache: TokenCache,
+}
+
+impl JwtProvider {
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            config,
+            cache: TokenCache::default(),
+        }
+    }
+
+    async fn sign(&self) -> Result<CachedToken> {
+        let AuthConfig::Jwt {
+            key_file,
+            algorithm,
+            issuer,
+            subject,
+            audience,
+            ttl_secs,
+            claims,
+        } = &self.config
+        else {
+            bail!("Invalid JWT provider configuration");
+        };
+
+        let algorithm: Algorithm = algorithm
+            .parse()
+            .map_err(|_| anyhow!("Unsupported JWT algorithm: {}", algorithm))?;
+        let key = tokio::fs::read(key_file)
+            .await
+            .with_context(|| format!("Failed to read JWT key file: {}", key_file))?;
+        let key = match algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                EncodingKey::from_secret(&key)
+            }
+            Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(&key)?,
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(&key)?,
+            _ => EncodingKey::from_rsa_pem(&key)?,
+        };
+
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::seconds(*ttl_secs as i64);
+        let mut payload = serde_json::Map::new();
+        for (k, v) in claims {
+            payload.insert(k.clone(), v.clone());
+        }
+        payload.insert("iat".to_string(), now.timestamp().into());
+        payload.insert("exp".to_string(), expires_at.timestamp().into());
+        for (claim, value) in [("iss", issuer), ("sub", subject), ("aud", audience)] {
+            if let Some(value) = value {
+                payload.insert(claim.to_string(), value.clone().into());
+            }
+        }
+
+        let token = jsonwebtoken::encode(&Header::new(algorithm), &payload, &key)
+            .context("Failed to sign JWT")?;
+
+        Ok(CachedToken {
+            token,
+            issued_at: now,
+            expires_at: Some(expires_at),
+        })
+    }
+}
+
+#[async_trait]
+impl AuthProvider for JwtProvider {
+    async fn metadata(&self) -> Result<Vec<(String, String)>> {
+        let token = self.cache.get_or_refresh(|| self.sign()).await?;
+        Ok(token.bearer())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_config_yaml() {
+        let config: AuthConfig =
+            serde_yaml::from_str("type: exec\ncommand: gcloud\nargs: [auth, print-access-token]\n")
+                .unwrap();
+        assert_eq!(
+            config,
+            AuthConfig::Exec {
+                command: "gcloud".to_string(),
+                args: vec!["auth".to_string(), "print-access-token".to_string()],
+                env: HashMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_auth_config_render() {
+        let config = AuthConfig::Exec {
+            command: "token-helper".to_string(),
+            args: vec!["--user={{var.user}}".to_string()],
+            env: HashMap::new(),
+        };
+        let template = TemplateEngine::from_args(&["user=alice".to_string()], None).unwrap();
+
+        match config.render(&template).unwrap() {
+            AuthConfig::Exec { args, .. } => assert_eq!(args, vec!["--user=alice"]),
+            _ => panic!("Expected exec config"),
+        }
+    }
+
+    #[test]
+    fn test_parse_exec_output() {
+        let token = parse_exec_output("abc123\n").unwrap();
+        assert_eq!(token.token, "abc123");
+        assert!(token.expires_at.is_none());
+
+        let token = parse_exec_output(
+            r#"{"kind":"ExecCredential","status":{"token":"xyz","expirationTimestamp":"2099-01-01T00:00:00Z"}}"#,
+        )
+        .unwrap();
+        assert_eq!(token.token, "xyz");
+        assert!(token.is_fresh());
+
+        assert!(parse_exec_output("").is_err());
+        assert!(parse_exec_output(r#"{"status":{}}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_exec_provider() {
+        let provider = create_auth_provider(&AuthConfig::Exec {
+            command: "echo".to_string(),
+            args: vec!["token-1".to_string()],
+            env: HashMap::new(),
+        });
+
+        let metadata = provider.metadata().await.unwrap();
+        assert_eq!(
+            metadata,
+            vec![("authorization".to_string(), "Bearer token-1".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jwt_provider_hmac() {
+        let key_file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(key_file.path(), b"secret").unwrap();
+
+        let provider = create_auth_provider(&AuthConfig::Jwt {
+            key_file: key_file.path().to_string_lossy().to_string(),
+            algorithm: "HS256".to_string(),
+            issuer: Some("grpc-client".to_string()),
+            subject: None,
+            audience: None,
+            ttl_secs: 20,
+            claims: HashMap::new(),
+        });
+
+        let metadata = provider.metadata().await.unwrap();
+        let token = metadata[0].1.strip_prefix("Bearer ").unwrap();
+        assert_eq!(token.split('.').count(), 3);
+
+        // Cached token is reused while it is fresh
+        assert_eq!(provider.metadata().await.unwrap(), metadata);
+    }
+
+    #[test]
+    fn test_expired_token_is_not_fresh() {
+        let token = CachedToken {
+            token: "t".to_string(),
+            issued_at: Utc::now() - chrono::Duration::minutes(10),
+            expires_at: Some(Utc::now() + chrono::Duration::seconds(5)),
+        };
+        assert!(!token.is_fresh());
+    }
+
+    #[test]
+    fn test_short_lived_token_is_fresh_for_half_its_lifetime() {
+        let expires_at = Some(Utc::now() + chrono::Duration::seconds(10));
+        assert!(CachedToken::new("t".to_string(), expires_at).is_fresh());
+
+        let token = CachedToken {
+            token: "t".to_string(),
+            issued_at: Utc::now() - chrono::Duration::seconds(6),
+            expires_at: Some(Utc::now() + chrono::Duration::seconds(4)),
+        };
+        assert!(!token.is_fresh());
+    }
+}
@@ -0,0 +1,398 @@
+use crate::client::{GrpcClient, PreparedCall};
+use crate::domain::status_code_name;
+use anyhow::{Result, bail};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tonic::Code;
+
+/// Default number of requests when neither a total nor a duration is given
+const DEFAULT_TOTAL_REQUESTS: usize = 200;
+/// Number of buckets in the text latency histogram
+const HISTOGRAM_BUCKETS: usize = 10;
+
+/// Load test settings
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// Total number of requests to send
+    pub total: Option<usize>,
+    /// Run for this long instead of a fixed number of requests
+    pub duration: Option<Duration>,
+    /// Number of concurrent workers
+    pub concurrency: usize,
+    /// Target requests per second across all workers
+    pub rps: Option<f64>,
+    /// Number of connections the workers are spread across
+    pub connections: usize,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            total: None,
+            duration: None,
+            concurrency: 50,
+            rps: None,
+            connections: 1,
+        }
+    }
+}
+
+/// Latency statistics in milliseconds
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyStats {
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// One bucket of the latency histogram
+#[derive(Debug, Clone, Serialize)]
+pub struct HistogramBucket {
+    /// Upper bound of the bucket in milliseconds
+    pub le_ms: f64,
+    pub count: usize,
+}
+
+/// Summary of a benchmark run
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub method: String,
+    pub total_requests: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub duration_ms: f64,
+    pub requests_per_second: f64,
+    pub latency: LatencyStats,
+    pub histogram: Vec<HistogramBucket>,
+    /// Count of calls per gRPC status code
+    pub status_codes: BTreeMap<String, usize>,
+    /// Up to one sample error message per status code
+    pub errors: BTreeMap<String, String>,
+}
+
+#[derive(Default)]
+struct WorkerResult {
+    latencies: Vec<Duration>,
+    status_codes: BTreeMap<String, usize>,
+    errors: BTreeMap<String, String>,
+}
+
+/// Run a load test against `method`, reusing the client's descriptor pool and
+/// opening `connections` channels up front.
+pub async fn run_bench(
+    client: &GrpcClient,
+    method: &str,
+    data: Value,
+    config: &BenchConfig,
+) -> Result<BenchReport> {
+    if config.concurrency == 0 {
+        bail!("Concurrency must be at least 1");
+    }
+    if config.connections == 0 {
+        bail!("Connection count must be at least 1");
+    }
+    if matches!(config.rps, Some(rps) if rps <= 0.0) {
+        bail!("Target RPS must be greater than 0");
+    }
+
+    let call = Arc::new(client.prepare_call(method, data).await?);
+
+    let mut channels = vec![client.get_or_create_channel().await?];
+    for _ in 1..config.connections {
+        channels.push(client.create_new_channel().await?);
+    }
+
+    let total = match (config.total, config.duration) {
+        (None, None) => Some(DEFAULT_TOTAL_REQUESTS),
+        (total, _) => total,
+    };
+    let deadline = config.duration;
+    let interval = config.rps.map(|rps| Duration::from_secs_f64(1.0 / rps));
+    let next = Arc::new(AtomicUsize::new(0));
+    let client = Arc::new(client.clone());
+    let start = Instant::now();
+
+    let workers: Vec<_> = (0..config.concurrency)
+        .map(|worker| {
+            let channel = channels[worker % channels.len()].clone();
+            let client = client.clone();
+            let call = call.clone();
+            let next = next.clone();
+            tokio::spawn(async move {
+                run_worker(
+                    &client, &call, channel, &next, total, deadline, interval, start,
+                )
+                .await
+            })
+        })
+        .collect();
+
+    let mut result = WorkerResult::default();
+    for worker in workers {
+        let worker = worker.await?;
+        result.latencies.extend(worker.latencies);
+        for (code, count) in worker.status_codes {
+            *result.status_codes.entry(code).or_default() += count;
+        }
+        for (code, message) in worker.errors {
+            result.errors.entry(code).or_insert(message);
+        }
+    }
+    let elapsed = start.elapsed();
+
+    Ok(build_report(method, result, elapsed))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_worker(
+    client: &GrpcClient,
+    call: &PreparedCall,
+    channel: tonic::transport::Channel,
+    next: &AtomicUsize,
+    total: Option<usize>,
+    deadline: Option<Duration>,
+    interval: Option<Duration>,
+    start: Instant,
+) -> WorkerResult {
+    let mut result = WorkerResult::default();
+
+    loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        if total.is_some_and(|total| index >= total) {
+            break;
+        }
+
+        // Spread requests evenly over time when a target rate is set
+        if let Some(interval) = interval {
+            tokio::time::sleep_until((start + interval * index as u32).into()).await;
+        }
+        if deadline.is_some_and(|deadline| start.elapsed() >= deadline) {
+            break;
+        }
+
+        let call_start = Instant::now();
+        let outcome = client.invoke_prepared(channel.clone(), call).await;
+        result.latencies.push(call_start.elapsed());
+
+        let code = match outcome {
+            Ok(_) => Code::Ok,
+            Err(status) => {
+                let code = status.code();
+                result
+                    .errors
+                    .entry(status_code_name(code).to_string())
+                    .or_insert_with(|| status.message().to_string());
+                code
+            }
+        };
+        *result
+            .status_codes
+            .entry(status_code_name(code).to_string())
+            .or_default() += 1;
+    }
+
+    result
+}
+
+fn build_report(method: &str, result: WorkerResult, elapsed: Duration) -> BenchReport {
+    let mut latencies = result.latencies;
+    latencies.sort();
+
+    let total_requests = latencies.len();
+    let succeeded = result.status_codes.get("OK").copied().unwrap_or(0);
+    let duration_ms = as_ms(elapsed);
+    let requests_per_second = if elapsed.is_zero() {
+        0.0
+    } else {
+        total_requests as f64 / elapsed.as_secs_f64()
+    };
+
+    BenchReport {
+        method: method.to_string(),
+        total_requests,
+        succeeded,
+        failed: total_requests - succeeded,
+        duration_ms,
+        requests_per_second,
+        latency: latency_stats(&latencies),
+        histogram: histogram(&latencies),
+        status_codes: result.status_codes,
+        errors: result.errors,
+    }
+}
+
+/// Compute latency statistics from sorted latencies
+fn latency_stats(sorted: &[Duration]) -> LatencyStats {
+    if sorted.is_empty() {
+        return LatencyStats::default();
+    }
+
+    let sum: Duration = sorted.iter().sum();
+    LatencyStats {
+        min_ms: as_ms(sorted[0]),
+        mean_ms: as_ms(sum) / sorted.len() as f64,
+        p50_ms: as_ms(percentile(sorted, 50.0)),
+        p90_ms: as_ms(percentile(sorted, 90.0)),
+        p99_ms: as_ms(percentile(sorted, 99.0)),
+        max_ms: as_ms(sorted[sorted.len() - 1]),
+    }
+}
+
+/// Nearest-rank percentile of sorted latencies
+fn percentile(sorted: &[Duration], pct: f64) -> Duration {
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Split the latency range into equal-width buckets
+fn histogram(sorted: &[Duration]) -> Vec<HistogramBucket> {
+    let (Some(min), Some(max)) = (sorted.first(), sorted.last()) else {
+        return Vec::new();
+    };
+
+    let (min, max) = (as_ms(*min), as_ms(*max));
+    let width = (max - min) / HISTOGRAM_BUCKETS as f64;
+    let mut buckets: Vec<HistogramBucket> = (1..=HISTOGRAM_BUCKETS)
+        .map(|i| HistogramBucket {
+            le_ms: min + width * i as f64,
+            count: 0,
+        })
+        .collect();
+
+    for latency in sorted {
+        let index = if width == 0.0 {
+            HISTOGRAM_BUCKETS - 1
+        } else {
+            (((as_ms(*latency) - min) / width) as usize).min(HISTOGRAM_BUCKETS - 1)
+        };
+        buckets[index].count += 1;
+    }
+
+    buckets
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl BenchReport {
+    /// Render the report as human-readable text
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Summary:");
+        let _ = writeln!(out, "  Method:        {}", self.method);
+        let _ = writeln!(out, "  Requests:      {}", self.total_requests);
+        let _ = writeln!(out, "  Succeeded:     {}", self.succeeded);
+        let _ = writeln!(out, "  Failed:        {}", self.failed);
+        let _ = writeln!(out, "  Total time:    {:.2} ms", self.duration_ms);
+        let _ = writeln!(out, "  Requests/sec:  {:.2}", self.requests_per_second);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Latency:");
+        let _ = writeln!(out, "  Min:   {:.2} ms", self.latency.min_ms);
+        let _ = writeln!(out, "  Mean:  {:.2} ms", self.latency.mean_ms);
+        let _ = writeln!(out, "  p50:   {:.2} ms", self.latency.p50_ms);
+        let _ = writeln!(out, "  p90:   {:.2} ms", self.latency.p90_ms);
+        let _ = writeln!(out, "  p99:   {:.2} ms", self.latency.p99_ms);
+        let _ = writeln!(out, "  Max:   {:.2} ms", self.latency.max_ms);
+
+        if !self.histogram.is_empty() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "Histogram:");
+            let peak = self.histogram.iter().map(|b| b.count).max().unwrap_or(0);
+            for bucket in &self.histogram {
+                let bar_len = (bucket.count * 40).checked_div(peak).unwrap_or(0);
+                let _ = writeln!(
+                    out,
+                    "  {:>10.2} ms [{:>6}] |{}",
+                    bucket.le_ms,
+                    bucket.count,
+                    "■".repeat(bar_len)
+                );
+            }
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Status codes:");
+        for (code, count) in &self.status_codes {
+            let _ = writeln!(out, "  [{}] {} responses", code, count);
+        }
+
+        if !self.errors.is_empty() {
+            let _ = writeln!(out);
+            let _ = writeln!(out, "Errors:");
+            for (code, message) in &self.errors {
+                let _ = writeln!(out, "  [{}] {}", code, message);
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|v| Duration::from_millis(*v)).collect()
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let latencies = ms(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        let stats = latency_stats(&latencies);
+
+        assert_eq!(stats.min_ms, 1.0);
+        assert_eq!(stats.p50_ms, 5.0);
+        assert_eq!(stats.p90_ms, 9.0);
+        assert_eq!(stats.p99_ms, 10.0);
+        assert_eq!(stats.max_ms, 10.0);
+        assert_eq!(stats.mean_ms, 5.5);
+    }
+
+    #[test]
+    fn test_histogram_counts_all_samples() {
+        let latencies = ms(&[1, 1, 2, 5, 10]);
+        let buckets = histogram(&latencies);
+
+        assert_eq!(buckets.len(), HISTOGRAM_BUCKETS);
+        assert_eq!(buckets.iter().map(|b| b.count).sum::<usize>(), 5);
+        assert_eq!(buckets.last().unwrap().count, 1);
+
+        // Identical latencies land in a single bucket
+        let buckets = histogram(&ms(&[3, 3, 3]));
+        assert_eq!(buckets.iter().map(|b| b.count).sum::<usize>(), 3);
+        assert!(histogram(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_build_report() {
+        let mut status_codes = BTreeMap::new();
+        status_codes.insert("OK".to_string(), 3);
+        status_codes.insert("UNAVAILABLE".to_string(), 1);
+        let result = WorkerResult {
+            latencies: ms(&[4, 1, 3, 2]),
+            status_codes,
+            errors: BTreeMap::from([("UNAVAILABLE".to_string(), "down".to_string())]),
+        };
+
+        let report = build_report(
+            "example.UserService/CreateUser",
+            result,
+            Duration::from_secs(2),
+        );
+        assert_eq!(report.total_requests, 4);
+        assert_eq!(report.succeeded, 3);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.requests_per_second, 2.0);
+        assert!(report.to_text().contains("[UNAVAILABLE] 1 responses"));
+    }
+}
@@ -1,4 +1,9 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use crate::auth::AuthConfig;
+use crate::redact::RedactionConfig;
+use crate::render::{BytesEncoding, ColorChoice};
+use crate::retry::RetryPolicy;
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
+use std::time::Duration;
 
 /// A modern gRPC command-line client
 #[derive(Parser, Clone)]
@@ -9,67 +14,228 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
 
-    /// Skip TLS (use plain HTTP/2)
-    #[arg(long)]
-    pub plaintext: bool,
+    /// Skip TLS (use plain HTTP/2); `--plaintext=false` forces TLS over a context's setting
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub plaintext: Option<bool>,
 
     /// Path to CA certificate file for TLS verification
     #[arg(long)]
     pub ca: Option<String>,
 
-    /// Additional headers in 'name: value' format
+    /// Additional headers in 'name: value' format ('-bin' keys take base64 values)
     #[arg(short = 'H', long)]
     pub header: Vec<String>,
 
+    /// Headers sent only on reflection requests, in 'name: value' format
+    #[arg(long)]
+    pub reflect_header: Vec<String>,
+
     /// Output format
     #[arg(long, default_value = "json")]
     pub format: FormatType,
 
+    /// Field paths shown as columns in table and csv output (e.g. 'id,name,address.city')
+    #[arg(long, value_delimiter = ',')]
+    pub columns: Vec<String>,
+
+    /// When to color text output
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// How bytes fields are shown in text output
+    #[arg(long, value_enum, default_value_t = BytesEncoding::Base64)]
+    pub bytes: BytesEncoding,
+
     /// Verbose output
     #[arg(short, long)]
     pub verbose: bool,
+
+    /// Template variables in 'key=value' format, referenced as {{var.key}}
+    #[arg(long = "var")]
+    pub vars: Vec<String>,
+
+    /// Path to a YAML/JSON file with template variables
+    #[arg(long)]
+    pub vars_file: Option<String>,
+
+    /// Named server context from the config file to use for endpoint, TLS and headers
+    #[arg(long, env = "GRPC_CLIENT_CONTEXT")]
+    pub context: Option<String>,
+
+    /// Path to the YAML config file with server contexts
+    /// (defaults to ~/.config/grpc-client/config.yml)
+    #[arg(long = "config", env = "GRPC_CLIENT_CONFIG")]
+    pub config_path: Option<String>,
+
+    /// Command printing an auth token (or kubectl-style ExecCredential JSON),
+    /// run before calls to set the authorization header. Arguments are split
+    /// like a shell does, so quote ones with spaces
+    #[arg(long)]
+    pub auth_exec: Option<String>,
+
+    /// Auth provider resolved from the selected context
+    #[arg(skip)]
+    pub auth: Option<AuthConfig>,
+
+    /// Total attempts for unary calls failing with a retryable status code
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_attempts: Option<u32>,
+
+    /// Status codes to retry on, comma separated (default: UNAVAILABLE,RESOURCE_EXHAUSTED)
+    #[arg(long, value_delimiter = ',')]
+    pub retry_on: Vec<String>,
+
+    /// Backoff before the first retry, doubled for each further attempt (e.g. 100ms)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub retry_backoff: Option<Duration>,
+
+    /// Retry policy resolved from the selected context
+    #[arg(skip)]
+    pub retry: Option<RetryPolicy>,
+
+    /// Mask values matched by this JSONPath in output, in addition to fields
+    /// marked with `debug_redact` (repeatable)
+    #[arg(long)]
+    pub redact: Vec<String>,
+
+    /// Show sensitive values instead of masking them
+    #[arg(long)]
+    pub no_redact: bool,
+
+    /// Redaction settings resolved from the selected context
+    #[arg(skip)]
+    pub redaction: Option<RedactionConfig>,
 }
 
 #[derive(Subcommand, Clone)]
 pub enum Command {
     /// List services or methods
     List {
-        /// Server endpoint (host:port)
-        endpoint: String,
+        /// Server endpoint (host:port), omitted when --context is given
+        endpoint: Option<String>,
         /// Optional service name to list methods for
         service: Option<String>,
     },
     /// Describe a service, method, or message
     Describe {
-        /// Server endpoint (host:port)
+        /// Server endpoint (host:port), omitted when --context is given
         endpoint: String,
         /// Symbol to describe
-        symbol: String,
+        symbol: Option<String>,
     },
     /// Invoke a gRPC method
+    #[command(group = ArgGroup::new("repeat_or_watch").args(["repeat", "watch"]))]
     Call {
-        /// Server endpoint (host:port)
+        /// Server endpoint (host:port), omitted when --context is given
         endpoint: String,
         /// Method to call (service.method or service/method)
-        method: String,
+        method: Option<String>,
         /// Request data (JSON string or @filename or @- for stdin)
         #[arg(short, long)]
         data: Option<String>,
         /// Emit default values in JSON output
         #[arg(long)]
         emit_defaults: bool,
+        /// Invoke the method N times, waiting --interval between calls
+        #[arg(long)]
+        repeat: Option<usize>,
+        /// Keep invoking the method every --interval until interrupted
+        #[arg(long)]
+        watch: bool,
+        /// Delay between repeated calls (e.g. 500ms, 2s, 1m)
+        #[arg(long, default_value = "2s", value_parser = humantime::parse_duration)]
+        interval: Duration,
+        /// With --repeat or --watch, print only the changes from the previous response
+        #[arg(long, requires = "repeat_or_watch")]
+        diff: bool,
+        /// Project each response with a JSONPath (`$.user.name`) or jq-style
+        /// (`.users[] | {id, name}`) query before printing
+        #[arg(short, long)]
+        query: Option<String>,
+    },
+    /// Check server health with the standard grpc.health.v1 service
+    Health {
+        /// Server endpoint (host:port), omitted when --context is given
+        endpoint: Option<String>,
+        /// Service to check; the whole server when omitted
+        service: Option<String>,
+        /// Stream status changes with Health/Watch until interrupted
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Load test a gRPC method
+    Bench {
+        /// Server endpoint (host:port), omitted when --context is given
+        endpoint: String,
+        /// Method to call (service.method or service/method)
+        method: Option<String>,
+        /// Request data (JSON string or @filename or @- for stdin)
+        #[arg(short, long)]
+        data: Option<String>,
+        /// Total number of requests to send (defaults to 200 without --duration)
+        #[arg(short = 'n', long)]
+        total: Option<usize>,
+        /// Run for a fixed duration instead (e.g. 10s, 1m)
+        #[arg(short = 'z', long, value_parser = humantime::parse_duration)]
+        duration: Option<Duration>,
+        /// Number of concurrent workers
+        #[arg(short, long, default_value = "50")]
+        concurrency: usize,
+        /// Target requests per second across all workers
+        #[arg(long)]
+        rps: Option<f64>,
+        /// Number of connections to spread workers across
+        #[arg(long, default_value = "1")]
+        connections: usize,
+    },
+    /// Run calls and assertions from YAML test files
+    Test {
+        /// Test files to run
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Write a JUnit XML report to this path
+        #[arg(long)]
+        junit: Option<String>,
+    },
+    /// Run a multi-step workflow file
+    Run {
+        /// Workflow file to run
+        file: String,
     },
     /// Start web server for UI interface
     Server {
         /// Port to run web server on
         #[arg(short, long, default_value = "4000")]
         port: u16,
+        /// Address to listen on (default: `web.bind` from the config, or 0.0.0.0)
+        #[arg(long)]
+        bind: Option<String>,
         /// Path to YAML configuration file
         #[arg(short, long, default_value = "fixtures/app.yml")]
         config: String,
         /// Path to UI assets directory
         #[arg(long, default_value = "ui/dist")]
         ui_path: String,
+        /// File to record call history in (default: ~/.config/grpc-client/history.jsonl)
+        #[arg(long)]
+        history: Option<String>,
+        /// Keep call history in memory only
+        #[arg(long, conflicts_with = "history")]
+        no_history: bool,
+        /// Directory of saved request collections (default: ~/.config/grpc-client/collections)
+        #[arg(long)]
+        collections: Option<String>,
+    },
+    /// Run a saved request from a collection
+    RunSaved {
+        /// Saved request as collection/name
+        saved: String,
+        /// Directory of saved request collections (default: ~/.config/grpc-client/collections)
+        #[arg(long)]
+        collections: Option<String>,
+        /// JSONPath or jq-style query applied to each response message
+        #[arg(short, long)]
+        query: Option<String>,
     },
 }
 
@@ -77,4 +243,6 @@ pub enum Command {
 pub enum FormatType {
     Json,
     Text,
+    Table,
+    Csv,
 }
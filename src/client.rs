@@ -1,15 +1,23 @@
 #![allow(clippy::too_many_arguments)]
 
 use crate::{
+    auth::{AuthConfig, AuthProvider, create_auth_provider},
     cli::{Cli, Command, FormatType},
     codec::BytesCodec,
     connection::create_channel,
     domain::{Endpoint, OutputFormat, ServiceName},
+    format::FormatterRegistry,
+    health::{self, HealthCheckRequest, HealthCheckResponse, ServingStatus},
+    live::LiveCall,
+    metadata::{insert_headers, metadata_to_pairs},
+    redact::Redactor,
     reflection::{
-        MessageDescriptor, MethodDescriptor, ServiceDescriptor, StreamingType, Symbol,
-        create_reflection_client,
+        MethodDescriptor, SchemaSource, ServiceDescriptor, StreamingType, Symbol,
+        create_reflection_client_with_metadata,
     },
+    retry::{RetryCode, RetryPolicy},
     server::config::GrpcServerConfig,
+    template::TemplateEngine,
 };
 use anyhow::{Context, Result, anyhow, bail};
 use bytes::Bytes;
@@ -18,35 +26,65 @@ use futures::stream::{self, TryStreamExt};
 use http::uri::PathAndQuery;
 use prost::Message;
 use prost_reflect::{DescriptorPool, DynamicMessage};
-use prost_types::FileDescriptorSet;
 use serde_json::Value;
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tonic::{
-    Code, Request, Response, Status, Streaming, client::Grpc, metadata::MetadataKey,
-    transport::Channel,
+    Code, Request, Response, Status, Streaming, client::Grpc, codec::ProstCodec, transport::Channel,
 };
 
 #[derive(Debug, Clone)]
 pub struct GrpcClient {
     pub endpoint: Endpoint,
     pub headers: Vec<(String, String)>,
+    /// Headers sent only on reflection requests
+    pub reflect_headers: Vec<(String, String)>,
     pub format: OutputFormat,
     pub verbose: bool,
     pub ca_cert_path: Option<String>,
     pub plaintext: bool,
     pub cache: PerformanceCache,
+    pub auth: Option<Arc<dyn AuthProvider>>,
+    /// Retry policy for unary calls
+    pub retry: Option<RetryPolicy>,
+    /// Masks sensitive values in responses and verbose logs
+    pub redactor: Option<Arc<Redactor>>,
+    /// Output formats available to `format`
+    pub formatters: FormatterRegistry,
+    /// Counts streamed response messages as they arrive
+    pub received: Option<Arc<AtomicU64>>,
 }
 
 impl GrpcClient {
     /// Create a new GrpcClient from server configuration
     pub fn from_config(config: &GrpcServerConfig) -> Result<Self> {
-        let endpoint = Endpoint::parse(&config.endpoint)?;
-        let headers = config
+        Self::from_config_with_template(config, &TemplateEngine::new())
+    }
+
+    /// Create a client from server configuration, expanding templates in the
+    /// endpoint, headers and auth settings with `template`
+    pub fn from_config_with_template(
+        config: &GrpcServerConfig,
+        template: &TemplateEngine,
+    ) -> Result<Self> {
+        let endpoint = Endpoint::parse(&template.render(&config.endpoint)?)?;
+        let headers: Vec<(String, String)> = config
             .headers
             .iter()
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect();
+        let headers = template
+            .render_headers(&headers)
+            .context("Failed to expand header templates")?;
+        let reflect_headers: Vec<(String, String)> = config
+            .reflect_headers
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let reflect_headers = template
+            .render_headers(&reflect_headers)
+            .context("Failed to expand reflection header templates")?;
         let ca_cert_path = config.ca_cert.clone();
         let plaintext = config.plaintext;
         let verbose = false;
@@ -59,36 +97,61 @@ impl GrpcClient {
         Ok(Self {
             endpoint,
             headers,
+            reflect_headers,
             format,
             verbose,
             ca_cert_path,
             plaintext,
             cache: PerformanceCache::new(),
+            auth: config
+                .auth
+                .as_ref()
+                .map(|auth| auth.render(template))
+                .transpose()?
+                .as_ref()
+                .map(create_auth_provider),
+            retry: config.retry.clone(),
+            redactor: Redactor::from_config(&config.redaction.clone().unwrap_or_default())?
+                .map(Arc::new),
+            formatters: FormatterRegistry::default(),
+            received: None,
         })
     }
 
     pub fn from_cli(cli: &Cli) -> Result<Self> {
         let endpoint = match &cli.command {
-            Command::List { endpoint, .. } | Command::Describe { endpoint, .. } => {
-                Endpoint::parse(endpoint)?
+            Command::List { endpoint, .. } | Command::Health { endpoint, .. } => endpoint
+                .as_deref()
+                .context("Missing server endpoint. Pass it or use --context")?,
+            Command::Describe { endpoint, .. }
+            | Command::Call { endpoint, .. }
+            | Command::Bench { endpoint, .. } => endpoint,
+            Command::Test { .. }
+            | Command::Run { .. }
+            | Command::RunSaved { .. }
+            | Command::Server { .. } => {
+                bail!("This command does not take a server endpoint")
             }
-            Command::Call { endpoint, .. } => Endpoint::parse(endpoint)?,
-            Command::Server { .. } => todo!(),
         };
 
-        let headers = cli
-            .header
-            .iter()
-            .map(|h| {
-                let parts: Vec<&str> = h.splitn(2, ':').collect();
-                if parts.len() == 2 {
-                    Ok((parts[0].trim().to_string(), parts[1].trim().to_string()))
-                } else {
-                    anyhow::bail!("Invalid header format: {}. Expected 'name: value'", h)
-                }
-            })
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to parse headers")?;
+        Self::from_cli_with_endpoint(cli, endpoint)
+    }
+
+    /// Create a client for `endpoint` using the TLS, header, auth and retry
+    /// settings from the command line
+    pub fn from_cli_with_endpoint(cli: &Cli, endpoint: &str) -> Result<Self> {
+        let template = TemplateEngine::from_args(&cli.vars, cli.vars_file.as_deref())?;
+        let endpoint = Endpoint::parse(&template.render(endpoint)?)?;
+
+        let headers = parse_header_args(&cli.header).context("Failed to parse headers")?;
+        let headers = template
+            .render_headers(&headers)
+            .context("Failed to expand header templates")?;
+        let reflect_headers =
+            parse_header_args(&cli.reflect_header).context("Failed to parse reflection headers")?;
+        let reflect_headers = template
+            .render_headers(&reflect_headers)
+            .context("Failed to expand reflection header templates")?;
 
         let format = match cli.format {
             FormatType::Json => OutputFormat::Json {
@@ -96,24 +159,76 @@ impl GrpcClient {
                 emit_defaults: false,
             },
             FormatType::Text => OutputFormat::Text { compact: false },
+            FormatType::Table => OutputFormat::Table {
+                columns: cli.columns.clone(),
+            },
+            FormatType::Csv => OutputFormat::Csv {
+                columns: cli.columns.clone(),
+            },
+        };
+
+        let auth = match &cli.auth_exec {
+            Some(command) => {
+                let mut parts = shell_words::split(command)
+                    .context("Invalid quoting in --auth-exec command")?
+                    .into_iter();
+                let program = parts.next().context("Empty --auth-exec command")?;
+                Some(AuthConfig::Exec {
+                    command: program,
+                    args: parts.collect(),
+                    env: Default::default(),
+                })
+            }
+            None => cli.auth.clone(),
         };
 
         let client = Self {
             endpoint,
             headers,
+            reflect_headers,
             format,
             verbose: cli.verbose,
             ca_cert_path: cli.ca.clone(),
-            plaintext: cli.plaintext,
+            plaintext: cli.plaintext.unwrap_or(false),
             cache: PerformanceCache::new(),
+            auth: auth
+                .map(|auth| auth.render(&template))
+                .transpose()?
+                .as_ref()
+                .map(create_auth_provider),
+            retry: retry_policy_from_cli(cli)?,
+            redactor: redactor_from_cli(cli)?,
+            formatters: FormatterRegistry::default(),
+            received: None,
         };
 
         Ok(client)
     }
 
+    /// Create a copy of this client with additional headers. Headers with the
+    /// same name (case-insensitive) replace the existing ones.
+    pub fn with_headers(&self, headers: Vec<(String, String)>) -> Self {
+        let mut client = self.clone();
+        for (key, value) in headers {
+            client
+                .headers
+                .retain(|(k, _)| !k.eq_ignore_ascii_case(&key));
+            client.headers.push((key, value));
+        }
+        client
+    }
+
+    /// Create a copy of this client that counts streamed response messages
+    /// in `counter`
+    pub fn with_counter(&self, counter: Arc<AtomicU64>) -> Self {
+        let mut client = self.clone();
+        client.received = Some(counter);
+        client
+    }
+
     pub async fn handle_service_list(&self) -> Result<Vec<ServiceName>> {
         let channel = self.get_or_create_channel().await?;
-        let mut client = create_reflection_client(channel);
+        let mut client = self.reflection_client(channel).await?;
         let services = client
             .list_services()
             .await
@@ -124,7 +239,7 @@ impl GrpcClient {
 
     pub async fn handle_method_list(&self, service: &str) -> Result<Vec<MethodDescriptor>> {
         let channel = self.get_or_create_channel().await?;
-        let mut client = create_reflection_client(channel);
+        let mut client = self.reflection_client(channel).await?;
         let service = ServiceName::new(service.to_string());
         let methods = client
             .list_methods(&service)
@@ -136,7 +251,7 @@ impl GrpcClient {
     pub async fn handle_describe(&self, symbol: &str) -> Result<Symbol> {
         let channel = self.get_or_create_channel().await?;
 
-        let mut client = create_reflection_client(channel);
+        let mut client = self.reflection_client(channel).await?;
 
         let symbol = client
             .resolve_symbol(symbol)
@@ -146,7 +261,32 @@ impl GrpcClient {
         Ok(symbol)
     }
 
+    /// Copy of `data` with sensitive values masked, for verbose logs
+    fn loggable(
+        &self,
+        data: &Value,
+        descriptor: Option<&prost_reflect::MessageDescriptor>,
+    ) -> Value {
+        match &self.redactor {
+            Some(redactor) => redactor.redacted(data, descriptor),
+            None => data.clone(),
+        }
+    }
+
     pub async fn handle_call(&self, method: &str, data: Value) -> Result<Vec<Value>> {
+        Ok(self.execute_call(method, data).await?.responses)
+    }
+
+    /// Invoke a method and return its responses together with call details
+    /// such as the number of attempts made.
+    pub async fn execute_call(&self, method: &str, data: Value) -> Result<CallOutcome> {
+        let resolved = self.resolve_call(method).await?;
+        self.execute_resolved(&resolved, data).await
+    }
+
+    /// Look up a method through reflection, so it can be called with
+    /// [`GrpcClient::execute_resolved`] without repeating the lookup
+    pub async fn resolve_call(&self, method: &str) -> Result<ResolvedMethod> {
         let (service_name, method_name) = parse_method(method)?;
 
         if self.verbose {
@@ -156,7 +296,7 @@ impl GrpcClient {
 
         let channel = self.get_or_create_channel().await?;
 
-        let mut client = create_reflection_client(channel.clone());
+        let mut client = self.reflection_client(channel.clone()).await?;
         let service = ServiceName::new(service_name.to_string());
         let service = client
             .get_service(&service)
@@ -174,7 +314,34 @@ impl GrpcClient {
                     method_name,
                     service_name
                 )
-            })?;
+            })?
+            .clone();
+
+        // Descriptors for masking sensitive fields and rendering responses
+        let pool = self
+            .get_or_create_descriptor_pool(channel, &method.input_type, &method.output_type)
+            .await?;
+        Ok(ResolvedMethod {
+            input_descriptor: pool.get_message_by_name(&method.input_type),
+            output_descriptor: pool.get_message_by_name(&method.output_type),
+            service,
+            method,
+        })
+    }
+
+    /// Invoke a method looked up with [`GrpcClient::resolve_call`]
+    pub async fn execute_resolved(
+        &self,
+        resolved: &ResolvedMethod,
+        data: Value,
+    ) -> Result<CallOutcome> {
+        let ResolvedMethod {
+            service,
+            method,
+            input_descriptor,
+            output_descriptor,
+        } = resolved;
+        let channel = self.get_or_create_channel().await?;
 
         if self.verbose {
             println!("Method info:");
@@ -189,248 +356,211 @@ impl GrpcClient {
                     StreamingType::BiDirectional => "BiDirectional",
                 }
             );
-            println!("  Request data: {:?}", data);
+            println!(
+                "  Request data: {:?}",
+                self.loggable(&data, input_descriptor.as_ref())
+            );
         }
 
         // Route to appropriate handler based on streaming type
-        match method.streaming_type {
+        let mut outcome = match method.streaming_type {
             StreamingType::Unary => {
-                let ret = self.handle_unary(channel, &service, method, data).await?;
-                Ok(vec![ret])
+                let (ret, attempts) = self.handle_unary(channel, service, method, data).await?;
+                CallOutcome {
+                    responses: vec![ret],
+                    attempts,
+                    streaming_type: StreamingType::Unary,
+                    input_descriptor: None,
+                    output_descriptor: None,
+                }
             }
             StreamingType::ServerStream => {
                 let ret = self
-                    .handle_server_streaming(channel, &service, method, data)
+                    .handle_server_streaming(channel, service, method, data)
                     .await?;
-                Ok(ret)
+                CallOutcome::single_attempt(ret, StreamingType::ServerStream)
             }
             StreamingType::ClientStream => {
                 let ret = self
-                    .handle_client_streaming(channel, &service, method, data)
+                    .handle_client_streaming(channel, service, method, data)
                     .await?;
-                Ok(ret)
+                CallOutcome::single_attempt(ret, StreamingType::ClientStream)
             }
             StreamingType::BiDirectional => {
                 let ret = self
-                    .handle_bidi_streaming(channel, &service, method, data)
+                    .handle_bidi_streaming(channel, service, method, data)
                     .await?;
-                Ok(ret)
+                CallOutcome::single_attempt(ret, StreamingType::BiDirectional)
             }
-        }
-    }
+        };
 
-    pub async fn format_service_description(&self, service: &ServiceDescriptor) -> Result<()> {
-        match &self.format {
-            OutputFormat::Json { pretty, .. } => {
-                let methods_json: Vec<Value> = service
-                    .methods
-                    .iter()
-                    .map(|method| {
-                        serde_json::json!({
-                            "name": method.name.as_str(),
-                            "input_type": method.input_type,
-                            "output_type": method.output_type,
-                            "client_streaming": method.client_streaming,
-                            "server_streaming": method.server_streaming,
-                            "description": method.description
-                        })
-                    })
-                    .collect();
-
-                let service_json = serde_json::json!({
-                    "name": service.name.as_str(),
-                    "description": service.description,
-                    "methods": methods_json
-                });
-
-                let output = if *pretty {
-                    serde_json::to_string_pretty(&service_json)?
-                } else {
-                    serde_json::to_string(&service_json)?
-                };
-                println!("{}", output);
+        if let Some(redactor) = &self.redactor {
+            for response in &mut outcome.responses {
+                redactor.redact(response, output_descriptor.as_ref());
             }
-            OutputFormat::Text { .. } => {
-                println!("service {} {{", service.name.as_str());
+        }
+        outcome.input_descriptor = input_descriptor.clone();
+        outcome.output_descriptor = output_descriptor.clone();
+        Ok(outcome)
+    }
 
-                if let Some(desc) = &service.description {
-                    println!("  // {}", desc);
-                }
+    /// Descriptor of a method's request message
+    pub async fn input_descriptor(&self, method: &str) -> Result<prost_reflect::MessageDescriptor> {
+        let (_, _, input_descriptor, _) = self.resolve_method(method).await?;
+        Ok(input_descriptor)
+    }
 
-                for method in &service.methods {
-                    let streaming_prefix = match (method.client_streaming, method.server_streaming)
-                    {
-                        (true, true) => "stream ",
-                        (true, false) => "stream ",
-                        (false, true) => "",
-                        (false, false) => "",
-                    };
-
-                    let streaming_suffix = match (method.client_streaming, method.server_streaming)
-                    {
-                        (true, true) => " returns (stream response)",
-                        (true, false) => " returns (response)",
-                        (false, true) => " returns (stream response)",
-                        (false, false) => " returns (response)",
-                    };
-
-                    println!(
-                        "  rpc {}({}{}) {};",
-                        method.name.as_str(),
-                        streaming_prefix,
-                        method.input_type,
-                        streaming_suffix.replace("response", &method.output_type)
-                    );
-
-                    if let Some(desc) = &method.description {
-                        println!("    // {}", desc);
-                    }
-                }
+    /// Resolve a method and encode its request messages once, so the call can be
+    /// repeated cheaply with [`GrpcClient::invoke_prepared`].
+    pub async fn prepare_call(&self, method: &str, data: Value) -> Result<PreparedCall> {
+        let (path, method, input_descriptor, output_descriptor) =
+            self.resolve_method(method).await?;
 
-                println!("}}");
-            }
-        }
+        let items = match (&method.streaming_type, data) {
+            (StreamingType::ClientStream | StreamingType::BiDirectional, Value::Array(arr)) => arr,
+            (_, data) => vec![data],
+        };
+        let requests = items
+            .into_iter()
+            .map(|item| {
+                parse_request_message(item, input_descriptor.clone())
+                    .map(|msg| Bytes::from(msg.encode_to_vec()))
+            })
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(())
+        Ok(PreparedCall {
+            path,
+            method,
+            requests,
+            output_descriptor,
+        })
     }
 
-    pub async fn format_method_description(&self, method: &MethodDescriptor) -> Result<()> {
-        match &self.format {
-            OutputFormat::Json { pretty, .. } => {
-                let streaming_type_str = match method.streaming_type {
-                    StreamingType::Unary => "unary",
-                    StreamingType::ServerStream => "server_streaming",
-                    StreamingType::ClientStream => "client_streaming",
-                    StreamingType::BiDirectional => "bidirectional",
-                };
+    /// Start a call that exchanges messages while it runs: requests are sent
+    /// as they are passed to [`LiveCall::send`] and each response is delivered
+    /// as soon as it is decoded.
+    pub async fn start_call(&self, method: &str) -> Result<LiveCall> {
+        let (path, method, input_descriptor, output_descriptor) =
+            self.resolve_method(method).await?;
+        let channel = self.get_or_create_channel().await?;
+        Ok(LiveCall::start(
+            self.clone(),
+            channel,
+            path,
+            method.streaming_type,
+            input_descriptor,
+            output_descriptor,
+        ))
+    }
 
-                let method_json = serde_json::json!({
-                    "name": method.name.as_str(),
-                    "service": method.service.as_str(),
-                    "input_type": method.input_type,
-                    "output_type": method.output_type,
-                    "streaming_type": streaming_type_str,
-                    "client_streaming": method.client_streaming,
-                    "server_streaming": method.server_streaming,
-                    "description": method.description,
-                    "full_name": format!("{}.{}", method.service.as_str(), method.name.as_str())
-                });
-
-                let output = if *pretty {
-                    serde_json::to_string_pretty(&method_json)?
-                } else {
-                    serde_json::to_string(&method_json)?
-                };
-                println!("{}", output);
-            }
-            OutputFormat::Text { .. } => {
-                let streaming_info = match (method.client_streaming, method.server_streaming) {
-                    (true, true) => " (bidirectional streaming)",
-                    (true, false) => " (client streaming)",
-                    (false, true) => " (server streaming)",
-                    (false, false) => " (unary)",
-                };
+    /// Look up a method through reflection, returning its path and the
+    /// descriptors of its request and response messages
+    async fn resolve_method(
+        &self,
+        method: &str,
+    ) -> Result<(
+        PathAndQuery,
+        MethodDescriptor,
+        prost_reflect::MessageDescriptor,
+        prost_reflect::MessageDescriptor,
+    )> {
+        let (service_name, method_name) = parse_method(method)?;
 
-                println!(
-                    "Method: {}.{}{}",
-                    method.service.as_str(),
-                    method.name.as_str(),
-                    streaming_info
-                );
-                println!("  Service: {}", method.service.as_str());
-                println!("  Input type: {}", method.input_type);
-                println!("  Output type: {}", method.output_type);
+        let channel = self.get_or_create_channel().await?;
+        let mut client = self.reflection_client(channel.clone()).await?;
+        let service = client
+            .get_service(&ServiceName::new(service_name.to_string()))
+            .await
+            .with_context(|| format!("Failed to get service: {}", service_name))?;
+        let method = service
+            .methods
+            .iter()
+            .find(|m| m.name.as_str() == method_name)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Method {} not found in service {}",
+                    method_name,
+                    service_name
+                )
+            })?
+            .clone();
 
-                if let Some(desc) = &method.description {
-                    println!("  Description: {}", desc);
-                }
+        let pool = self
+            .get_or_create_descriptor_pool(channel, &method.input_type, &method.output_type)
+            .await?;
+        let input_descriptor = pool
+            .get_message_by_name(&method.input_type)
+            .with_context(|| {
+                format!(
+                    "Failed to get message descriptor for: {}",
+                    method.input_type
+                )
+            })?;
+        let output_descriptor =
+            pool.get_message_by_name(&method.output_type)
+                .with_context(|| {
+                    format!(
+                        "Failed to get message descriptor for: {}",
+                        method.output_type
+                    )
+                })?;
+
+        let path = create_method_path(service.name.as_str(), method.name.as_str())?;
+        Ok((path, method, input_descriptor, output_descriptor))
+    }
 
-                // Show protobuf-style method signature
-                let streaming_prefix = match (method.client_streaming, method.server_streaming) {
-                    (true, true) => "stream ",
-                    (true, false) => "stream ",
-                    (false, true) => "",
-                    (false, false) => "",
-                };
+    /// Invoke a prepared call on the given channel, returning the raw response
+    /// messages without decoding them
+    pub async fn invoke_prepared(
+        &self,
+        channel: Channel,
+        call: &PreparedCall,
+    ) -> Result<Vec<Bytes>, Status> {
+        let mut client = Grpc::new(channel);
+        client
+            .ready()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
 
-                let streaming_suffix = match (method.client_streaming, method.server_streaming) {
-                    (true, true) => "stream ",
-                    (true, false) => "",
-                    (false, true) => "stream ",
-                    (false, false) => "",
-                };
+        let path = call.path.clone();
+        let to_status = |e: anyhow::Error| Status::internal(e.to_string());
+        let first = call.requests.first().cloned().unwrap_or_default();
+        let request_stream = || stream::iter(call.requests.clone());
 
-                println!(
-                    "  Signature: rpc {}({}{}) returns ({}{});",
-                    method.name.as_str(),
-                    streaming_prefix,
-                    method.input_type,
-                    streaming_suffix,
-                    method.output_type
-                );
+        match call.method.streaming_type {
+            StreamingType::Unary => {
+                let request = self
+                    .create_grpc_request_with_headers(first)
+                    .await
+                    .map_err(to_status)?;
+                let response = client.unary(request, path, BytesCodec).await?;
+                Ok(vec![response.into_inner()])
             }
-        }
-
-        Ok(())
-    }
-
-    pub async fn format_message_description(&self, message: &MessageDescriptor) -> Result<()> {
-        match &self.format {
-            OutputFormat::Json { pretty, .. } => {
-                let fields_json: Vec<Value> = message
-                    .fields
-                    .iter()
-                    .map(|field| {
-                        serde_json::json!({
-                            "name": field.name,
-                            "type": field.field_type,
-                            "number": field.number,
-                            "optional": field.optional,
-                            "repeated": field.repeated
-                        })
-                    })
-                    .collect();
-
-                let message_json = serde_json::json!({
-                    "name": message.name,
-                    "description": message.description,
-                    "fields": fields_json
-                });
-
-                let output = if *pretty {
-                    serde_json::to_string_pretty(&message_json)?
-                } else {
-                    serde_json::to_string(&message_json)?
-                };
-                println!("{}", output);
+            StreamingType::ServerStream => {
+                let request = self
+                    .create_grpc_request_with_headers(first)
+                    .await
+                    .map_err(to_status)?;
+                let response = client.server_streaming(request, path, BytesCodec).await?;
+                response.into_inner().try_collect().await
             }
-            OutputFormat::Text { .. } => {
-                println!("message {} {{", message.name);
-
-                if let Some(desc) = &message.description {
-                    println!("  // {}", desc);
-                }
-
-                for field in &message.fields {
-                    let field_modifier = if field.repeated {
-                        "repeated "
-                    } else if field.optional {
-                        "optional "
-                    } else {
-                        ""
-                    };
-
-                    println!(
-                        "  {}{} {} = {};",
-                        field_modifier, field.field_type, field.name, field.number
-                    );
-                }
-
-                println!("}}");
+            StreamingType::ClientStream => {
+                let request = self
+                    .create_grpc_request_with_headers(request_stream())
+                    .await
+                    .map_err(to_status)?;
+                let response = client.client_streaming(request, path, BytesCodec).await?;
+                Ok(vec![response.into_inner()])
+            }
+            StreamingType::BiDirectional => {
+                let request = self
+                    .create_grpc_request_with_headers(request_stream())
+                    .await
+                    .map_err(to_status)?;
+                let response = client.streaming(request, path, BytesCodec).await?;
+                response.into_inner().try_collect().await
             }
         }
-
-        Ok(())
     }
 
     async fn handle_unary(
@@ -439,7 +569,7 @@ impl GrpcClient {
         service: &ServiceDescriptor,
         method: &MethodDescriptor,
         data: Value,
-    ) -> Result<Value> {
+    ) -> Result<(Value, u32)> {
         let input = &method.input_type;
         let output = &method.output_type;
         let service_name = service.name.as_str();
@@ -465,7 +595,7 @@ impl GrpcClient {
             println!("Making gRPC call to {}/{}", service.name, method.name);
             println!("Input type: {}", method.input_type);
             println!("Output type: {}", method.output_type);
-            println!("Request: {}", data);
+            println!("Request: {}", self.loggable(&data, Some(&input_descriptor)));
         }
 
         let request_message = parse_request_message(data, input_descriptor)?;
@@ -473,19 +603,62 @@ impl GrpcClient {
         // Prepare client and request
         let mut client = prepare_grpc_client(channel).await?;
         let path_and_query = create_method_path(service_name, method_name)?;
-        let request =
-            self.create_grpc_request_with_headers(Bytes::from(request_message.encode_to_vec()))?;
+        let body = Bytes::from(request_message.encode_to_vec());
+
+        // Make unary call, retrying transient failures per the retry policy
+        let mut attempt = 1;
+        let response = loop {
+            let request = self.create_grpc_request_with_headers(body.clone()).await?;
+            let status = match client
+                .unary(request, path_and_query.clone(), BytesCodec)
+                .await
+            {
+                Ok(response) => break response,
+                Err(status) => status,
+            };
+
+            let Some(delay) = self
+                .retry
+                .as_ref()
+                .and_then(|policy| policy.retry_delay(&status, attempt))
+            else {
+                let context = if attempt > 1 {
+                    format!("gRPC call failed after {} attempts", attempt)
+                } else {
+                    "gRPC call failed".to_string()
+                };
+                return Err(anyhow::Error::new(status).context(context));
+            };
 
-        // Make unary call
-        let response = client
-            .unary(request, path_and_query, BytesCodec)
-            .await
-            .context("gRPC call failed")?;
+            if self.verbose {
+                println!(
+                    "Attempt {} failed with {:?}: {}. Retrying in {:?}",
+                    attempt,
+                    status.code(),
+                    status.message(),
+                    delay
+                );
+            }
+            tokio::time::sleep(delay).await;
+            client
+                .ready()
+                .await
+                .context("Failed to prepare gRPC client")?;
+            attempt += 1;
+        };
+
+        if self.verbose {
+            if attempt > 1 {
+                println!("Succeeded after {} attempts", attempt);
+            }
+            print_metadata("Response headers", response.metadata());
+        }
 
         let response_bytes: Bytes = response.into_inner();
 
         // Decode response using utility
-        decode_response_message(response_bytes.as_ref(), output_descriptor)
+        let response = decode_response_message(response_bytes.as_ref(), output_descriptor)?;
+        Ok((response, attempt))
     }
 
     async fn handle_server_streaming(
@@ -520,7 +693,7 @@ impl GrpcClient {
             );
             println!("Input type: {}", input);
             println!("Output type: {}", output);
-            println!("Request: {:?}", data);
+            println!("Request: {:?}", self.loggable(&data, Some(&input_desc)));
         }
 
         let request_message = parse_request_message(data, input_desc)?;
@@ -528,8 +701,9 @@ impl GrpcClient {
         // Prepare client and request
         let mut client = prepare_grpc_client(channel).await?;
         let path_and_query = create_method_path(service_name, method_name)?;
-        let request =
-            self.create_grpc_request_with_headers(Bytes::from(request_message.encode_to_vec()))?;
+        let request = self
+            .create_grpc_request_with_headers(Bytes::from(request_message.encode_to_vec()))
+            .await?;
 
         // Make the server streaming call
         let response = client
@@ -537,7 +711,13 @@ impl GrpcClient {
             .await
             .context("Server streaming gRPC call failed")?;
 
-        let responses = process_response_stream(response, output_desc, self.verbose).await?;
+        let responses = process_response_stream(
+            response,
+            output_desc,
+            self.received.as_deref(),
+            self.verbose,
+        )
+        .await?;
 
         Ok(responses)
     }
@@ -571,7 +751,9 @@ impl GrpcClient {
         let path_and_query = create_method_path(service_name, method_name)?;
 
         let request_stream = create_request_stream(data, input_descriptor)?;
-        let request = self.create_grpc_request_with_headers(request_stream)?;
+        let request = self
+            .create_grpc_request_with_headers(request_stream)
+            .await?;
 
         let response = client
             .client_streaming(request, path_and_query, BytesCodec)
@@ -615,7 +797,9 @@ impl GrpcClient {
         let path_and_query = create_method_path(service_name, method_name)?;
 
         let request_stream = create_request_stream(data, input_descriptor)?;
-        let request = self.create_grpc_request_with_headers(request_stream)?;
+        let request = self
+            .create_grpc_request_with_headers(request_stream)
+            .await?;
 
         // Make bidirectional streaming call
         let response_stream = client
@@ -623,8 +807,13 @@ impl GrpcClient {
             .await
             .context("Bidirectional streaming call failed")?;
 
-        let responses =
-            process_response_stream(response_stream, output_descriptor, self.verbose).await?;
+        let responses = process_response_stream(
+            response_stream,
+            output_descriptor,
+            self.received.as_deref(),
+            self.verbose,
+        )
+        .await?;
 
         Ok(responses)
     }
@@ -637,7 +826,7 @@ impl GrpcClient {
         input_type: &str,
         output_type: &str,
     ) -> Result<DescriptorPool> {
-        let mut reflection_client = create_reflection_client(channel);
+        let mut reflection_client = self.reflection_client(channel).await?;
         let mut files_to_process = VecDeque::new();
         let mut processed_files = std::collections::HashSet::new();
         let mut all_file_descriptors = Vec::new();
@@ -646,10 +835,10 @@ impl GrpcClient {
         let initial_symbols = vec![input_type.to_string(), output_type.to_string()];
 
         for symbol in initial_symbols {
-            if let Ok(file_desc) = reflection_client.get_file_containing_symbol(&symbol).await {
-                if !processed_files.contains(file_desc.name()) {
-                    files_to_process.push_back(file_desc);
-                }
+            if let Ok(file_desc) = reflection_client.get_file_containing_symbol(&symbol).await
+                && !processed_files.contains(file_desc.name())
+            {
+                files_to_process.push_back(file_desc);
             }
         }
 
@@ -684,27 +873,108 @@ impl GrpcClient {
             );
         }
 
-        DescriptorPool::from_file_descriptor_set(FileDescriptorSet {
-            file: all_file_descriptors,
-        })
-        .context("Failed to create descriptor pool")
+        // Build the pool from the serialized files so field options survive
+        let mut file_set = Vec::new();
+        for file_desc in &all_file_descriptors {
+            let file_data = reflection_client
+                .raw_file(file_desc.name())
+                .unwrap_or_else(|| file_desc.encode_to_vec());
+            prost::encoding::bytes::encode(1, &file_data, &mut file_set);
+        }
+
+        DescriptorPool::decode(file_set.as_slice()).context("Failed to create descriptor pool")
+    }
+
+    /// Ask the server's `grpc.health.v1.Health` service for the status of a
+    /// service, or of the whole server when `service` is empty
+    pub async fn health_check(&self, service: &str) -> Result<ServingStatus, Status> {
+        let (mut client, request) = self.health_request(service).await?;
+        let response: Response<HealthCheckResponse> = client
+            .unary(
+                request,
+                PathAndQuery::from_static(health::CHECK_PATH),
+                ProstCodec::default(),
+            )
+            .await?;
+        Ok(response.into_inner().status())
+    }
+
+    /// Subscribe to status changes through `grpc.health.v1.Health/Watch`. The
+    /// server sends the current status first and then every change.
+    pub async fn health_watch(
+        &self,
+        service: &str,
+    ) -> Result<Streaming<HealthCheckResponse>, Status> {
+        let (mut client, request) = self.health_request(service).await?;
+        let response = client
+            .server_streaming(
+                request,
+                PathAndQuery::from_static(health::WATCH_PATH),
+                ProstCodec::default(),
+            )
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    async fn health_request(
+        &self,
+        service: &str,
+    ) -> Result<(Grpc<Channel>, Request<HealthCheckRequest>), Status> {
+        let channel = self
+            .get_or_create_channel()
+            .await
+            .map_err(|e| Status::unavailable(format!("{:#}", e)))?;
+        let mut client = Grpc::new(channel);
+        client
+            .ready()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+
+        let request = self
+            .create_grpc_request_with_headers(HealthCheckRequest {
+                service: service.to_string(),
+            })
+            .await
+            .map_err(|e| Status::internal(format!("{:#}", e)))?;
+        Ok((client, request))
     }
 
-    fn create_grpc_request_with_headers<T>(&self, body: T) -> Result<Request<T>> {
+    pub(crate) async fn create_grpc_request_with_headers<T>(&self, body: T) -> Result<Request<T>> {
+        let auth_metadata = self.auth_metadata().await?;
         let mut request = Request::new(body);
 
-        // Add headers to the request
-        for (key, value) in &self.headers {
-            let key = MetadataKey::from_bytes(key.as_bytes())
-                .with_context(|| format!("Invalid header key: {}", key))?;
-            request
-                .metadata_mut()
-                .insert(key, value.parse().context("Invalid header value")?);
-        }
+        // Add headers to the request, auth credentials take precedence
+        insert_headers(request.metadata_mut(), &self.headers)?;
+        insert_headers(request.metadata_mut(), &auth_metadata)?;
 
         Ok(request)
     }
 
+    /// Fetch credentials from the configured auth provider, if any
+    async fn auth_metadata(&self) -> Result<Vec<(String, String)>> {
+        match &self.auth {
+            Some(provider) => provider
+                .metadata()
+                .await
+                .context("Failed to obtain auth credentials"),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Metadata for reflection requests: call headers, reflection-only headers
+    /// and auth credentials, in increasing order of precedence
+    async fn reflection_metadata(&self) -> Result<Vec<(String, String)>> {
+        let mut metadata = self.headers.clone();
+        metadata.extend(self.reflect_headers.iter().cloned());
+        metadata.extend(self.auth_metadata().await?);
+        Ok(metadata)
+    }
+
+    async fn reflection_client(&self, channel: Channel) -> Result<Box<dyn SchemaSource>> {
+        let metadata = self.reflection_metadata().await?;
+        Ok(create_reflection_client_with_metadata(channel, metadata))
+    }
+
     pub async fn get_or_create_channel(&self) -> Result<Channel> {
         let cache_key = format!("{}:{}", self.endpoint.host, self.endpoint.port);
 
@@ -731,6 +1001,13 @@ impl GrpcClient {
         Ok(channel)
     }
 
+    /// Open a new, uncached connection to the endpoint
+    pub async fn create_new_channel(&self) -> Result<Channel> {
+        create_channel(&self.endpoint, self.plaintext, &self.ca_cert_path)
+            .await
+            .context("Failed to create gRPC channel")
+    }
+
     pub async fn get_or_create_descriptor_pool(
         &self,
         channel: Channel,
@@ -760,6 +1037,112 @@ impl GrpcClient {
     }
 }
 
+/// Responses of a method call with details about how it was made
+#[derive(Debug, Clone)]
+pub struct CallOutcome {
+    pub responses: Vec<Value>,
+    /// Number of attempts, greater than 1 when the call was retried
+    pub attempts: u32,
+    pub streaming_type: StreamingType,
+    /// Descriptor of the request messages, for masking them in history
+    pub input_descriptor: Option<prost_reflect::MessageDescriptor>,
+    /// Descriptor of the response messages, for type-aware rendering
+    pub output_descriptor: Option<prost_reflect::MessageDescriptor>,
+}
+
+impl CallOutcome {
+    fn single_attempt(responses: Vec<Value>, streaming_type: StreamingType) -> Self {
+        Self {
+            responses,
+            attempts: 1,
+            streaming_type,
+            input_descriptor: None,
+            output_descriptor: None,
+        }
+    }
+}
+
+/// A method looked up through reflection, with the descriptors of its
+/// messages when the server provides them
+#[derive(Debug, Clone)]
+pub struct ResolvedMethod {
+    service: ServiceDescriptor,
+    method: MethodDescriptor,
+    input_descriptor: Option<prost_reflect::MessageDescriptor>,
+    output_descriptor: Option<prost_reflect::MessageDescriptor>,
+}
+
+/// A method call resolved and encoded ahead of time for repeated invocation
+#[derive(Debug, Clone)]
+pub struct PreparedCall {
+    pub path: PathAndQuery,
+    pub method: MethodDescriptor,
+    /// Encoded request messages (one for unary and server streaming calls)
+    pub requests: Vec<Bytes>,
+    pub output_descriptor: prost_reflect::MessageDescriptor,
+}
+
+impl PreparedCall {
+    /// Decode a raw response message into JSON
+    pub fn decode_response(&self, bytes: &[u8]) -> Result<Value> {
+        decode_response_message(bytes, self.output_descriptor.clone())
+    }
+}
+
+/// Build the retry policy from the context's policy and the --max-attempts,
+/// --retry-on and --retry-backoff flags, which override it
+fn retry_policy_from_cli(cli: &Cli) -> Result<Option<RetryPolicy>> {
+    let has_flags =
+        cli.max_attempts.is_some() || !cli.retry_on.is_empty() || cli.retry_backoff.is_some();
+    if !has_flags {
+        return Ok(cli.retry.clone());
+    }
+
+    let mut policy = cli.retry.clone().unwrap_or_default();
+    if let Some(max_attempts) = cli.max_attempts {
+        policy.max_attempts = max_attempts;
+    }
+    if !cli.retry_on.is_empty() {
+        policy.retryable_codes = cli
+            .retry_on
+            .iter()
+            .map(|code| RetryCode::parse(code))
+            .collect::<Result<_>>()?;
+    }
+    if let Some(backoff) = cli.retry_backoff {
+        policy.initial_backoff = backoff;
+    }
+
+    Ok(Some(policy))
+}
+
+/// Redaction settings from the context, extended with `--redact` paths and
+/// disabled by `--no-redact`
+fn redactor_from_cli(cli: &Cli) -> Result<Option<Arc<Redactor>>> {
+    if cli.no_redact {
+        return Ok(None);
+    }
+
+    let mut config = cli.redaction.clone().unwrap_or_default();
+    config.paths.extend(cli.redact.iter().cloned());
+    Ok(Redactor::from_config(&config)?.map(Arc::new))
+}
+
+/// Parse `name: value` header arguments from the command line
+fn parse_header_args(headers: &[String]) -> Result<Vec<(String, String)>> {
+    headers
+        .iter()
+        .map(|h| {
+            let parts: Vec<&str> = h.splitn(2, ':').collect();
+            if parts.len() == 2 {
+                Ok((parts[0].trim().to_string(), parts[1].trim().to_string()))
+            } else {
+                anyhow::bail!("Invalid header format: {}. Expected 'name: value'", h)
+            }
+        })
+        .collect()
+}
+
 fn create_request_stream(
     data: Value,
     input_descriptor: prost_reflect::MessageDescriptor,
@@ -784,8 +1167,13 @@ fn create_request_stream(
 async fn process_response_stream(
     response: Response<Streaming<Bytes>>,
     output_descriptor: prost_reflect::MessageDescriptor,
+    received: Option<&AtomicU64>,
     verbose: bool,
 ) -> Result<Vec<Value>> {
+    if verbose {
+        print_metadata("Response headers", response.metadata());
+    }
+
     let mut stream = response.into_inner();
 
     // Process each response in the stream with enhanced error handling
@@ -806,12 +1194,32 @@ async fn process_response_stream(
             .map_err(|e| handle_stream_error(&e, "Response decoding", verbose))?;
 
         responses.push(ret);
+        if let Some(received) = received {
+            received.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    if verbose && let Ok(Some(trailers)) = stream.trailers().await {
+        print_metadata("Response trailers", &trailers);
     }
 
     Ok(responses)
 }
 
-fn parse_method(method: &str) -> Result<(&str, &str)> {
+/// Print response metadata, with binary (`-bin`) values shown as base64
+fn print_metadata(title: &str, metadata: &tonic::metadata::MetadataMap) {
+    let pairs = metadata_to_pairs(metadata);
+    if pairs.is_empty() {
+        return;
+    }
+
+    println!("{}:", title);
+    for (key, value) in pairs {
+        println!("  {}: {}", key, value);
+    }
+}
+
+pub(crate) fn parse_method(method: &str) -> Result<(&str, &str)> {
     // Check for slash first (service/method format)
     if let Some(pos) = method.rfind('/') {
         Ok((&method[..pos], &method[pos + 1..]))
@@ -825,7 +1233,10 @@ fn parse_method(method: &str) -> Result<(&str, &str)> {
     }
 }
 
-fn decode_response_message(buf: &[u8], desc: prost_reflect::MessageDescriptor) -> Result<Value> {
+pub(crate) fn decode_response_message(
+    buf: &[u8],
+    desc: prost_reflect::MessageDescriptor,
+) -> Result<Value> {
     let msg = DynamicMessage::decode(desc, buf).context("Failed to decode response message")?;
     let data = serde_json::to_value(&msg).context("Failed to convert response to JSON")?;
     Ok(data)
@@ -845,7 +1256,7 @@ async fn prepare_grpc_client(channel: Channel) -> Result<Grpc<Channel>> {
     Ok(client)
 }
 
-fn parse_request_message(
+pub(crate) fn parse_request_message(
     value: Value,
     input_desc: prost_reflect::MessageDescriptor,
 ) -> Result<DynamicMessage> {
@@ -894,52 +1305,54 @@ fn handle_stream_error(
     }
 
     if let Some(status) = error.downcast_ref::<Status>() {
-        match status.code() {
+        // Keep the status in the error chain so callers can inspect the code
+        let message = match status.code() {
             Code::Unavailable => {
-                anyhow!(
+                format!(
                     "{} failed: Server unavailable. Please check the server is running and accessible.",
                     operation_name
                 )
             }
             Code::DeadlineExceeded => {
-                anyhow!(
+                format!(
                     "{} failed: Request timed out. The server may be overloaded.",
                     operation_name
                 )
             }
             Code::ResourceExhausted => {
-                anyhow!(
+                format!(
                     "{} failed: Server resource exhausted. Try reducing request size or frequency.",
                     operation_name
                 )
             }
             Code::PermissionDenied => {
-                anyhow!(
+                format!(
                     "{} failed: Permission denied. Check authentication credentials.",
                     operation_name
                 )
             }
             Code::Unauthenticated => {
-                anyhow!(
+                format!(
                     "{} failed: Authentication required. Provide valid credentials.",
                     operation_name
                 )
             }
             Code::NotFound => {
-                anyhow!(
+                format!(
                     "{} failed: Service or method not found. Verify the service and method names.",
                     operation_name
                 )
             }
             _ => {
-                anyhow!(
+                format!(
                     "{} failed: gRPC error ({}): {}",
                     operation_name,
                     status.code(),
                     status.message()
                 )
             }
-        }
+        };
+        anyhow::Error::new(status.clone()).context(message)
     } else {
         anyhow!("{} failed: {}", operation_name, error)
     }
@@ -1098,12 +1511,13 @@ mod tests {
         assert_eq!(path.as_str(), "/com.example.Service/ListUsers");
     }
 
-    #[test]
-    fn test_create_grpc_request_with_headers() {
+    #[tokio::test]
+    async fn test_create_grpc_request_with_headers() {
         let client = create_test_client_with_headers();
 
         let request = client
             .create_grpc_request_with_headers("test_body".to_string())
+            .await
             .unwrap();
         assert_eq!(*request.get_ref(), "test_body");
 
@@ -1112,14 +1526,16 @@ mod tests {
         assert!(metadata.len() >= 2); // At least our test headers
     }
 
-    #[test]
-    fn test_create_grpc_request_with_invalid_header() {
+    #[tokio::test]
+    async fn test_create_grpc_request_with_invalid_header() {
         let mut client = create_test_client();
         client
             .headers
             .push(("invalid\nheader".to_string(), "value".to_string()));
 
-        let result = client.create_grpc_request_with_headers("test_body".to_string());
+        let result = client
+            .create_grpc_request_with_headers("test_body".to_string())
+            .await;
         assert!(result.is_err());
         assert!(
             result
@@ -1129,6 +1545,148 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_with_headers_overrides() {
+        let client = create_test_client_with_headers();
+        let client = client.with_headers(vec![
+            ("authorization".to_string(), "Bearer other".to_string()),
+            ("x-extra".to_string(), "1".to_string()),
+        ]);
+
+        assert_eq!(client.headers.len(), 3);
+        assert!(
+            client
+                .headers
+                .contains(&("authorization".to_string(), "Bearer other".to_string()))
+        );
+        assert!(!client.headers.iter().any(|(k, _)| k == "Authorization"));
+    }
+
+    #[test]
+    fn test_retry_policy_from_cli() {
+        let cli = Cli::parse_from(["grpc-client", "list", "localhost:9090"]);
+        assert!(GrpcClient::from_cli(&cli).unwrap().retry.is_none());
+
+        let mut cli = Cli::parse_from([
+            "grpc-client",
+            "--max-attempts",
+            "5",
+            "--retry-on",
+            "unavailable,aborted",
+            "list",
+            "localhost:9090",
+        ]);
+        cli.retry = Some(RetryPolicy {
+            initial_backoff: std::time::Duration::from_secs(1),
+            ..Default::default()
+        });
+
+        let policy = GrpcClient::from_cli(&cli).unwrap().retry.unwrap();
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.initial_backoff, std::time::Duration::from_secs(1));
+        assert_eq!(
+            policy.retryable_codes,
+            vec![RetryCode(Code::Unavailable), RetryCode(Code::Aborted)]
+        );
+
+        let cli = Cli::parse_from([
+            "grpc-client",
+            "--retry-on",
+            "NOT_A_CODE",
+            "list",
+            "localhost:9090",
+        ]);
+        assert!(GrpcClient::from_cli(&cli).is_err());
+
+        let zero_attempts = Cli::try_parse_from([
+            "grpc-client",
+            "--max-attempts",
+            "0",
+            "list",
+            "localhost:9090",
+        ]);
+        assert!(zero_attempts.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_grpc_request_with_auth_exec() {
+        let cli = Cli::parse_from([
+            "grpc-client",
+            "-H",
+            "authorization: Bearer stale",
+            "--auth-exec",
+            "echo fresh-token",
+            "list",
+            "localhost:9090",
+        ]);
+        let client = GrpcClient::from_cli(&cli).unwrap();
+
+        let request = client
+            .create_grpc_request_with_headers("test_body".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            request.metadata().get("authorization").unwrap(),
+            "Bearer fresh-token"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_exec_keeps_quoted_arguments() {
+        let cli = |command: &str| {
+            Cli::parse_from([
+                "grpc-client",
+                "--auth-exec",
+                command,
+                "list",
+                "localhost:9090",
+            ])
+        };
+        let client = GrpcClient::from_cli(&cli(r#"printf "%s-%s" 'token with' "spaces""#)).unwrap();
+
+        let request = client
+            .create_grpc_request_with_headers("test_body".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            request.metadata().get("authorization").unwrap(),
+            "Bearer token with-spaces"
+        );
+
+        let unterminated = GrpcClient::from_cli(&cli("printf 'token")).unwrap_err();
+        assert!(unterminated.to_string().contains("Invalid quoting"));
+    }
+
+    #[tokio::test]
+    async fn test_reflection_metadata() {
+        let cli = Cli::parse_from([
+            "grpc-client",
+            "-H",
+            "authorization: Bearer token",
+            "--reflect-header",
+            "x-reflect: yes",
+            "list",
+            "localhost:9090",
+        ]);
+        let client = GrpcClient::from_cli(&cli).unwrap();
+
+        let metadata = client.reflection_metadata().await.unwrap();
+        assert_eq!(
+            metadata,
+            vec![
+                ("authorization".to_string(), "Bearer token".to_string()),
+                ("x-reflect".to_string(), "yes".to_string()),
+            ]
+        );
+
+        // Reflection-only headers are not sent on regular calls
+        let request = client
+            .create_grpc_request_with_headers("test_body".to_string())
+            .await
+            .unwrap();
+        assert!(request.metadata().get("x-reflect").is_none());
+    }
+
     fn create_test_client() -> GrpcClient {
         let cli = Cli::parse_from(["grpc-client", "list", "localhost:9090"]);
         GrpcClient::from_cli(&cli).unwrap()
@@ -0,0 +1,318 @@
+use crate::template::TemplateEngine;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+/// Default directory of collection files
+pub fn default_collections_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("grpc-client").join("collections"))
+}
+
+/// A named folder of saved requests. Each collection is stored as one YAML
+/// file, `<name>.yml`, which can be shared and imported elsewhere.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Collection {
+    /// Collection name; taken from the file name or API path when omitted
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub requests: Vec<SavedRequest>,
+}
+
+/// A request saved for running again later
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SavedRequest {
+    /// Request name; taken from the API path when omitted
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Server ID of the web server config, or a CLI context name or endpoint.
+    /// Without one the CLI uses --context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Full method name (e.g., "package.service/method")
+    pub method: String,
+    /// Request message, or an array of messages for client streaming. May
+    /// reference variables as {{var.name}}.
+    #[serde(default = "empty_request")]
+    pub request: Value,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn empty_request() -> Value {
+    Value::Object(Default::default())
+}
+
+impl Collection {
+    /// Parse a collection file, in YAML or JSON
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let collection: Self = serde_yaml::from_str(content)?;
+        Ok(collection)
+    }
+
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(self).context("Failed to serialize collection to YAML")
+    }
+
+    /// Check names are usable in paths and files and unique per collection
+    pub fn validate(&self) -> Result<()> {
+        validate_name("collection", &self.name)?;
+        for (i, request) in self.requests.iter().enumerate() {
+            request.validate()?;
+            if self.requests[..i].iter().any(|r| r.name == request.name) {
+                bail!(
+                    "Duplicate request '{}' in collection '{}'",
+                    request.name,
+                    self.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn request(&self, name: &str) -> Option<&SavedRequest> {
+        self.requests.iter().find(|request| request.name == name)
+    }
+
+    /// Add a request, replacing one with the same name in place
+    pub fn upsert_request(&mut self, request: SavedRequest) {
+        match self.requests.iter_mut().find(|r| r.name == request.name) {
+            Some(existing) => *existing = request,
+            None => self.requests.push(request),
+        }
+    }
+}
+
+impl SavedRequest {
+    /// Copy of the request with templates expanded in its server, method,
+    /// headers and request message, as it is run and authorized
+    pub fn render(&self, template: &TemplateEngine) -> Result<Self> {
+        let server = self
+            .server
+            .as_deref()
+            .map(|server| template.render(server))
+            .transpose()
+            .context("Failed to expand server templates")?;
+        let method = template
+            .render(&self.method)
+            .context("Failed to expand method templates")?;
+        let headers = self
+            .headers
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), template.render(value)?)))
+            .collect::<Result<_>>()
+            .context("Failed to expand header templates")?;
+        let request = template
+            .render_value(self.request.clone())
+            .context("Failed to expand request templates")?;
+        Ok(Self {
+            server,
+            method,
+            headers,
+            request,
+            ..self.clone()
+        })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_name("request", &self.name)?;
+        if !self.method.contains('/') {
+            bail!(
+                "Invalid method '{}' in request '{}': use package.Service/Method",
+                self.method,
+                self.name
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Check that a collection or request name can be used in paths and file names
+pub fn validate_name(kind: &str, name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "Invalid {} name '{}': use letters, digits, '-', '_' and '.', not starting with '.'",
+            kind,
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Split a `collection/name` reference to a saved request
+pub fn parse_saved_path(path: &str) -> Result<(&str, &str)> {
+    match path.split_once('/') {
+        Some((collection, name)) if !collection.is_empty() && !name.is_empty() => {
+            Ok((collection, name))
+        }
+        _ => bail!("Invalid saved request '{}': use collection/name", path),
+    }
+}
+
+/// File of a collection in `dir`
+pub fn collection_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{}.yml", name))
+}
+
+/// Existing file of a collection in `dir`, `<name>.yml` or `<name>.yaml`
+fn find_collection_file(dir: &Path, name: &str) -> PathBuf {
+    let path = collection_path(dir, name);
+    let alternative = path.with_extension("yaml");
+    if !path.exists() && alternative.exists() {
+        alternative
+    } else {
+        path
+    }
+}
+
+/// Read a collection file. A collection without a name is named after the file.
+pub fn load_collection_file(path: &Path) -> Result<Collection> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read collection file: {}", path.display()))?;
+    let mut collection = Collection::from_yaml(&content)
+        .with_context(|| format!("Invalid collection file: {}", path.display()))?;
+    if collection.name.is_empty() {
+        collection.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    collection
+        .validate()
+        .with_context(|| format!("Invalid collection file: {}", path.display()))?;
+    Ok(collection)
+}
+
+/// Find a saved request by its `collection/name` reference in `dir`
+pub fn load_saved_request(dir: &Path, path: &str) -> Result<SavedRequest> {
+    let (collection, name) = parse_saved_path(path)?;
+    validate_name("collection", collection)?;
+    let collection = load_collection_file(&find_collection_file(dir, collection))?;
+    collection
+        .request(name)
+        .cloned()
+        .with_context(|| format!("No request '{}' in collection '{}'", name, collection.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const USERS: &str = r#"
+description: User service calls
+requests:
+  - name: get-user
+    server: local
+    method: example.UserService/GetUser
+    request: {userId: "{{var.user}}"}
+    headers:
+      x-tenant: acme
+  - name: list-users
+    method: example.UserService/ListUsers
+"#;
+
+    #[test]
+    fn test_load_saved_request() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("users.yml"), USERS).unwrap();
+
+        let request = load_saved_request(dir.path(), "users/get-user").unwrap();
+        assert_eq!(request.server.as_deref(), Some("local"));
+        assert_eq!(request.request, json!({"userId": "{{var.user}}"}));
+        assert_eq!(request.headers["x-tenant"], "acme");
+
+        let request = load_saved_request(dir.path(), "users/list-users").unwrap();
+        assert_eq!(request.request, json!({}));
+        assert!(request.server.is_none());
+
+        let err = load_saved_request(dir.path(), "users/missing").unwrap_err();
+        assert!(err.to_string().contains("No request 'missing'"));
+        assert!(load_saved_request(dir.path(), "get-user").is_err());
+        assert!(load_saved_request(dir.path(), "../users/get-user").is_err());
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let mut collection = Collection::from_yaml(USERS).unwrap();
+        collection.name = "users".to_string();
+        let yaml = collection.to_yaml().unwrap();
+        assert_eq!(Collection::from_yaml(&yaml).unwrap(), collection);
+
+        // JSON files are accepted too
+        let json = serde_json::to_string(&collection).unwrap();
+        assert_eq!(Collection::from_yaml(&json).unwrap(), collection);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut collection = Collection::from_yaml(USERS).unwrap();
+        collection.name = "users".to_string();
+        assert!(collection.validate().is_ok());
+
+        let duplicate = collection.requests[0].clone();
+        collection.requests.push(duplicate);
+        assert!(collection.validate().is_err());
+
+        collection.requests.pop();
+        collection.requests[1].method = "ListUsers".to_string();
+        assert!(collection.validate().is_err());
+
+        assert!(validate_name("collection", "..").is_err());
+        assert!(validate_name("collection", "a/b").is_err());
+        assert!(validate_name("collection", "v1.users").is_ok());
+    }
+
+    #[test]
+    fn test_render_saved_request() {
+        let mut request = Collection::from_yaml(USERS).unwrap().requests.remove(0);
+        request.server = Some("{{var.server}}".to_string());
+        request.method = "example.UserService/{{var.op}}".to_string();
+        request
+            .headers
+            .insert("x-user".to_string(), "{{var.user}}".to_string());
+        let mut template = TemplateEngine::new();
+        template.set("server", "staging");
+        template.set("op", "GetUser");
+        template.set("user", "7");
+
+        let rendered = request.render(&template).unwrap();
+        assert_eq!(rendered.server.as_deref(), Some("staging"));
+        assert_eq!(rendered.method, "example.UserService/GetUser");
+        assert_eq!(rendered.request, json!({"userId": "7"}));
+        assert_eq!(rendered.headers["x-user"], "7");
+        assert_eq!(rendered.headers["x-tenant"], "acme");
+
+        let missing = request.render(&TemplateEngine::new()).unwrap_err();
+        assert!(format!("{:#}", missing).contains("server templates"));
+    }
+
+    #[test]
+    fn test_upsert_request() {
+        let mut collection = Collection::from_yaml(USERS).unwrap();
+        let mut request = collection.requests[0].clone();
+        request.request = json!({"userId": "2"});
+        collection.upsert_request(request);
+        assert_eq!(collection.requests.len(), 2);
+        assert_eq!(collection.requests[0].request, json!({"userId": "2"}));
+
+        request = collection.requests[0].clone();
+        request.name = "get-other".to_string();
+        collection.upsert_request(request);
+        assert_eq!(collection.requests[2].name, "get-other");
+    }
+}
@@ -0,0 +1,153 @@
+use serde_json::Value;
+use std::fmt;
+
+/// A single difference between two JSON values
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", display_path(path), value),
+            Change::Removed { path, value } => write!(f, "- {}: {}", display_path(path), value),
+            Change::Changed { path, old, new } => {
+                write!(f, "~ {}: {} -> {}", display_path(path), old, new)
+            }
+        }
+    }
+}
+
+/// Compare two JSON values field by field. Paths use dotted field names and
+/// `[index]` for array elements, e.g. `user.addresses[0].city`.
+pub fn diff_values(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_at("", old, new, &mut changes);
+    changes
+}
+
+fn diff_at(path: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            for (key, old_value) in old_map {
+                let child = join_field(path, key);
+                match new_map.get(key) {
+                    Some(new_value) => diff_at(&child, old_value, new_value, changes),
+                    None => changes.push(Change::Removed {
+                        path: child,
+                        value: old_value.clone(),
+                    }),
+                }
+            }
+            for (key, new_value) in new_map {
+                if !old_map.contains_key(key) {
+                    changes.push(Change::Added {
+                        path: join_field(path, key),
+                        value: new_value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for (i, old_item) in old_items.iter().enumerate() {
+                let child = format!("{}[{}]", path, i);
+                match new_items.get(i) {
+                    Some(new_item) => diff_at(&child, old_item, new_item, changes),
+                    None => changes.push(Change::Removed {
+                        path: child,
+                        value: old_item.clone(),
+                    }),
+                }
+            }
+            for (i, new_item) in new_items.iter().enumerate().skip(old_items.len()) {
+                changes.push(Change::Added {
+                    path: format!("{}[{}]", path, i),
+                    value: new_item.clone(),
+                });
+            }
+        }
+        _ if old != new => changes.push(Change::Changed {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn join_field(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "(root)" } else { path }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_identical_values() {
+        let value = json!({"status": "RUNNING", "progress": [1, 2]});
+        assert!(diff_values(&value, &value).is_empty());
+    }
+
+    #[test]
+    fn test_nested_changes() {
+        let old = json!({"job": {"status": "RUNNING", "progress": 10}, "logs": ["a"]});
+        let new = json!({"job": {"status": "DONE", "finished": true}, "logs": ["a", "b"]});
+
+        let changes = diff_values(&old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                Change::Changed {
+                    path: "job.status".to_string(),
+                    old: json!("RUNNING"),
+                    new: json!("DONE"),
+                },
+                Change::Removed {
+                    path: "job.progress".to_string(),
+                    value: json!(10),
+                },
+                Change::Added {
+                    path: "job.finished".to_string(),
+                    value: json!(true),
+                },
+                Change::Added {
+                    path: "logs[1]".to_string(),
+                    value: json!("b"),
+                },
+            ]
+        );
+        assert_eq!(
+            changes[0].to_string(),
+            r#"~ job.status: "RUNNING" -> "DONE""#
+        );
+    }
+
+    #[test]
+    fn test_root_change() {
+        let changes = diff_values(&json!(1), &json!("one"));
+        assert_eq!(changes[0].to_string(), r#"~ (root): 1 -> "one""#);
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use derive_more::{Display, From, Into};
 use serde::{Deserialize, Serialize};
+use tonic::Code;
 
 /// Simple newtypes for domain concepts
 #[derive(Debug, Clone, PartialEq, Eq, Display, From, Into, Serialize, Deserialize)]
@@ -76,11 +77,97 @@ impl std::fmt::Display for Endpoint {
     }
 }
 
+/// Parse a gRPC status code from its canonical name (e.g. `NOT_FOUND`),
+/// ignoring case and accepting `-` for `_`
+pub fn parse_status_code(name: &str) -> Result<Code> {
+    let normalized = name.trim().to_ascii_uppercase().replace('-', "_");
+    let code = match normalized.as_str() {
+        "OK" => Code::Ok,
+        "CANCELLED" => Code::Cancelled,
+        "UNKNOWN" => Code::Unknown,
+        "INVALID_ARGUMENT" => Code::InvalidArgument,
+        "DEADLINE_EXCEEDED" => Code::DeadlineExceeded,
+        "NOT_FOUND" => Code::NotFound,
+        "ALREADY_EXISTS" => Code::AlreadyExists,
+        "PERMISSION_DENIED" => Code::PermissionDenied,
+        "RESOURCE_EXHAUSTED" => Code::ResourceExhausted,
+        "FAILED_PRECONDITION" => Code::FailedPrecondition,
+        "ABORTED" => Code::Aborted,
+        "OUT_OF_RANGE" => Code::OutOfRange,
+        "UNIMPLEMENTED" => Code::Unimplemented,
+        "INTERNAL" => Code::Internal,
+        "UNAVAILABLE" => Code::Unavailable,
+        "DATA_LOSS" => Code::DataLoss,
+        "UNAUTHENTICATED" => Code::Unauthenticated,
+        _ => anyhow::bail!("Unknown status code: {}", name),
+    };
+    Ok(code)
+}
+
+/// Canonical name of a gRPC status code (e.g. `NOT_FOUND`)
+pub fn status_code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
+    }
+}
+
 /// Configuration types
 #[derive(Debug, Clone)]
 pub enum OutputFormat {
-    Json { pretty: bool, emit_defaults: bool },
-    Text { compact: bool },
+    Json {
+        pretty: bool,
+        emit_defaults: bool,
+    },
+    Text {
+        compact: bool,
+    },
+    Table {
+        columns: Vec<String>,
+    },
+    Csv {
+        columns: Vec<String>,
+    },
+    /// A format added to the [`crate::format::FormatterRegistry`]
+    Custom {
+        name: String,
+    },
+}
+
+impl OutputFormat {
+    /// Name the format is registered under
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Json { .. } => "json",
+            Self::Text { .. } => "text",
+            Self::Table { .. } => "table",
+            Self::Csv { .. } => "csv",
+            Self::Custom { name } => name,
+        }
+    }
+
+    /// Requested columns of table formats
+    pub fn columns(&self) -> &[String] {
+        match self {
+            Self::Table { columns } | Self::Csv { columns } => columns,
+            _ => &[],
+        }
+    }
 }
 
 impl Default for OutputFormat {
@@ -115,6 +202,35 @@ impl Default for SecurityConfig {
     }
 }
 
+/// Serde helpers for durations written as human-readable strings like "250ms" or "2s"
+pub(crate) mod duration_str {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&humantime::format_duration(*duration).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        humantime::parse_duration(&value).map_err(serde::de::Error::custom)
+    }
+
+    /// Deserialize optional durations in the same format
+    pub mod option {
+        use serde::{Deserialize, Deserializer};
+        use std::time::Duration;
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|value| humantime::parse_duration(&value).map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -146,6 +262,17 @@ mod tests {
         assert_eq!(endpoint.port, 80);
     }
 
+    #[test]
+    fn test_status_code_names() {
+        assert_eq!(parse_status_code("not_found").unwrap(), Code::NotFound);
+        assert_eq!(parse_status_code("OK").unwrap(), Code::Ok);
+        assert_eq!(
+            status_code_name(Code::ResourceExhausted),
+            "RESOURCE_EXHAUSTED"
+        );
+        assert!(parse_status_code("SOMETIMES").is_err());
+    }
+
     #[test]
     fn test_service_and_method_names() {
         let service = ServiceName::new("test.Service".to_string());
@@ -1,8 +1,18 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use serde_json::Value;
-
-use crate::domain::OutputFormat;
+use prost_reflect::MessageDescriptor;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Arc;
+
+use crate::domain::{OutputFormat, ServiceName};
+use crate::reflection::{
+    MessageDescriptor as ReflectedMessage, MethodDescriptor, ServiceDescriptor, StreamingType,
+    Symbol,
+};
+use crate::render::TextRenderer;
+use crate::table::{CsvFormatter, TableFormatter};
 
 #[async_trait]
 pub trait ResponseFormatter: Send + Sync {
@@ -54,7 +64,7 @@ impl JsonFormatter {
     pub fn from_output_format(format: &OutputFormat) -> Self {
         match format {
             OutputFormat::Json { pretty, .. } => Self::new(*pretty),
-            OutputFormat::Text { .. } => Self::new(true),
+            _ => Self::new(true),
         }
     }
 }
@@ -159,17 +169,44 @@ impl StreamingFormatter for JsonFormatter {
 
 pub struct TextFormatter {
     compact: bool,
+    renderer: TextRenderer,
+    descriptor: Option<MessageDescriptor>,
 }
 
 impl TextFormatter {
     pub fn new(compact: bool) -> Self {
-        Self { compact }
+        Self {
+            compact,
+            renderer: TextRenderer::default(),
+            descriptor: None,
+        }
+    }
+
+    /// Use the given renderer for colors and bytes encoding
+    pub fn with_renderer(mut self, renderer: TextRenderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Render responses according to the fields of this message type
+    pub fn with_descriptor(mut self, descriptor: Option<MessageDescriptor>) -> Self {
+        self.descriptor = descriptor;
+        self
+    }
+
+    fn render(&self, response: &Value) -> Result<String> {
+        if self.compact {
+            Ok(serde_json::to_string(response)?)
+        } else {
+            let text = self.renderer.render(response, self.descriptor.as_ref());
+            Ok(text.trim_end().to_string())
+        }
     }
 
     pub fn from_output_format(format: &OutputFormat) -> Self {
         match format {
             OutputFormat::Text { compact } => Self::new(*compact),
-            OutputFormat::Json { .. } => Self::new(false),
+            _ => Self::new(false),
         }
     }
 }
@@ -177,11 +214,7 @@ impl TextFormatter {
 #[async_trait]
 impl ResponseFormatter for TextFormatter {
     async fn format_response(&self, response: &Value) -> Result<String> {
-        if self.compact {
-            Ok(serde_json::to_string(response)?)
-        } else {
-            Ok(serde_json::to_string_pretty(response)?)
-        }
+        self.render(response)
     }
 
     async fn format_error(&self, status: &tonic::Status) -> Result<String> {
@@ -196,13 +229,12 @@ impl ResponseFormatter for TextFormatter {
 #[async_trait]
 impl StreamingFormatter for TextFormatter {
     async fn format_stream_response(&self, response: &Value, sequence: usize) -> Result<String> {
-        let response_str = if self.compact {
-            serde_json::to_string(response)?
+        let response_str = self.render(response)?;
+        if self.compact {
+            Ok(format!("[{}] {}", sequence, response_str))
         } else {
-            serde_json::to_string_pretty(response)?
-        };
-
-        Ok(format!("[{}] {}", sequence, response_str))
+            Ok(format!("[{}]\n{}", sequence, response_str))
+        }
     }
 
     async fn format_stream_start(&self) -> Result<String> {
@@ -237,17 +269,405 @@ impl StreamingFormatter for TextFormatter {
     }
 }
 
-pub fn create_formatter(format: &OutputFormat) -> Box<dyn ResponseFormatter> {
-    match format {
-        OutputFormat::Json { .. } => Box::new(JsonFormatter::from_output_format(format)),
-        OutputFormat::Text { .. } => Box::new(TextFormatter::from_output_format(format)),
+/// Formats everything the CLI prints: call responses, streams, and the
+/// results of `list` and `describe`
+#[async_trait]
+pub trait OutputFormatter: ResponseFormatter + StreamingFormatter {
+    /// Format the result of `list` or `describe`. By default it is formatted
+    /// like a response with the JSON shape of [`Listing::to_value`].
+    async fn format_listing(&self, listing: &Listing<'_>) -> Result<String> {
+        self.format_response(&listing.to_value()).await
+    }
+}
+
+/// Result of `list` or `describe`
+#[derive(Debug, Clone, Copy)]
+pub enum Listing<'a> {
+    Services(&'a [ServiceName]),
+    Methods(&'a [MethodDescriptor]),
+    Symbol(&'a Symbol),
+}
+
+impl Listing<'_> {
+    /// JSON form of the listing; lists are wrapped in an object so that table
+    /// formats get one row per entry
+    pub fn to_value(&self) -> Value {
+        match self {
+            Listing::Services(services) => json!({ "services": service_list(services) }),
+            Listing::Methods(methods) => json!({ "methods": method_list(methods) }),
+            Listing::Symbol(Symbol::Service(service)) => service_json(service),
+            Listing::Symbol(Symbol::Method(method)) => method_json(method),
+            Listing::Symbol(Symbol::Message(message)) => message_json(message),
+        }
+    }
+}
+
+fn streaming_type_name(streaming_type: &StreamingType) -> &'static str {
+    match streaming_type {
+        StreamingType::Unary => "unary",
+        StreamingType::ServerStream => "server_streaming",
+        StreamingType::ClientStream => "client_streaming",
+        StreamingType::BiDirectional => "bidirectional",
+    }
+}
+
+fn service_list(services: &[ServiceName]) -> Vec<Value> {
+    services
+        .iter()
+        .map(|service| json!({ "name": service.as_str() }))
+        .collect()
+}
+
+fn method_list(methods: &[MethodDescriptor]) -> Vec<Value> {
+    methods
+        .iter()
+        .map(|method| {
+            json!({
+                "name": method.name.as_str(),
+                "service": method.service.as_str(),
+                "input_type": method.input_type,
+                "output_type": method.output_type,
+                "streaming_type": streaming_type_name(&method.streaming_type),
+                "client_streaming": method.client_streaming,
+                "server_streaming": method.server_streaming
+            })
+        })
+        .collect()
+}
+
+fn service_json(service: &ServiceDescriptor) -> Value {
+    let methods: Vec<Value> = service
+        .methods
+        .iter()
+        .map(|method| {
+            json!({
+                "name": method.name.as_str(),
+                "input_type": method.input_type,
+                "output_type": method.output_type,
+                "client_streaming": method.client_streaming,
+                "server_streaming": method.server_streaming,
+                "description": method.description
+            })
+        })
+        .collect();
+
+    json!({
+        "name": service.name.as_str(),
+        "description": service.description,
+        "methods": methods
+    })
+}
+
+fn method_json(method: &MethodDescriptor) -> Value {
+    json!({
+        "name": method.name.as_str(),
+        "service": method.service.as_str(),
+        "input_type": method.input_type,
+        "output_type": method.output_type,
+        "streaming_type": streaming_type_name(&method.streaming_type),
+        "client_streaming": method.client_streaming,
+        "server_streaming": method.server_streaming,
+        "description": method.description,
+        "full_name": format!("{}.{}", method.service.as_str(), method.name.as_str())
+    })
+}
+
+fn message_json(message: &ReflectedMessage) -> Value {
+    let fields: Vec<Value> = message
+        .fields
+        .iter()
+        .map(|field| {
+            json!({
+                "name": field.name,
+                "type": field.field_type,
+                "number": field.number,
+                "optional": field.optional,
+                "repeated": field.repeated
+            })
+        })
+        .collect();
+
+    json!({
+        "name": message.name,
+        "description": message.description,
+        "fields": fields
+    })
+}
+
+#[async_trait]
+impl OutputFormatter for JsonFormatter {
+    async fn format_listing(&self, listing: &Listing<'_>) -> Result<String> {
+        // Lists print one JSON document per entry
+        let values = match listing {
+            Listing::Services(services) => service_list(services),
+            Listing::Methods(methods) => method_list(methods),
+            Listing::Symbol(_) => vec![listing.to_value()],
+        };
+        let mut lines = Vec::with_capacity(values.len());
+        for value in &values {
+            lines.push(self.format_response(value).await?);
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+#[async_trait]
+impl OutputFormatter for TextFormatter {
+    async fn format_listing(&self, listing: &Listing<'_>) -> Result<String> {
+        let mut out = String::new();
+        match listing {
+            Listing::Services(services) => {
+                for service in *services {
+                    writeln!(out, "{}", service.as_str())?;
+                }
+            }
+            Listing::Methods(methods) => {
+                for method in *methods {
+                    let streaming_indicator = match method.streaming_type {
+                        StreamingType::Unary => "",
+                        StreamingType::ServerStream => " (server streaming)",
+                        StreamingType::ClientStream => " (client streaming)",
+                        StreamingType::BiDirectional => " (bidirectional)",
+                    };
+                    writeln!(
+                        out,
+                        "{}.{}{}",
+                        method.service.as_str(),
+                        method.name.as_str(),
+                        streaming_indicator
+                    )?;
+                }
+            }
+            Listing::Symbol(Symbol::Service(service)) => write_service_text(&mut out, service)?,
+            Listing::Symbol(Symbol::Method(method)) => write_method_text(&mut out, method)?,
+            Listing::Symbol(Symbol::Message(message)) => write_message_text(&mut out, message)?,
+        }
+        Ok(out.trim_end().to_string())
+    }
+}
+
+fn write_service_text(out: &mut String, service: &ServiceDescriptor) -> std::fmt::Result {
+    writeln!(out, "service {} {{", service.name.as_str())?;
+
+    if let Some(desc) = &service.description {
+        writeln!(out, "  // {}", desc)?;
+    }
+
+    for method in &service.methods {
+        let streaming_prefix = if method.client_streaming {
+            "stream "
+        } else {
+            ""
+        };
+        let streaming_suffix = if method.server_streaming {
+            " returns (stream response)"
+        } else {
+            " returns (response)"
+        };
+
+        writeln!(
+            out,
+            "  rpc {}({}{}) {};",
+            method.name.as_str(),
+            streaming_prefix,
+            method.input_type,
+            streaming_suffix.replace("response", &method.output_type)
+        )?;
+
+        if let Some(desc) = &method.description {
+            writeln!(out, "    // {}", desc)?;
+        }
+    }
+
+    writeln!(out, "}}")
+}
+
+fn write_method_text(out: &mut String, method: &MethodDescriptor) -> std::fmt::Result {
+    let streaming_info = match (method.client_streaming, method.server_streaming) {
+        (true, true) => " (bidirectional streaming)",
+        (true, false) => " (client streaming)",
+        (false, true) => " (server streaming)",
+        (false, false) => " (unary)",
+    };
+
+    writeln!(
+        out,
+        "Method: {}.{}{}",
+        method.service.as_str(),
+        method.name.as_str(),
+        streaming_info
+    )?;
+    writeln!(out, "  Service: {}", method.service.as_str())?;
+    writeln!(out, "  Input type: {}", method.input_type)?;
+    writeln!(out, "  Output type: {}", method.output_type)?;
+
+    if let Some(desc) = &method.description {
+        writeln!(out, "  Description: {}", desc)?;
+    }
+
+    // Show protobuf-style method signature
+    let streaming_prefix = if method.client_streaming {
+        "stream "
+    } else {
+        ""
+    };
+    let streaming_suffix = if method.server_streaming {
+        "stream "
+    } else {
+        ""
+    };
+
+    writeln!(
+        out,
+        "  Signature: rpc {}({}{}) returns ({}{});",
+        method.name.as_str(),
+        streaming_prefix,
+        method.input_type,
+        streaming_suffix,
+        method.output_type
+    )
+}
+
+fn write_message_text(out: &mut String, message: &ReflectedMessage) -> std::fmt::Result {
+    writeln!(out, "message {} {{", message.name)?;
+
+    if let Some(desc) = &message.description {
+        writeln!(out, "  // {}", desc)?;
+    }
+
+    for field in &message.fields {
+        let field_modifier = if field.repeated {
+            "repeated "
+        } else if field.optional {
+            "optional "
+        } else {
+            ""
+        };
+
+        writeln!(
+            out,
+            "  {}{} {} = {};",
+            field_modifier, field.field_type, field.name, field.number
+        )?;
+    }
+
+    writeln!(out, "}}")
+}
+
+impl OutputFormatter for TableFormatter {}
+
+impl OutputFormatter for CsvFormatter {}
+
+/// Settings a formatter is created with
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    pub format: OutputFormat,
+    /// Colors and bytes encoding for text output
+    pub renderer: TextRenderer,
+    /// Descriptor of the response messages, when known
+    pub descriptor: Option<MessageDescriptor>,
+}
+
+impl FormatOptions {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_renderer(mut self, renderer: TextRenderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn with_descriptor(mut self, descriptor: Option<MessageDescriptor>) -> Self {
+        self.descriptor = descriptor;
+        self
+    }
+}
+
+/// Creates a formatter from the options
+pub type FormatterFactory = Arc<dyn Fn(&FormatOptions) -> Box<dyn OutputFormatter> + Send + Sync>;
+
+/// Output formats by name. The default registry has `json`, `text`, `table`
+/// and `csv`; register a factory to add a format, selected with
+/// [`OutputFormat::Custom`], or to replace a built-in one.
+#[derive(Clone)]
+pub struct FormatterRegistry {
+    factories: HashMap<String, FormatterFactory>,
+}
+
+impl FormatterRegistry {
+    /// A registry without any formats
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&FormatOptions) -> Box<dyn OutputFormatter> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Arc::new(factory));
+    }
+
+    /// Create the formatter for `options.format`
+    pub fn create(&self, options: &FormatOptions) -> Result<Box<dyn OutputFormatter>> {
+        let name = options.format.name();
+        let factory = self.factories.get(name).with_context(|| {
+            format!(
+                "Unknown output format '{}' (available: {})",
+                name,
+                self.names().join(", ")
+            )
+        })?;
+        Ok(factory(options))
+    }
+
+    /// Registered format names, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.factories.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("json", |options| {
+            Box::new(JsonFormatter::from_output_format(&options.format))
+        });
+        registry.register("text", |options| {
+            Box::new(
+                TextFormatter::from_output_format(&options.format)
+                    .with_renderer(options.renderer)
+                    .with_descriptor(options.descriptor.clone()),
+            )
+        });
+        registry.register("table", |options| {
+            Box::new(
+                TableFormatter::new(options.format.columns().to_vec())
+                    .with_descriptor(options.descriptor.clone()),
+            )
+        });
+        registry.register("csv", |options| {
+            Box::new(
+                CsvFormatter::new(options.format.columns().to_vec())
+                    .with_descriptor(options.descriptor.clone()),
+            )
+        });
+        registry
     }
 }
 
-pub fn create_streaming_formatter(format: &OutputFormat) -> Box<dyn StreamingFormatter> {
-    match format {
-        OutputFormat::Json { .. } => Box::new(JsonFormatter::from_output_format(format)),
-        OutputFormat::Text { .. } => Box::new(TextFormatter::from_output_format(format)),
+impl std::fmt::Debug for FormatterRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FormatterRegistry")
+            .field("formats", &self.names())
+            .finish()
     }
 }
 
@@ -272,18 +692,60 @@ mod tests {
         let response = json!({"name": "test", "value": 42});
 
         let formatted = formatter.format_response(&response).await.unwrap();
-        assert!(formatted.contains("test"));
+        assert_eq!(formatted, "name: test\nvalue: 42");
+
+        let formatted = formatter
+            .format_stream_response(&response, 3)
+            .await
+            .unwrap();
+        assert_eq!(formatted, "[3]\nname: test\nvalue: 42");
     }
 
     #[test]
-    fn test_create_formatter() {
+    fn test_registry_creates_formatters() {
+        let registry = FormatterRegistry::default();
+        assert_eq!(registry.names(), vec!["csv", "json", "table", "text"]);
+
         let json_format = OutputFormat::Json {
             pretty: true,
             emit_defaults: false,
         };
-        let _formatter = create_formatter(&json_format);
+        assert!(registry.create(&FormatOptions::new(json_format)).is_ok());
 
-        let text_format = OutputFormat::Text { compact: false };
-        let _formatter = create_formatter(&text_format);
+        let custom = OutputFormat::Custom {
+            name: "yaml".to_string(),
+        };
+        let err = registry
+            .create(&FormatOptions::new(custom.clone()))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("available: csv, json, table, text")
+        );
+
+        let mut registry = registry;
+        registry.register("yaml", |_| Box::new(JsonFormatter::new(false)));
+        assert!(registry.create(&FormatOptions::new(custom)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_format_listing() {
+        let services = [
+            ServiceName::new("a.Foo".to_string()),
+            ServiceName::new("b.Bar".to_string()),
+        ];
+        let listing = Listing::Services(&services);
+
+        let json = JsonFormatter::new(false).format_listing(&listing).await;
+        assert_eq!(json.unwrap(), "{\"name\":\"a.Foo\"}\n{\"name\":\"b.Bar\"}");
+
+        let text = TextFormatter::new(false).format_listing(&listing).await;
+        assert_eq!(text.unwrap(), "a.Foo\nb.Bar");
+
+        let table = TableFormatter::new(Vec::new())
+            .format_listing(&listing)
+            .await;
+        assert_eq!(table.unwrap(), "name\n-----\na.Foo\nb.Bar");
     }
 }
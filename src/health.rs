@@ -0,0 +1,90 @@
+//! Messages of the standard `grpc.health.v1.Health` service. They are defined
+//! here rather than fetched through reflection, so health checks also work
+//! against servers without reflection.
+
+use std::fmt;
+
+/// Path of the `Check` method
+pub const CHECK_PATH: &str = "/grpc.health.v1.Health/Check";
+
+/// Path of the `Watch` method
+pub const WATCH_PATH: &str = "/grpc.health.v1.Health/Watch";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HealthCheckRequest {
+    /// Service to check; empty for the server as a whole
+    #[prost(string, tag = "1")]
+    pub service: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HealthCheckResponse {
+    #[prost(enumeration = "ServingStatus", tag = "1")]
+    pub status: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ServingStatus {
+    Unknown = 0,
+    Serving = 1,
+    NotServing = 2,
+    /// Only sent by `Watch`, for services the server doesn't know
+    ServiceUnknown = 3,
+}
+
+impl ServingStatus {
+    /// Process exit code for `grpc-client health`: 0 for SERVING, 2 for
+    /// NOT_SERVING and 3 for UNKNOWN or SERVICE_UNKNOWN. Failed calls exit
+    /// with 1.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Serving => 0,
+            Self::NotServing => 2,
+            Self::Unknown | Self::ServiceUnknown => 3,
+        }
+    }
+}
+
+impl fmt::Display for ServingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Unknown => "UNKNOWN",
+            Self::Serving => "SERVING",
+            Self::NotServing => "NOT_SERVING",
+            Self::ServiceUnknown => "SERVICE_UNKNOWN",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_health_messages_round_trip() {
+        let request = HealthCheckRequest {
+            service: "example.UserService".to_string(),
+        };
+        let decoded = HealthCheckRequest::decode(request.encode_to_vec().as_slice()).unwrap();
+        assert_eq!(decoded, request);
+
+        let response = HealthCheckResponse::decode([0x08, 0x02].as_slice()).unwrap();
+        assert_eq!(response.status(), ServingStatus::NotServing);
+        assert_eq!(response.status().to_string(), "NOT_SERVING");
+
+        // Unknown values fall back to UNKNOWN
+        let response = HealthCheckResponse::decode([0x08, 0x09].as_slice()).unwrap();
+        assert_eq!(response.status(), ServingStatus::Unknown);
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(ServingStatus::Serving.exit_code(), 0);
+        assert_eq!(ServingStatus::NotServing.exit_code(), 2);
+        assert_eq!(ServingStatus::Unknown.exit_code(), 3);
+        assert_eq!(ServingStatus::ServiceUnknown.exit_code(), 3);
+    }
+}
@@ -0,0 +1,296 @@
+use anyhow::{Result, bail};
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// A JSONPath expression over decoded responses.
+///
+/// Supports the common subset: `$` (optional), `.field`, `['field']`,
+/// `[index]` (negative counts from the end), `[*]` / `.*` wildcards and
+/// `..field` recursive descent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(i64),
+    Wildcard,
+    Descendant(String),
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self> {
+        let source = path.trim();
+        let rooted = source.starts_with('$');
+        let mut rest = source.strip_prefix('$').unwrap_or(source);
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let (name, after) = take_identifier(after);
+                if name.is_empty() {
+                    bail!("Expected a field name after '..' in path: {}", source);
+                }
+                segments.push(Segment::Descendant(name.to_string()));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(".*") {
+                segments.push(Segment::Wildcard);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let (name, after) = take_identifier(after);
+                if name.is_empty() {
+                    bail!("Expected a field name after '.' in path: {}", source);
+                }
+                segments.push(Segment::Field(name.to_string()));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let Some(end) = after.find(']') else {
+                    bail!("Unclosed '[' in path: {}", source);
+                };
+                segments.push(parse_bracket(after[..end].trim(), source)?);
+                rest = &after[end + 1..];
+            } else if !rooted && segments.is_empty() {
+                // Allow a bare leading field name, e.g. `user.name`
+                let (name, after) = take_identifier(rest);
+                if name.is_empty() {
+                    bail!("Invalid path: {}", source);
+                }
+                segments.push(Segment::Field(name.to_string()));
+                rest = after;
+            } else {
+                bail!("Unexpected '{}' in path: {}", rest, source);
+            }
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    /// All values in `root` matched by this path
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        self.select_entries(root)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// JSON pointers (RFC 6901) of all values matched by this path, for use
+    /// with [`Value::pointer_mut`]
+    pub fn select_pointers(&self, root: &Value) -> Vec<String> {
+        self.select_entries(root)
+            .into_iter()
+            .map(|(pointer, _)| pointer)
+            .collect()
+    }
+
+    fn select_entries<'a>(&self, root: &'a Value) -> Vec<(String, &'a Value)> {
+        let mut current = vec![(String::new(), root)];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for (pointer, value) in current {
+                match segment {
+                    Segment::Field(name) => {
+                        if let Some(child) = value.get(name) {
+                            next.push((child_pointer(&pointer, name), child));
+                        }
+                    }
+                    Segment::Index(index) => {
+                        if let Value::Array(items) = value {
+                            let index = if *index < 0 {
+                                items.len() as i64 + index
+                            } else {
+                                *index
+                            };
+                            if let Some((i, item)) = usize::try_from(index)
+                                .ok()
+                                .and_then(|i| Some((i, items.get(i)?)))
+                            {
+                                next.push((child_pointer(&pointer, &i.to_string()), item));
+                            }
+                        }
+                    }
+                    Segment::Wildcard => match value {
+                        Value::Array(items) => next.extend(
+                            items
+                                .iter()
+                                .enumerate()
+                                .map(|(i, item)| (child_pointer(&pointer, &i.to_string()), item)),
+                        ),
+                        Value::Object(map) => next.extend(
+                            map.iter()
+                                .map(|(key, child)| (child_pointer(&pointer, key), child)),
+                        ),
+                        _ => {}
+                    },
+                    Segment::Descendant(name) => {
+                        collect_descendants(&pointer, value, name, &mut next)
+                    }
+                }
+            }
+            current = next;
+        }
+        current
+    }
+
+    /// The first value matched by this path
+    pub fn select_first<'a>(&self, root: &'a Value) -> Option<&'a Value> {
+        self.select(root).into_iter().next()
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn take_identifier(input: &str) -> (&str, &str) {
+    let end = input
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(input.len());
+    input.split_at(end)
+}
+
+fn parse_bracket(inner: &str, source: &str) -> Result<Segment> {
+    if inner == "*" {
+        return Ok(Segment::Wildcard);
+    }
+    for quote in ['\'', '"'] {
+        if let Some(name) = inner
+            .strip_prefix(quote)
+            .and_then(|name| name.strip_suffix(quote))
+        {
+            return Ok(Segment::Field(name.to_string()));
+        }
+    }
+    match inner.parse() {
+        Ok(index) => Ok(Segment::Index(index)),
+        Err(_) => bail!("Invalid index '[{}]' in path: {}", inner, source),
+    }
+}
+
+fn child_pointer(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn collect_descendants<'a>(
+    pointer: &str,
+    value: &'a Value,
+    name: &str,
+    out: &mut Vec<(String, &'a Value)>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_pointer = child_pointer(pointer, key);
+                if key == name {
+                    out.push((child_pointer.clone(), child));
+                }
+                collect_descendants(&child_pointer, child, name, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_descendants(&child_pointer(pointer, &i.to_string()), item, name, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn users() -> Value {
+        json!({
+            "users": [
+                {"id": "1", "name": "John", "address": {"city": "Berlin"}},
+                {"id": "2", "name": "Jane", "address": {"city": "Paris"}}
+            ],
+            "page-token": "abc"
+        })
+    }
+
+    fn select(path: &str, value: &Value) -> Vec<Value> {
+        JsonPath::parse(path)
+            .unwrap()
+            .select(value)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_fields_and_indexes() {
+        let value = users();
+        assert_eq!(select("$.users[0].name", &value), vec![json!("John")]);
+        assert_eq!(select("users[-1].id", &value), vec![json!("2")]);
+        assert_eq!(select("$['page-token']", &value), vec![json!("abc")]);
+        assert_eq!(select("$.page-token", &value), vec![json!("abc")]);
+        assert_eq!(select("$", &value), vec![value.clone()]);
+        assert!(select("$.users[5]", &value).is_empty());
+        assert!(select("$.missing.field", &value).is_empty());
+    }
+
+    #[test]
+    fn test_wildcards_and_descendants() {
+        let value = users();
+        assert_eq!(
+            select("$.users[*].name", &value),
+            vec![json!("John"), json!("Jane")]
+        );
+        assert_eq!(select("$.users[0].*", &value).len(), 3);
+        assert_eq!(
+            select("$..city", &value),
+            vec![json!("Berlin"), json!("Paris")]
+        );
+    }
+
+    #[test]
+    fn test_select_pointers() {
+        let value = users();
+        let pointers = JsonPath::parse("$..city").unwrap().select_pointers(&value);
+        assert_eq!(
+            pointers,
+            vec!["/users/0/address/city", "/users/1/address/city"]
+        );
+        for pointer in &pointers {
+            assert!(value.pointer(pointer).is_some());
+        }
+
+        let pointers = JsonPath::parse("$['page-token']")
+            .unwrap()
+            .select_pointers(&value);
+        assert_eq!(pointers, vec!["/page-token"]);
+        assert_eq!(
+            JsonPath::parse("$.users[-1]")
+                .unwrap()
+                .select_pointers(&value),
+            vec!["/users/1"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        assert!(JsonPath::parse("$.users[").is_err());
+        assert!(JsonPath::parse("$.users[abc]").is_err());
+        assert!(JsonPath::parse("$.").is_err());
+        assert!(JsonPath::parse("$users").is_err());
+    }
+}
@@ -1,12 +1,29 @@
 mod codec;
 
+pub mod auth;
+pub mod bench;
 pub mod cli;
 pub mod client;
+pub mod collection;
 pub mod connection;
+pub mod diff;
 pub mod domain;
 pub mod format;
+pub mod health;
+pub mod jsonpath;
+pub mod live;
+pub mod metadata;
+pub mod profile;
+pub mod query;
+pub mod redact;
 pub mod reflection;
+pub mod render;
+pub mod retry;
 pub mod server;
+pub mod table;
+pub mod template;
+pub mod test_runner;
+pub mod workflow;
 
 // Re-export main types for convenience
 pub use cli::{Cli, Command, FormatType};
@@ -0,0 +1,249 @@
+//! Calls whose messages are exchanged while they run, so responses can be
+//! shown as they arrive and client-stream messages sent as they are typed.
+
+use crate::client::{GrpcClient, decode_response_message, parse_request_message};
+use crate::codec::BytesCodec;
+use crate::metadata::metadata_to_pairs;
+use crate::reflection::StreamingType;
+use anyhow::{Context, Result, anyhow};
+use bytes::Bytes;
+use http::uri::PathAndQuery;
+use prost::Message;
+use prost_reflect::MessageDescriptor;
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Request, Response, Status, Streaming, client::Grpc, transport::Channel};
+
+/// Number of requests and events buffered between the caller and the call
+const CHANNEL_BUFFER: usize = 16;
+
+/// Something that happened during a live call
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallEvent {
+    /// Response headers sent by the server
+    Headers(Vec<(String, String)>),
+    /// A decoded response message, with sensitive fields masked
+    Response(Value),
+    /// The call ended; always the last event
+    Finished(CallStatus),
+}
+
+/// Final status of a call
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallStatus {
+    pub code: Code,
+    pub message: String,
+    pub trailers: Vec<(String, String)>,
+}
+
+impl CallStatus {
+    /// Status of a call cancelled by the caller
+    pub fn cancelled() -> Self {
+        Self {
+            code: Code::Cancelled,
+            message: "Call cancelled".to_string(),
+            trailers: Vec::new(),
+        }
+    }
+
+    fn ok(trailers: Vec<(String, String)>) -> Self {
+        Self {
+            code: Code::Ok,
+            message: String::new(),
+            trailers,
+        }
+    }
+}
+
+impl From<&Status> for CallStatus {
+    fn from(status: &Status) -> Self {
+        Self {
+            code: status.code(),
+            message: status.message().to_string(),
+            trailers: metadata_to_pairs(status.metadata()),
+        }
+    }
+}
+
+/// A running call, created with [`GrpcClient::start_call`]. Dropping it
+/// cancels the call.
+pub struct LiveCall {
+    pub streaming_type: StreamingType,
+    input_descriptor: MessageDescriptor,
+    requests: Option<mpsc::Sender<Bytes>>,
+    events: mpsc::Receiver<CallEvent>,
+    task: JoinHandle<()>,
+}
+
+impl LiveCall {
+    pub(crate) fn start(
+        client: GrpcClient,
+        channel: Channel,
+        path: PathAndQuery,
+        streaming_type: StreamingType,
+        input_descriptor: MessageDescriptor,
+        output_descriptor: MessageDescriptor,
+    ) -> Self {
+        let (request_tx, request_rx) = mpsc::channel(CHANNEL_BUFFER);
+        let (event_tx, events) = mpsc::channel(CHANNEL_BUFFER);
+        let call = CallTask {
+            client,
+            path,
+            output_descriptor,
+            events: event_tx,
+        };
+
+        let call_type = streaming_type.clone();
+        let task = tokio::spawn(async move {
+            let status = call
+                .run(channel, call_type, request_rx)
+                .await
+                .unwrap_or_else(|status| CallStatus::from(&status));
+            call.emit(CallEvent::Finished(status)).await;
+        });
+
+        Self {
+            streaming_type,
+            input_descriptor,
+            requests: Some(request_tx),
+            events,
+            task,
+        }
+    }
+
+    /// Descriptor of the request messages
+    pub fn input_descriptor(&self) -> &MessageDescriptor {
+        &self.input_descriptor
+    }
+
+    /// Send a request message. Unary and server-streaming calls take a single
+    /// request and start once it arrives.
+    pub async fn send(&self, request: Value) -> Result<()> {
+        let message = parse_request_message(request, self.input_descriptor.clone())?;
+        self.requests
+            .as_ref()
+            .context("The request stream is closed")?
+            .send(Bytes::from(message.encode_to_vec()))
+            .await
+            .map_err(|_| anyhow!("The call no longer accepts requests"))
+    }
+
+    /// Half-close the call: no more requests follow. A unary or
+    /// server-streaming call closed before any request sends an empty one.
+    pub fn close_send(&mut self) {
+        self.requests = None;
+    }
+
+    /// Next event of the call, `None` after [`CallEvent::Finished`]
+    pub async fn next_event(&mut self) -> Option<CallEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for LiveCall {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct CallTask {
+    client: GrpcClient,
+    path: PathAndQuery,
+    output_descriptor: MessageDescriptor,
+    events: mpsc::Sender<CallEvent>,
+}
+
+impl CallTask {
+    async fn run(
+        &self,
+        channel: Channel,
+        streaming_type: StreamingType,
+        mut requests: mpsc::Receiver<Bytes>,
+    ) -> Result<CallStatus, Status> {
+        let mut client = Grpc::new(channel);
+        client
+            .ready()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        let path = self.path.clone();
+
+        match streaming_type {
+            StreamingType::Unary => {
+                let first = requests.recv().await.unwrap_or_default();
+                drop(requests);
+                let request = self.request(first).await?;
+                let response = client.unary(request, path, BytesCodec).await?;
+                self.single(response).await
+            }
+            StreamingType::ServerStream => {
+                let first = requests.recv().await.unwrap_or_default();
+                drop(requests);
+                let request = self.request(first).await?;
+                let response = client.server_streaming(request, path, BytesCodec).await?;
+                self.stream(response).await
+            }
+            StreamingType::ClientStream => {
+                let request = self.request(ReceiverStream::new(requests)).await?;
+                let response = client.client_streaming(request, path, BytesCodec).await?;
+                self.single(response).await
+            }
+            StreamingType::BiDirectional => {
+                let request = self.request(ReceiverStream::new(requests)).await?;
+                let response = client.streaming(request, path, BytesCodec).await?;
+                self.stream(response).await
+            }
+        }
+    }
+
+    async fn request<T>(&self, body: T) -> Result<Request<T>, Status> {
+        self.client
+            .create_grpc_request_with_headers(body)
+            .await
+            .map_err(internal)
+    }
+
+    async fn single(&self, response: Response<Bytes>) -> Result<CallStatus, Status> {
+        let (metadata, body, _) = response.into_parts();
+        self.emit(CallEvent::Headers(metadata_to_pairs(&metadata)))
+            .await;
+        let response = self.decode(&body).map_err(internal)?;
+        self.emit(CallEvent::Response(response)).await;
+        Ok(CallStatus::ok(Vec::new()))
+    }
+
+    async fn stream(&self, response: Response<Streaming<Bytes>>) -> Result<CallStatus, Status> {
+        let (metadata, mut stream, _) = response.into_parts();
+        self.emit(CallEvent::Headers(metadata_to_pairs(&metadata)))
+            .await;
+
+        while let Some(message) = stream.message().await? {
+            let response = self.decode(&message).map_err(internal)?;
+            self.emit(CallEvent::Response(response)).await;
+        }
+
+        let trailers = stream.trailers().await?;
+        Ok(CallStatus::ok(
+            trailers.map(|t| metadata_to_pairs(&t)).unwrap_or_default(),
+        ))
+    }
+
+    /// Decode a response and mask its sensitive fields
+    fn decode(&self, bytes: &[u8]) -> Result<Value> {
+        let mut value = decode_response_message(bytes, self.output_descriptor.clone())?;
+        if let Some(redactor) = &self.client.redactor {
+            redactor.redact(&mut value, Some(&self.output_descriptor));
+        }
+        Ok(value)
+    }
+
+    /// Deliver an event; dropped when the [`LiveCall`] is gone
+    async fn emit(&self, event: CallEvent) {
+        let _ = self.events.send(event).await;
+    }
+}
+
+fn internal(e: anyhow::Error) -> Status {
+    Status::internal(format!("{:#}", e))
+}
@@ -1,23 +1,41 @@
-use std::{fs, io::Read as _};
+use std::{fs, io::Read as _, path::PathBuf, time::Duration};
 
-use anyhow::{Context as _, Result};
+use anyhow::{Context as _, Result, anyhow, bail};
+use chrono::{Local, SecondsFormat};
 use clap::Parser;
 use grpc_client::{
     OutputFormat,
+    bench::{BenchConfig, run_bench},
     cli::{Cli, Command},
+    client::CallOutcome,
     client::GrpcClient,
-    reflection::{StreamingType, Symbol},
-    server::start_server,
+    collection::{default_collections_dir, load_saved_request},
+    diff::diff_values,
+    format::{FormatOptions, FormatterRegistry, Listing},
+    health::ServingStatus,
+    profile::{ServerResolver, resolve_context},
+    query::Query,
+    render::TextRenderer,
+    server::{history::default_history_path, start_server},
+    template::TemplateEngine,
+    test_runner::run_test_files,
+    workflow::{load_workflow, run_workflow},
 };
-use serde_json::Value;
+use prost_reflect::MessageDescriptor;
+use serde_json::{Value, json};
+use tokio::time::MissedTickBehavior;
+use tonic::{Code, Status};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let cli = resolve_context(Cli::parse())?;
 
     match &cli.command {
         Command::List { service, .. } => {
             let client = GrpcClient::from_cli(&cli)?;
+            let formatter = client
+                .formatters
+                .create(&FormatOptions::new(client.format.clone()))?;
 
             match service {
                 Some(name) => {
@@ -25,115 +43,56 @@ async fn main() -> Result<()> {
                     if client.verbose {
                         println!("Methods for service '{}':", name);
                     }
-
-                    for method in methods {
-                        match &client.format {
-                            OutputFormat::Json { pretty, .. } => {
-                                let streaming_type_str = match method.streaming_type {
-                                    StreamingType::Unary => "unary",
-                                    StreamingType::ServerStream => "server_streaming",
-                                    StreamingType::ClientStream => "client_streaming",
-                                    StreamingType::BiDirectional => "bidirectional",
-                                };
-
-                                let json = if *pretty {
-                                    serde_json::to_string_pretty(&serde_json::json!({
-                                        "name": method.name.as_str(),
-                                        "service": method.service.as_str(),
-                                        "input_type": method.input_type,
-                                        "output_type": method.output_type,
-                                        "streaming_type": streaming_type_str,
-                                        "client_streaming": method.client_streaming,
-                                        "server_streaming": method.server_streaming
-                                    }))?
-                                } else {
-                                    serde_json::to_string(&serde_json::json!({
-                                        "name": method.name.as_str(),
-                                        "service": method.service.as_str(),
-                                        "input_type": method.input_type,
-                                        "output_type": method.output_type,
-                                        "streaming_type": streaming_type_str,
-                                        "client_streaming": method.client_streaming,
-                                        "server_streaming": method.server_streaming
-                                    }))?
-                                };
-                                println!("{}", json);
-                            }
-                            OutputFormat::Text { .. } => {
-                                let streaming_indicator = match method.streaming_type {
-                                    StreamingType::Unary => "",
-                                    StreamingType::ServerStream => " (server streaming)",
-                                    StreamingType::ClientStream => " (client streaming)",
-                                    StreamingType::BiDirectional => " (bidirectional)",
-                                };
-                                println!(
-                                    "{}.{}{}",
-                                    method.service.as_str(),
-                                    method.name.as_str(),
-                                    streaming_indicator
-                                );
-                            }
-                        }
-                    }
+                    print_output(
+                        formatter
+                            .format_listing(&Listing::Methods(&methods))
+                            .await?,
+                    );
                 }
                 None => {
                     let services = client.handle_service_list().await?;
                     if client.verbose {
                         println!("Available services:");
                     }
-
-                    for service in services {
-                        match &client.format {
-                            OutputFormat::Json { pretty, .. } => {
-                                let json = if *pretty {
-                                    serde_json::to_string_pretty(&serde_json::json!({
-                                        "name": service.as_str()
-                                    }))?
-                                } else {
-                                    serde_json::to_string(&serde_json::json!({
-                                        "name": service.as_str()
-                                    }))?
-                                };
-                                println!("{}", json);
-                            }
-                            OutputFormat::Text { .. } => {
-                                println!("{}", service.as_str());
-                            }
-                        }
-                    }
+                    print_output(
+                        formatter
+                            .format_listing(&Listing::Services(&services))
+                            .await?,
+                    );
                 }
             }
             Ok(())
         }
-        Command::Describe { symbol, .. } => {
+        Command::Describe {
+            symbol: Some(symbol),
+            ..
+        } => {
             if cli.verbose {
                 println!("Describing symbol: {}", symbol);
             }
 
             let client = GrpcClient::from_cli(&cli)?;
+            let formatter = client
+                .formatters
+                .create(&FormatOptions::new(client.format.clone()))?;
 
             let symbol = client.handle_describe(symbol).await?;
-
-            match symbol {
-                Symbol::Service(service_desc) => {
-                    client.format_service_description(&service_desc).await?;
-                }
-                Symbol::Method(method_desc) => {
-                    client.format_method_description(&method_desc).await?;
-                }
-                Symbol::Message(message_desc) => {
-                    client.format_message_description(&message_desc).await?;
-                }
-            }
+            print_output(formatter.format_listing(&Listing::Symbol(&symbol)).await?);
             Ok(())
         }
         Command::Call {
-            method,
+            method: Some(method),
             data,
             emit_defaults,
+            repeat,
+            watch,
+            interval,
+            diff,
+            query,
             ..
         } => {
             let client = GrpcClient::from_cli(&cli)?;
+            let query = query.as_deref().map(Query::parse).transpose()?;
             if cli.verbose {
                 // println!("Calling method: {}.{}", service_name, method_name);
                 println!("Endpoint: {}", client.endpoint);
@@ -146,23 +105,328 @@ async fn main() -> Result<()> {
             } else {
                 client.format.clone()
             };
-            let data = parse_request_data(data.as_deref())?;
-            let ret = client.handle_call(method, data).await?;
-            for response in ret {
-                format_call_response(&response, &format)?;
+            let template = TemplateEngine::from_args(&cli.vars, cli.vars_file.as_deref())?;
+            let data = parse_request_data(data.as_deref(), &template)?;
+
+            let printer = ResponsePrinter {
+                format,
+                query,
+                renderer: TextRenderer::new(cli.color.enabled(), cli.bytes),
+                formatters: client.formatters.clone(),
+            };
+
+            if *watch || repeat.is_some() {
+                let schedule = RepeatSchedule {
+                    count: if *watch { None } else { *repeat },
+                    interval: *interval,
+                    diff: *diff,
+                };
+                return run_repeated_call(&client, method, data, &printer, &schedule).await;
+            }
+
+            let outcome = client.execute_call(method, data).await?;
+            printer.print_outcome(&outcome).await?;
+
+            Ok(())
+        }
+        Command::Health { service, watch, .. } => {
+            let client = GrpcClient::from_cli(&cli)?;
+            let service = service.as_deref().unwrap_or_default();
+            let status = if *watch {
+                watch_health(&client, service).await?
+            } else {
+                let status = check_health(&client, service).await?;
+                print_health(&client.format, service, status, None)?;
+                status
+            };
+
+            if status != ServingStatus::Serving {
+                std::process::exit(status.exit_code());
+            }
+            Ok(())
+        }
+        Command::Bench {
+            method: Some(method),
+            data,
+            total,
+            duration,
+            concurrency,
+            rps,
+            connections,
+            ..
+        } => {
+            let client = GrpcClient::from_cli(&cli)?;
+            let template = TemplateEngine::from_args(&cli.vars, cli.vars_file.as_deref())?;
+            let data = parse_request_data(data.as_deref(), &template)?;
+            let config = BenchConfig {
+                total: *total,
+                duration: *duration,
+                concurrency: *concurrency,
+                rps: *rps,
+                connections: *connections,
+            };
+            if cli.verbose {
+                println!("Endpoint: {}", client.endpoint);
+            }
+
+            let report = run_bench(&client, method, data, &config).await?;
+            match &client.format {
+                OutputFormat::Json { .. } => {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                _ => print!("{}", report.to_text()),
+            }
+
+            Ok(())
+        }
+        Command::Test { files, junit } => {
+            let template = TemplateEngine::from_args(&cli.vars, cli.vars_file.as_deref())?;
+            let mut resolver = ServerResolver::new(cli.clone())?;
+            let report = run_test_files(files, &mut resolver, &template).await?;
+            print!("{}", report.to_text());
+
+            if let Some(path) = junit {
+                fs::write(path, report.to_junit_xml())
+                    .with_context(|| format!("Failed to write JUnit report: {}", path))?;
+            }
+            if report.failed() > 0 {
+                bail!(
+                    "{} of {} tests failed",
+                    report.failed(),
+                    report.results.len()
+                );
+            }
+
+            Ok(())
+        }
+        Command::Run { file } => {
+            let template = TemplateEngine::from_args(&cli.vars, cli.vars_file.as_deref())?;
+            let mut resolver = ServerResolver::new(cli.clone())?;
+            let workflow = load_workflow(file)?;
+            let report = run_workflow(&workflow, &mut resolver, &template).await?;
+            print!("{}", report.to_text(cli.verbose));
+
+            if !report.passed() {
+                bail!("Workflow '{}' failed", report.name);
+            }
+
+            Ok(())
+        }
+        Command::RunSaved {
+            saved,
+            collections,
+            query,
+        } => {
+            let dir = collections
+                .as_deref()
+                .map(PathBuf::from)
+                .or_else(default_collections_dir)
+                .context("Could not determine the collections directory. Pass --collections")?;
+            let template = TemplateEngine::from_args(&cli.vars, cli.vars_file.as_deref())?;
+            let saved = load_saved_request(&dir, saved)?.render(&template)?;
+            let headers: Vec<(String, String)> = saved.headers.into_iter().collect();
+            let client = ServerResolver::new(cli.clone())?
+                .client(saved.server.as_deref())?
+                .with_headers(headers);
+            if cli.verbose {
+                println!("Endpoint: {}", client.endpoint);
             }
 
+            let printer = ResponsePrinter {
+                format: client.format.clone(),
+                query: query.as_deref().map(Query::parse).transpose()?,
+                renderer: TextRenderer::new(cli.color.enabled(), cli.bytes),
+                formatters: client.formatters.clone(),
+            };
+            let outcome = client.execute_call(&saved.method, saved.request).await?;
+            printer.print_outcome(&outcome).await?;
+
             Ok(())
         }
         Command::Server {
             port,
+            bind,
             config,
             ui_path,
-        } => start_server(*port, config, ui_path).await,
+            history,
+            no_history,
+            collections,
+        } => {
+            let history = match history {
+                Some(path) => Some(PathBuf::from(path)),
+                None if *no_history => None,
+                None => default_history_path(),
+            };
+            let collections = collections
+                .as_deref()
+                .map(PathBuf::from)
+                .or_else(default_collections_dir);
+            start_server(
+                bind.as_deref(),
+                *port,
+                config,
+                ui_path,
+                history.as_deref(),
+                collections.as_deref(),
+                !cli.no_redact,
+            )
+            .await
+        }
+        Command::Describe { symbol: None, .. }
+        | Command::Call { method: None, .. }
+        | Command::Bench { method: None, .. } => {
+            unreachable!("positional arguments are validated by resolve_context")
+        }
     }
 }
 
-fn parse_request_data(data: Option<&str>) -> Result<Value> {
+/// How often `call --repeat`/`--watch` re-invokes a method
+struct RepeatSchedule {
+    /// Number of calls, or `None` to run until interrupted
+    count: Option<usize>,
+    interval: Duration,
+    /// Print only the changes from the previous response
+    diff: bool,
+}
+
+/// Re-invoke a method on a schedule, reusing the client's cached channel and
+/// descriptor pool, and print each result with a timestamp. Failed calls are
+/// reported and polling continues.
+async fn run_repeated_call(
+    client: &GrpcClient,
+    method: &str,
+    data: Value,
+    printer: &ResponsePrinter,
+    schedule: &RepeatSchedule,
+) -> Result<()> {
+    let mut ticker = tokio::time::interval(schedule.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut previous: Option<Value> = None;
+    let mut iteration = 0;
+
+    while schedule.count.is_none_or(|count| iteration < count) {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        iteration += 1;
+
+        let timestamp = Local::now().to_rfc3339_opts(SecondsFormat::Millis, false);
+        let outcome = match client.execute_call(method, data.clone()).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                eprintln!("[{}] #{} error: {:#}", timestamp, iteration, e);
+                continue;
+            }
+        };
+        println!("[{}] #{}", timestamp, iteration);
+
+        // Project before comparing so diffs only cover the queried values
+        let streaming = outcome.streaming_type.is_server_streaming();
+        let (responses, descriptor) = match &printer.query {
+            Some(query) => (query.apply_all(&outcome.responses), None),
+            None => (outcome.responses, outcome.output_descriptor),
+        };
+
+        // Single responses are compared directly, streams as an array
+        let current = match <[Value; 1]>::try_from(responses) {
+            Ok([response]) => response,
+            Err(responses) => Value::Array(responses),
+        };
+
+        match previous.as_ref().filter(|_| schedule.diff) {
+            Some(previous) => {
+                let changes = diff_values(previous, &current);
+                if changes.is_empty() {
+                    println!("(no changes)");
+                }
+                for change in changes {
+                    println!("{}", change);
+                }
+            }
+            None => {
+                let responses = match &current {
+                    Value::Array(responses) => responses.as_slice(),
+                    response => std::slice::from_ref(response),
+                };
+                printer
+                    .print_projected(responses, descriptor.as_ref(), streaming)
+                    .await?;
+            }
+        }
+        previous = Some(current);
+    }
+
+    Ok(())
+}
+
+/// Check a service once. Services the server doesn't know are reported as
+/// SERVICE_UNKNOWN rather than as a failed call.
+async fn check_health(client: &GrpcClient, service: &str) -> Result<ServingStatus> {
+    match client.health_check(service).await {
+        Ok(status) => Ok(status),
+        Err(status) if status.code() == Code::NotFound => Ok(ServingStatus::ServiceUnknown),
+        Err(status) => Err(health_error(status)),
+    }
+}
+
+/// Print the status of a service each time it changes, until the server ends
+/// the stream or the user interrupts. Returns the last status.
+async fn watch_health(client: &GrpcClient, service: &str) -> Result<ServingStatus> {
+    let mut stream = client.health_watch(service).await.map_err(health_error)?;
+    let mut last = None;
+
+    loop {
+        let message = tokio::select! {
+            message = stream.message() => message.map_err(health_error)?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        let Some(response) = message else {
+            break;
+        };
+
+        let status = response.status();
+        if last != Some(status) {
+            let timestamp = Local::now().to_rfc3339_opts(SecondsFormat::Millis, false);
+            print_health(&client.format, service, status, Some(&timestamp))?;
+            last = Some(status);
+        }
+    }
+
+    last.context("Health watch ended before the server reported a status")
+}
+
+fn health_error(status: Status) -> anyhow::Error {
+    match status.code() {
+        Code::Unimplemented => anyhow!("Server does not implement grpc.health.v1.Health"),
+        code => anyhow!("Health check failed: {:?}: {}", code, status.message()),
+    }
+}
+
+/// Print a health status, as one JSON document per line in JSON format
+fn print_health(
+    format: &OutputFormat,
+    service: &str,
+    status: ServingStatus,
+    timestamp: Option<&str>,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json { .. } => {
+            let mut value = json!({"service": service, "status": status.to_string()});
+            if let Some(timestamp) = timestamp {
+                value["timestamp"] = json!(timestamp);
+            }
+            println!("{}", serde_json::to_string(&value)?);
+        }
+        _ => match timestamp {
+            Some(timestamp) => println!("[{}] {}", timestamp, status),
+            None => println!("{}", status),
+        },
+    }
+    Ok(())
+}
+
+fn parse_request_data(data: Option<&str>, template: &TemplateEngine) -> Result<Value> {
     let request_json = match data {
         Some("@-") => {
             // Read from stdin
@@ -193,64 +457,73 @@ fn parse_request_data(data: Option<&str>) -> Result<Value> {
     let data = serde_json::from_str::<serde_json::Value>(&request_json)
         .with_context(|| format!("Invalid JSON in request: {}", request_json))?;
 
-    Ok(data)
+    template
+        .render_value(data)
+        .context("Failed to expand request templates")
 }
 
-fn format_call_response(v: &Value, format: &OutputFormat) -> Result<()> {
-    match format {
-        OutputFormat::Json { pretty, .. } => {
-            if *pretty {
-                // Response is already pretty-printed
-                println!("{}", serde_json::to_string_pretty(&v)?);
-            } else {
-                println!("{}", serde_json::to_string(&v)?);
+/// Output settings for call responses
+struct ResponsePrinter {
+    format: OutputFormat,
+    query: Option<Query>,
+    renderer: TextRenderer,
+    formatters: FormatterRegistry,
+}
+
+impl ResponsePrinter {
+    /// Print the responses of a call, projected through the query when given
+    async fn print_outcome(&self, outcome: &CallOutcome) -> Result<()> {
+        let streaming = outcome.streaming_type.is_server_streaming();
+        match &self.query {
+            Some(query) => {
+                self.print_projected(&query.apply_all(&outcome.responses), None, streaming)
+                    .await
+            }
+            None => {
+                self.print_projected(
+                    &outcome.responses,
+                    outcome.output_descriptor.as_ref(),
+                    streaming,
+                )
+                .await
             }
-        }
-        OutputFormat::Text { .. } => {
-            // Parse and display in text format
-            println!("Response:");
-            print_json_as_text(v, 0);
         }
     }
 
-    Ok(())
-}
-fn print_json_as_text(value: &Value, indent: usize) {
-    let indent_str = "  ".repeat(indent);
-
-    match value {
-        Value::Object(map) => {
-            for (key, val) in map {
-                match val {
-                    Value::Object(_) | Value::Array(_) => {
-                        println!("{}{}:", indent_str, key);
-                        print_json_as_text(val, indent + 1);
-                    }
-                    _ => {
-                        println!("{}{}: {}", indent_str, key, format_json_value(val));
-                    }
-                }
-            }
-        }
-        Value::Array(arr) => {
-            for (i, val) in arr.iter().enumerate() {
-                println!("{}[{}]:", indent_str, i);
-                print_json_as_text(val, indent + 1);
+    /// Print values that the query was already applied to. Responses of
+    /// streaming methods are numbered and framed by the formatter's stream
+    /// start and completion output.
+    async fn print_projected(
+        &self,
+        values: &[Value],
+        descriptor: Option<&MessageDescriptor>,
+        streaming: bool,
+    ) -> Result<()> {
+        let options = FormatOptions::new(self.format.clone())
+            .with_renderer(self.renderer)
+            .with_descriptor(descriptor.cloned());
+        let formatter = self.formatters.create(&options)?;
+
+        if !streaming {
+            for v in values {
+                print_output(formatter.format_response(v).await?);
             }
+            return Ok(());
         }
-        _ => {
-            println!("{}{}", indent_str, format_json_value(value));
+
+        print_output(formatter.format_stream_start().await?);
+        for (i, v) in values.iter().enumerate() {
+            print_output(formatter.format_stream_response(v, i + 1).await?);
         }
+        print_output(formatter.format_stream_complete(values.len()).await?);
+        Ok(())
     }
 }
 
-fn format_json_value(value: &Value) -> String {
-    match value {
-        Value::String(s) => s.clone(),
-        Value::Number(n) => n.to_string(),
-        Value::Bool(b) => b.to_string(),
-        Value::Null => "null".to_string(),
-        _ => value.to_string(),
+/// Print formatter output, skipping formats that print nothing at this point
+fn print_output(output: String) {
+    if !output.is_empty() {
+        println!("{}", output);
     }
 }
 
@@ -259,42 +532,59 @@ mod tests {
     use serde_json::json;
 
     use super::*;
+    use grpc_client::reflection::StreamingType;
 
     #[tokio::test]
     async fn test_parse_request_data_json_string() {
-        let result = parse_request_data(Some(r#"{"name": "test"}"#)).unwrap();
+        let result =
+            parse_request_data(Some(r#"{"name": "test"}"#), &TemplateEngine::new()).unwrap();
         assert_eq!(result, json!({"name": "test"}));
     }
 
     #[tokio::test]
     async fn test_parse_request_data_empty() {
-        let result = parse_request_data(None).unwrap();
+        let result = parse_request_data(None, &TemplateEngine::new()).unwrap();
         assert_eq!(result, json!({}));
     }
 
     #[tokio::test]
     async fn test_parse_request_data_invalid_json() {
-        let result = parse_request_data(Some(r#"{"invalid": json"#));
+        let result = parse_request_data(Some(r#"{"invalid": json"#), &TemplateEngine::new());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid JSON"));
     }
 
-    #[test]
-    fn test_format_json_value() {
-        assert_eq!(
-            format_json_value(&Value::String("test".to_string())),
-            "test"
-        );
-        assert_eq!(
-            format_json_value(&Value::Number(serde_json::Number::from(42))),
-            "42"
-        );
-        assert_eq!(format_json_value(&Value::Bool(true)), "true");
-        assert_eq!(format_json_value(&Value::Null), "null");
+    #[tokio::test]
+    async fn test_parse_request_data_templates() {
+        let template = TemplateEngine::from_args(&["id=42".to_string()], None).unwrap();
+        let result = parse_request_data(
+            Some(r#"{"user_id": "{{var.id}}", "size": "{{randInt 5 5}}"}"#),
+            &template,
+        )
+        .unwrap();
+        assert_eq!(result, json!({"user_id": "42", "size": "5"}));
+    }
+
+    #[tokio::test]
+    async fn test_parse_request_data_template_with_quote() {
+        let template =
+            TemplateEngine::from_args(&[r#"name=Jane", "admin": "true"#.to_string()], None)
+                .unwrap();
+        let result = parse_request_data(Some(r#"{"name": "{{var.name}}"}"#), &template).unwrap();
+        assert_eq!(result, json!({"name": r#"Jane", "admin": "true"#}));
+    }
+
+    fn printer(format: OutputFormat, query: Option<&str>) -> ResponsePrinter {
+        ResponsePrinter {
+            format,
+            query: query.map(|q| Query::parse(q).unwrap()),
+            renderer: TextRenderer::default(),
+            formatters: FormatterRegistry::default(),
+        }
     }
 
     #[tokio::test]
-    async fn test_format_call_response_json_pretty() {
+    async fn test_print_responses_json_pretty() {
         let format = OutputFormat::Json {
             pretty: true,
             emit_defaults: false,
@@ -302,24 +592,49 @@ mod tests {
 
         // Capture output would require more complex testing setup,
         // so we'll just ensure it doesn't panic
-        let result = format_call_response(&json!({"name": "test"}), &format);
+        let result = printer(format, None)
+            .print_projected(&[json!({"name": "test"})], None, false)
+            .await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
-    async fn test_format_call_response_json_compact() {
+    async fn test_print_responses_json_stream() {
         let format = OutputFormat::Json {
             pretty: false,
             emit_defaults: false,
         };
-        let result = format_call_response(&json!({"name": "test"}), &format);
+        let result = printer(format, None)
+            .print_projected(&[json!({"name": "a"}), json!({"name": "b"})], None, true)
+            .await;
         assert!(result.is_ok());
     }
 
     #[tokio::test]
-    async fn test_format_call_response_text() {
-        let format = OutputFormat::Text { compact: false };
-        let result = format_call_response(&json!({"name": "test"}), &format);
+    async fn test_print_responses_unknown_format() {
+        let format = OutputFormat::Custom {
+            name: "yaml".to_string(),
+        };
+        let result = printer(format, None)
+            .print_projected(&[json!({"name": "test"})], None, false)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_print_responses_with_query() {
+        let printer = printer(
+            OutputFormat::Text { compact: false },
+            Some(".users[] | {name}"),
+        );
+        let outcome = CallOutcome {
+            responses: vec![json!({"users": [{"name": "a"}, {"name": "b"}]})],
+            attempts: 1,
+            streaming_type: StreamingType::Unary,
+            input_descriptor: None,
+            output_descriptor: None,
+        };
+        let result = printer.print_outcome(&outcome).await;
         assert!(result.is_ok());
     }
 }
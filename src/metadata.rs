@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use tonic::metadata::{BinaryMetadataValue, KeyAndValueRef, MetadataKey, MetadataMap};
+
+/// Suffix marking binary metadata keys, whose values are base64 on the command line
+pub const BINARY_SUFFIX: &str = "-bin";
+
+/// Insert `(name, value)` header pairs into gRPC request metadata.
+/// Later entries replace earlier ones with the same name.
+///
+/// Keys ending in `-bin` are binary metadata: their values must be base64
+/// encoded and are sent as the decoded bytes.
+pub fn insert_headers(metadata: &mut MetadataMap, headers: &[(String, String)]) -> Result<()> {
+    for (key, value) in headers {
+        if is_binary_key(key) {
+            let key = MetadataKey::from_bytes(key.as_bytes())
+                .with_context(|| format!("Invalid header key: {}", key))?;
+            let bytes = decode_base64(value)
+                .with_context(|| format!("Invalid base64 value for binary header: {}", key))?;
+            metadata.insert_bin(key, BinaryMetadataValue::from_bytes(&bytes));
+        } else {
+            let key = MetadataKey::from_bytes(key.as_bytes())
+                .with_context(|| format!("Invalid header key: {}", key))?;
+            metadata.insert(key, value.parse().context("Invalid header value")?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert metadata into displayable `(name, value)` pairs, encoding binary
+/// values as base64 so they round-trip with [`insert_headers`].
+pub fn metadata_to_pairs(metadata: &MetadataMap) -> Vec<(String, String)> {
+    metadata
+        .iter()
+        .map(|entry| match entry {
+            KeyAndValueRef::Ascii(key, value) => (
+                key.to_string(),
+                value
+                    .to_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|_| String::from_utf8_lossy(value.as_encoded_bytes()).into()),
+            ),
+            KeyAndValueRef::Binary(key, value) => {
+                let value = value
+                    .to_bytes()
+                    .map(|bytes| STANDARD.encode(bytes))
+                    .unwrap_or_else(|_| String::from_utf8_lossy(value.as_encoded_bytes()).into());
+                (key.to_string(), value)
+            }
+        })
+        .collect()
+}
+
+pub fn is_binary_key(key: &str) -> bool {
+    key.to_ascii_lowercase().ends_with(BINARY_SUFFIX)
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>> {
+    let value = value.trim();
+    STANDARD
+        .decode(value)
+        .or_else(|_| STANDARD_NO_PAD.decode(value.trim_end_matches('=')))
+        .context("Value is not valid base64")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_headers() {
+        let mut metadata = MetadataMap::new();
+        insert_headers(
+            &mut metadata,
+            &[
+                ("x-a".to_string(), "1".to_string()),
+                ("x-a".to_string(), "2".to_string()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(metadata.len(), 1);
+        assert_eq!(metadata.get("x-a").unwrap(), "2");
+    }
+
+    #[test]
+    fn test_insert_invalid_header() {
+        let mut metadata = MetadataMap::new();
+        let result = insert_headers(&mut metadata, &[("bad\nkey".to_string(), "v".to_string())]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_binary_headers() {
+        let mut metadata = MetadataMap::new();
+        insert_headers(
+            &mut metadata,
+            &[
+                ("trace-context-bin".to_string(), "AAECAw==".to_string()),
+                ("unpadded-bin".to_string(), "AAECAw".to_string()),
+            ],
+        )
+        .unwrap();
+
+        let value = metadata.get_bin("trace-context-bin").unwrap();
+        assert_eq!(value.to_bytes().unwrap().as_ref(), &[0, 1, 2, 3]);
+        let value = metadata.get_bin("unpadded-bin").unwrap();
+        assert_eq!(value.to_bytes().unwrap().as_ref(), &[0, 1, 2, 3]);
+
+        let result = insert_headers(
+            &mut metadata,
+            &[("trace-context-bin".to_string(), "not base64!".to_string())],
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_metadata_to_pairs() {
+        let mut metadata = MetadataMap::new();
+        insert_headers(
+            &mut metadata,
+            &[
+                ("x-plain".to_string(), "value".to_string()),
+                ("trace-context-bin".to_string(), "AAECAw==".to_string()),
+            ],
+        )
+        .unwrap();
+
+        let pairs = metadata_to_pairs(&metadata);
+        assert!(pairs.contains(&("x-plain".to_string(), "value".to_string())));
+        assert!(pairs.contains(&("trace-context-bin".to_string(), "AAECAw==".to_string())));
+    }
+}
@@ -0,0 +1,408 @@
+use crate::cli::{Cli, Command};
+use crate::client::GrpcClient;
+use crate::server::config::{GrpcServerConfig, ServerConfig};
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Default location of the CLI config file, shared in format with the web server config
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".config").join("grpc-client").join("config.yml"))
+}
+
+/// Load the config file used for `--context` lookups
+pub fn load_config(config_path: Option<&str>) -> Result<ServerConfig> {
+    match config_path {
+        Some(path) => ServerConfig::from_file(path),
+        None => {
+            let path = default_config_path().filter(|p| p.exists()).context(
+                "No config file found. Pass --config or create ~/.config/grpc-client/config.yml",
+            )?;
+            ServerConfig::from_file(&path.to_string_lossy())
+        }
+    }
+}
+
+/// Resolve `--context` into concrete command-line settings.
+///
+/// When a context is given, the endpoint positional argument may be omitted
+/// and is taken from the server config. TLS settings and default headers are
+/// merged with command-line flags, with the command line taking precedence.
+pub fn resolve_context(cli: Cli) -> Result<Cli> {
+    // Test files, workflows and saved requests name their own servers; the
+    // context only provides a default
+    if matches!(
+        cli.command,
+        Command::Test { .. } | Command::Run { .. } | Command::RunSaved { .. }
+    ) {
+        return Ok(cli);
+    }
+
+    let Some(name) = cli.context.clone() else {
+        validate_positionals(&cli.command)?;
+        return Ok(cli);
+    };
+
+    let config = load_config(cli.config_path.as_deref())?;
+    let server = config
+        .get_server(&name)
+        .with_context(|| {
+            format!(
+                "Context '{}' not found. Available contexts: {}",
+                name,
+                available_contexts(&config)
+            )
+        })?
+        .clone();
+
+    apply_server_config(cli, &server)
+}
+
+/// Whether a positional argument names an endpoint rather than a service,
+/// symbol or method: it has a scheme or a port, which those never have
+fn is_endpoint(arg: &str) -> bool {
+    arg.contains("://")
+        || arg
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+}
+
+/// Merge a server config into the CLI arguments
+pub fn apply_server_config(mut cli: Cli, server: &GrpcServerConfig) -> Result<Cli> {
+    // With a context, the first positional is the service, symbol or method
+    // rather than the endpoint, unless it is one
+    let shift = |endpoint: &Option<String>| !endpoint.as_deref().is_some_and(is_endpoint);
+    cli.command = match cli.command {
+        Command::List {
+            endpoint,
+            service: None,
+        } if shift(&endpoint) => Command::List {
+            endpoint: Some(server.endpoint.clone()),
+            service: endpoint,
+        },
+        Command::Health {
+            endpoint,
+            service: None,
+            watch,
+        } if shift(&endpoint) => Command::Health {
+            endpoint: Some(server.endpoint.clone()),
+            service: endpoint,
+            watch,
+        },
+        Command::Describe {
+            endpoint,
+            symbol: None,
+        } if !is_endpoint(&endpoint) => Command::Describe {
+            endpoint: server.endpoint.clone(),
+            symbol: Some(endpoint),
+        },
+        command => command,
+    };
+
+    if let Command::Call {
+        endpoint,
+        method: method @ None,
+        ..
+    }
+    | Command::Bench {
+        endpoint,
+        method: method @ None,
+        ..
+    } = &mut cli.command
+        && !is_endpoint(endpoint)
+    {
+        *method = Some(std::mem::replace(endpoint, server.endpoint.clone()));
+    }
+
+    if cli.auth.is_none() {
+        cli.auth = server.auth.clone();
+    }
+    if cli.retry.is_none() {
+        cli.retry = server.retry.clone();
+    }
+    if cli.redaction.is_none() {
+        cli.redaction = server.redaction.clone();
+    }
+    if cli.plaintext.is_none() {
+        cli.plaintext = Some(server.plaintext);
+    }
+    if cli.ca.is_none() {
+        cli.ca = server.ca_cert.clone();
+    }
+
+    // Context headers go first so that later command-line headers override them
+    let mut headers = sorted_headers(&server.headers);
+    headers.extend(cli.header);
+    cli.header = headers;
+
+    let mut reflect_headers: Vec<String> = sorted_headers(&server.reflect_headers);
+    reflect_headers.extend(cli.reflect_header);
+    cli.reflect_header = reflect_headers;
+
+    Ok(cli)
+}
+
+/// Builds clients for servers named in test and workflow files. A name is looked up as a
+/// context in the config file first and is otherwise used as an endpoint with
+/// the command-line settings. Clients are cached per name, so calls to the same
+/// server share a channel and descriptor pool.
+pub struct ServerResolver {
+    cli: Cli,
+    config: Option<ServerConfig>,
+    clients: HashMap<String, GrpcClient>,
+}
+
+impl ServerResolver {
+    pub fn new(cli: Cli) -> Result<Self> {
+        let has_config =
+            cli.config_path.is_some() || default_config_path().is_some_and(|path| path.exists());
+        let config = has_config
+            .then(|| load_config(cli.config_path.as_deref()))
+            .transpose()?;
+
+        Ok(Self {
+            cli,
+            config,
+            clients: HashMap::new(),
+        })
+    }
+
+    /// Client for `server`, falling back to `--context` when not given
+    pub fn client(&mut self, server: Option<&str>) -> Result<GrpcClient> {
+        let name = server
+            .or(self.cli.context.as_deref())
+            .context("No server given. Set `server` in the file or pass --context")?
+            .to_string();
+
+        if let Some(client) = self.clients.get(&name) {
+            return Ok(client.clone());
+        }
+
+        let context = self
+            .config
+            .as_ref()
+            .and_then(|config| config.get_server(&name));
+        let client = match context {
+            Some(server) => {
+                let cli = apply_server_config(self.cli.clone(), server)?;
+                GrpcClient::from_cli_with_endpoint(&cli, &server.endpoint)?
+            }
+            None => GrpcClient::from_cli_with_endpoint(&self.cli, &name)?,
+        };
+
+        self.clients.insert(name, client.clone());
+        Ok(client)
+    }
+}
+
+fn sorted_headers(headers: &HashMap<String, String>) -> Vec<String> {
+    let mut headers: Vec<_> = headers.iter().collect();
+    headers.sort();
+    headers
+        .into_iter()
+        .map(|(k, v)| format!("{}: {}", k, v))
+        .collect()
+}
+
+fn validate_positionals(command: &Command) -> Result<()> {
+    match command {
+        Command::List { endpoint: None, .. } | Command::Health { endpoint: None, .. } => {
+            bail!("Missing server endpoint. Pass it or use --context")
+        }
+        Command::Describe { symbol: None, .. } => bail!("Missing symbol to describe"),
+        Command::Call { method: None, .. } | Command::Bench { method: None, .. } => {
+            bail!("Missing method to call")
+        }
+        _ => Ok(()),
+    }
+}
+
+fn available_contexts(config: &ServerConfig) -> String {
+    let mut ids: Vec<&str> = config.servers.keys().map(|id| id.as_str()).collect();
+    ids.sort();
+    ids.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn server() -> GrpcServerConfig {
+        GrpcServerConfig {
+            name: "Acc Service".to_string(),
+            endpoint: "127.0.0.1:50000".to_string(),
+            plaintext: true,
+            ca_cert: Some("fixtures/ca.crt".to_string()),
+            headers: HashMap::from([("x-tenant".to_string(), "acme".to_string())]),
+            description: None,
+            auth: None,
+            retry: None,
+            redaction: None,
+            reflect_headers: HashMap::new(),
+            access: None,
+        }
+    }
+
+    #[test]
+    fn test_context_fills_endpoint() {
+        let cli = Cli::parse_from(["grpc-client", "call", "example.UserService/ListUsers"]);
+        let cli = apply_server_config(cli, &server()).unwrap();
+
+        match &cli.command {
+            Command::Call {
+                endpoint, method, ..
+            } => {
+                assert_eq!(endpoint, "127.0.0.1:50000");
+                assert_eq!(method.as_deref(), Some("example.UserService/ListUsers"));
+            }
+            _ => panic!("Expected call command"),
+        }
+        assert_eq!(cli.plaintext, Some(true));
+        assert_eq!(cli.ca.as_deref(), Some("fixtures/ca.crt"));
+        assert_eq!(cli.header, vec!["x-tenant: acme".to_string()]);
+    }
+
+    #[test]
+    fn test_context_fills_health_endpoint() {
+        let cli = Cli::parse_from(["grpc-client", "health", "--watch", "example.UserService"]);
+        let cli = apply_server_config(cli, &server()).unwrap();
+
+        match &cli.command {
+            Command::Health {
+                endpoint,
+                service,
+                watch,
+            } => {
+                assert_eq!(endpoint.as_deref(), Some("127.0.0.1:50000"));
+                assert_eq!(service.as_deref(), Some("example.UserService"));
+                assert!(watch);
+            }
+            _ => panic!("Expected health command"),
+        }
+    }
+
+    #[test]
+    fn test_context_keeps_explicit_endpoint() {
+        let apply = |args: &[&str]| {
+            let cli = Cli::parse_from(["grpc-client"].iter().chain(args));
+            apply_server_config(cli, &server()).unwrap().command
+        };
+
+        match apply(&["list", "localhost:9090"]) {
+            Command::List { endpoint, service } => {
+                assert_eq!(endpoint.as_deref(), Some("localhost:9090"));
+                assert_eq!(service, None);
+            }
+            _ => panic!("Expected list command"),
+        }
+        match apply(&["health", "https://grpc.example.com"]) {
+            Command::Health {
+                endpoint, service, ..
+            } => {
+                assert_eq!(endpoint.as_deref(), Some("https://grpc.example.com"));
+                assert_eq!(service, None);
+            }
+            _ => panic!("Expected health command"),
+        }
+        match apply(&["describe", "localhost:9090"]) {
+            Command::Describe { endpoint, symbol } => {
+                assert_eq!(endpoint, "localhost:9090");
+                assert_eq!(symbol, None);
+            }
+            _ => panic!("Expected describe command"),
+        }
+        match apply(&["call", "[::1]:9090"]) {
+            Command::Call {
+                endpoint, method, ..
+            } => {
+                assert_eq!(endpoint, "[::1]:9090");
+                assert_eq!(method, None);
+            }
+            _ => panic!("Expected call command"),
+        }
+
+        // Service names never have a port
+        match apply(&["list", "example.UserService"]) {
+            Command::List { endpoint, service } => {
+                assert_eq!(endpoint.as_deref(), Some("127.0.0.1:50000"));
+                assert_eq!(service.as_deref(), Some("example.UserService"));
+            }
+            _ => panic!("Expected list command"),
+        }
+    }
+
+    #[test]
+    fn test_context_command_line_overrides() {
+        let cli = Cli::parse_from([
+            "grpc-client",
+            "--plaintext=false",
+            "--ca",
+            "other.crt",
+            "-H",
+            "x-tenant: other",
+            "list",
+            "localhost:9090",
+            "example.UserService",
+        ]);
+        let cli = apply_server_config(cli, &server()).unwrap();
+
+        match &cli.command {
+            Command::List { endpoint, service } => {
+                assert_eq!(endpoint.as_deref(), Some("localhost:9090"));
+                assert_eq!(service.as_deref(), Some("example.UserService"));
+            }
+            _ => panic!("Expected list command"),
+        }
+        assert_eq!(cli.plaintext, Some(false));
+        assert_eq!(cli.ca.as_deref(), Some("other.crt"));
+        assert_eq!(cli.header.last().unwrap(), "x-tenant: other");
+
+        let cli = Cli::parse_from(["grpc-client", "--plaintext", "list", "localhost:9090"]);
+        let cli = apply_server_config(
+            cli,
+            &GrpcServerConfig {
+                plaintext: false,
+                ..server()
+            },
+        )
+        .unwrap();
+        assert_eq!(cli.plaintext, Some(true));
+    }
+
+    #[test]
+    fn test_resolve_context_from_file() {
+        let cli = Cli::parse_from([
+            "grpc-client",
+            "--config",
+            "fixtures/app.yml",
+            "--context",
+            "acc-service",
+            "list",
+        ]);
+        let cli = resolve_context(cli).unwrap();
+        match &cli.command {
+            Command::List { endpoint, service } => {
+                assert_eq!(endpoint.as_deref(), Some("127.0.0.1:50000"));
+                assert!(service.is_none());
+            }
+            _ => panic!("Expected list command"),
+        }
+
+        let cli = Cli::parse_from([
+            "grpc-client",
+            "--config",
+            "fixtures/app.yml",
+            "--context",
+            "missing",
+            "list",
+        ]);
+        assert!(resolve_context(cli).is_err());
+    }
+
+    #[test]
+    fn test_missing_positionals_without_context() {
+        let cli = Cli::parse_from(["grpc-client", "call", "localhost:9090"]);
+        assert!(resolve_context(cli).is_err());
+    }
+}
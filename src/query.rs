@@ -0,0 +1,233 @@
+use crate::jsonpath::JsonPath;
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// A projection applied to each response message before it is shown.
+///
+/// Accepts either a JSONPath expression starting with `$` (see [`JsonPath`])
+/// or a subset of jq:
+///
+/// - paths: `.`, `.user.name`, `.users[0]`, `.users[]`, `.["page-token"]`
+/// - object construction: `{id, email: .contact.email}`
+/// - pipes: `.users[] | {id, name}`
+///
+/// A query can produce any number of values per message.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: String,
+    stages: Vec<Stage>,
+}
+
+#[derive(Debug, Clone)]
+enum Stage {
+    Path(JsonPath),
+    Object(Vec<(String, JsonPath)>),
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Self> {
+        let source = query.trim();
+        if source.is_empty() {
+            bail!("Empty query");
+        }
+
+        let stages = if source.starts_with('$') {
+            vec![Stage::Path(JsonPath::parse(source)?)]
+        } else {
+            split_top_level(source, '|')
+                .into_iter()
+                .map(parse_stage)
+                .collect::<Result<_>>()
+                .with_context(|| format!("Invalid query: {}", source))?
+        };
+
+        Ok(Self {
+            source: source.to_string(),
+            stages,
+        })
+    }
+
+    /// Apply the query to one message
+    pub fn apply(&self, value: &Value) -> Vec<Value> {
+        let mut values = vec![value.clone()];
+        for stage in &self.stages {
+            values = values.iter().flat_map(|value| stage.apply(value)).collect();
+        }
+        values
+    }
+
+    /// Apply the query to each message, flattening the results
+    pub fn apply_all(&self, values: &[Value]) -> Vec<Value> {
+        values.iter().flat_map(|value| self.apply(value)).collect()
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Stage {
+    fn apply(&self, value: &Value) -> Vec<Value> {
+        match self {
+            Stage::Path(path) => path.select(value).into_iter().cloned().collect(),
+            Stage::Object(fields) => {
+                let object: Map<String, Value> = fields
+                    .iter()
+                    .map(|(key, path)| {
+                        let field = path.select_first(value).cloned().unwrap_or(Value::Null);
+                        (key.clone(), field)
+                    })
+                    .collect();
+                vec![Value::Object(object)]
+            }
+        }
+    }
+}
+
+fn parse_stage(stage: &str) -> Result<Stage> {
+    let stage = stage.trim();
+    if let Some(inner) = stage
+        .strip_prefix('{')
+        .and_then(|inner| inner.strip_suffix('}'))
+    {
+        let fields = split_top_level(inner, ',')
+            .into_iter()
+            .filter(|entry| !entry.trim().is_empty())
+            .map(parse_object_entry)
+            .collect::<Result<_>>()?;
+        return Ok(Stage::Object(fields));
+    }
+
+    Ok(Stage::Path(parse_jq_path(stage)?))
+}
+
+/// `key` (shorthand for `key: .key`) or `key: path`
+fn parse_object_entry(entry: &str) -> Result<(String, JsonPath)> {
+    let entry = entry.trim();
+    match entry.split_once(':') {
+        Some((key, path)) => {
+            let key = key.trim().trim_matches('"').to_string();
+            Ok((key, parse_jq_path(path)?))
+        }
+        None => {
+            let key = entry.trim_matches('"').to_string();
+            let path = JsonPath::parse(&format!("$['{}']", key))?;
+            Ok((key, path))
+        }
+    }
+}
+
+/// Translate a jq path (`.a[].b`, `.[0]`) into a JSONPath
+fn parse_jq_path(path: &str) -> Result<JsonPath> {
+    let path = path.trim();
+    let Some(rest) = path.strip_prefix('.') else {
+        bail!("Expected a path starting with '.': {}", path);
+    };
+
+    // `.[0]` indexes the current value directly
+    let rest = if rest.starts_with('[') {
+        rest.to_string()
+    } else if rest.is_empty() {
+        String::new()
+    } else {
+        format!(".{}", rest)
+    };
+    JsonPath::parse(&format!("${}", rest.replace("[]", "[*]")))
+}
+
+/// Split on `separator` outside brackets, braces and quotes
+fn split_top_level(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{' | '(') => depth += 1,
+            (None, ']' | '}' | ')') => depth = depth.saturating_sub(1),
+            (None, c) if c == separator && depth == 0 => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response() -> Value {
+        json!({
+            "users": [
+                {"id": "1", "name": "John", "contact": {"email": "john@example.com"}},
+                {"id": "2", "name": "Jane", "contact": {"email": "jane@example.com"}}
+            ],
+            "next-page": "abc"
+        })
+    }
+
+    fn apply(query: &str) -> Vec<Value> {
+        Query::parse(query).unwrap().apply(&response())
+    }
+
+    #[test]
+    fn test_jq_paths() {
+        assert_eq!(apply("."), vec![response()]);
+        assert_eq!(apply(".users[0].name"), vec![json!("John")]);
+        assert_eq!(apply(".users[].id"), vec![json!("1"), json!("2")]);
+        assert_eq!(apply(".[\"next-page\"]"), vec![json!("abc")]);
+        assert!(apply(".missing").is_empty());
+    }
+
+    #[test]
+    fn test_jsonpath_queries() {
+        assert_eq!(apply("$.users[*].name"), vec![json!("John"), json!("Jane")]);
+        assert_eq!(
+            apply("$..email"),
+            vec![json!("john@example.com"), json!("jane@example.com")]
+        );
+    }
+
+    #[test]
+    fn test_pipes_and_objects() {
+        assert_eq!(
+            apply(".users[] | {id, email: .contact.email}"),
+            vec![
+                json!({"id": "1", "email": "john@example.com"}),
+                json!({"id": "2", "email": "jane@example.com"})
+            ]
+        );
+        assert_eq!(
+            apply(".users[1] | .contact | .email"),
+            vec![json!("jane@example.com")]
+        );
+        assert_eq!(apply("{missing}"), vec![json!({"missing": null})]);
+    }
+
+    #[test]
+    fn test_apply_all_flattens_messages() {
+        let query = Query::parse(".id").unwrap();
+        let values = query.apply_all(&[json!({"id": 1}), json!({"id": 2}), json!({})]);
+        assert_eq!(values, vec![json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse("users").is_err());
+        assert!(Query::parse(".users[").is_err());
+        assert!(Query::parse("{id: name}").is_err());
+    }
+}
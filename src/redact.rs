@@ -0,0 +1,375 @@
+use crate::jsonpath::JsonPath;
+use anyhow::{Context, Result};
+use prost_reflect::{FieldDescriptor, Kind, MessageDescriptor};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Which values to mask in call output, verbose logs and web responses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionConfig {
+    /// Set to false to show all values
+    pub enabled: bool,
+    /// JSONPath expressions selecting values to mask (e.g. `$..payment_info.card_number`)
+    pub paths: Vec<String>,
+    /// Regexes matched against field names (e.g. `(?i)password|secret`)
+    pub fields: Vec<String>,
+    /// Boolean field options marking a field as sensitive, given as an option
+    /// name (`debug_redact`) or an extension name (`acme.sensitive`)
+    pub field_options: Vec<String>,
+    /// Text shown in place of masked values
+    pub mask: String,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            paths: Vec::new(),
+            fields: Vec::new(),
+            field_options: vec!["debug_redact".to_string()],
+            mask: "[REDACTED]".to_string(),
+        }
+    }
+}
+
+/// Masks sensitive values in decoded messages.
+///
+/// Fields are matched by their options in the message descriptor, by name
+/// pattern and by JSONPath. Masked values are replaced with a string,
+/// whatever their original type.
+#[derive(Debug)]
+pub struct Redactor {
+    paths: Vec<JsonPath>,
+    fields: Vec<Regex>,
+    field_options: Vec<String>,
+    mask: Value,
+}
+
+impl Redactor {
+    /// Build a redactor, or `None` when redaction is disabled
+    pub fn from_config(config: &RedactionConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let paths = config
+            .paths
+            .iter()
+            .map(|path| {
+                JsonPath::parse(path).with_context(|| format!("Invalid redaction path: {}", path))
+            })
+            .collect::<Result<_>>()?;
+        let fields = config
+            .fields
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .with_context(|| format!("Invalid redaction field pattern: {}", pattern))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Some(Self {
+            paths,
+            fields,
+            field_options: config.field_options.clone(),
+            mask: Value::String(config.mask.clone()),
+        }))
+    }
+
+    /// Mask sensitive values of a decoded message in place. Without a
+    /// descriptor only name patterns and paths apply.
+    pub fn redact(&self, value: &mut Value, descriptor: Option<&MessageDescriptor>) {
+        match descriptor {
+            Some(descriptor) => self.redact_message(value, descriptor),
+            None => self.redact_by_name(value),
+        }
+
+        for path in &self.paths {
+            for pointer in path.select_pointers(value) {
+                if let Some(target) = value.pointer_mut(&pointer) {
+                    *target = self.mask.clone();
+                }
+            }
+        }
+    }
+
+    /// Copy of `headers` with the values of credential headers masked:
+    /// `authorization`, cookies, `*-token`, `*-api-key` and binary `*-bin`
+    /// headers
+    pub fn redacted_headers(&self, headers: &[(String, String)]) -> Vec<(String, String)> {
+        let mask = self.mask.as_str().unwrap_or_default();
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if is_sensitive_header(name) {
+                    mask.to_string()
+                } else {
+                    value.clone()
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+
+    /// A masked copy of a decoded message
+    pub fn redacted(&self, value: &Value, descriptor: Option<&MessageDescriptor>) -> Value {
+        let mut value = value.clone();
+        self.redact(&mut value, descriptor);
+        value
+    }
+
+    fn redact_message(&self, value: &mut Value, descriptor: &MessageDescriptor) {
+        let Value::Object(map) = value else {
+            return;
+        };
+
+        for (key, child) in map.iter_mut() {
+            let field = descriptor
+                .get_field_by_json_name(key)
+                .or_else(|| descriptor.get_field_by_name(key));
+            let Some(field) = field else {
+                // Keys outside the descriptor, e.g. in `Struct` or `Any` values
+                if self.matches_name(key) {
+                    *child = self.mask.clone();
+                } else {
+                    self.redact_by_name(child);
+                }
+                continue;
+            };
+
+            if self.is_sensitive(&field) {
+                *child = self.mask.clone();
+                continue;
+            }
+
+            let Kind::Message(message) = field.kind() else {
+                continue;
+            };
+            if field.is_map() {
+                if let Kind::Message(entry) = message.map_entry_value_field().kind()
+                    && let Value::Object(entries) = child
+                {
+                    for entry_value in entries.values_mut() {
+                        self.redact_message(entry_value, &entry);
+                    }
+                }
+            } else if let Value::Array(items) = child {
+                for item in items {
+                    self.redact_message(item, &message);
+                }
+            } else {
+                self.redact_message(child, &message);
+            }
+        }
+    }
+
+    fn redact_by_name(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => self.redact_object_by_name(map),
+            Value::Array(items) => {
+                for item in items {
+                    self.redact_by_name(item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn redact_object_by_name(&self, map: &mut Map<String, Value>) {
+        for (key, child) in map.iter_mut() {
+            if self.matches_name(key) {
+                *child = self.mask.clone();
+            } else {
+                self.redact_by_name(child);
+            }
+        }
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        self.fields.iter().any(|pattern| pattern.is_match(name))
+    }
+
+    fn is_sensitive(&self, field: &FieldDescriptor) -> bool {
+        if self.matches_name(field.name()) || self.matches_name(field.json_name()) {
+            return true;
+        }
+        if self.field_options.is_empty() {
+            return false;
+        }
+
+        let options = field.options();
+        self.field_options.iter().any(|name| {
+            let option = options
+                .get_field_by_name(name)
+                .is_some_and(|value| value.as_bool() == Some(true));
+            option
+                || options.extensions().any(|(extension, value)| {
+                    (extension.full_name() == name || extension.name() == name)
+                        && value.as_bool() == Some(true)
+                })
+        })
+    }
+}
+
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    matches!(
+        name.as_str(),
+        "authorization" | "proxy-authorization" | "cookie" | "set-cookie"
+    ) || ["-token", "-api-key", "-bin"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use prost_reflect::{DescriptorPool, DynamicMessage};
+    use serde_json::json;
+
+    fn field(name: &str, number: u32, redact: bool) -> Value {
+        let mut field = json!({
+            "name": name,
+            "jsonName": name,
+            "number": number,
+            "label": "LABEL_OPTIONAL",
+            "type": "TYPE_STRING"
+        });
+        if redact {
+            field["options"] = json!({"debugRedact": true});
+        }
+        field
+    }
+
+    fn user_descriptor() -> MessageDescriptor {
+        let set = json!({
+            "file": [{
+                "name": "test.proto",
+                "package": "test",
+                "syntax": "proto3",
+                "messageType": [
+                    {
+                        "name": "User",
+                        "field": [
+                            field("name", 1, false),
+                            {
+                                "name": "payments",
+                                "jsonName": "payments",
+                                "number": 2,
+                                "label": "LABEL_REPEATED",
+                                "type": "TYPE_MESSAGE",
+                                "typeName": ".test.PaymentInfo"
+                            },
+                            field("password", 3, false)
+                        ]
+                    },
+                    {
+                        "name": "PaymentInfo",
+                        "field": [field("card_number", 1, true), field("expiration", 2, false)]
+                    }
+                ]
+            }]
+        });
+        let set_descriptor = DescriptorPool::global()
+            .get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap();
+        let set = DynamicMessage::deserialize(set_descriptor, set).unwrap();
+        DescriptorPool::decode(set.encode_to_vec().as_slice())
+            .unwrap()
+            .get_message_by_name("test.User")
+            .unwrap()
+    }
+
+    fn user() -> Value {
+        json!({
+            "name": "Jane",
+            "password": "hunter2",
+            "payments": [{"card_number": "4111", "expiration": "12/30"}]
+        })
+    }
+
+    #[test]
+    fn test_redacts_fields_marked_in_descriptor() {
+        let redactor = Redactor::from_config(&RedactionConfig::default())
+            .unwrap()
+            .unwrap();
+        let value = redactor.redacted(&user(), Some(&user_descriptor()));
+
+        assert_eq!(value["payments"][0]["card_number"], "[REDACTED]");
+        assert_eq!(value["payments"][0]["expiration"], "12/30");
+        assert_eq!(value["password"], "hunter2");
+    }
+
+    #[test]
+    fn test_redacts_by_name_and_path() {
+        let config = RedactionConfig {
+            paths: vec!["$.name".to_string()],
+            fields: vec!["(?i)pass".to_string()],
+            mask: "***".to_string(),
+            ..Default::default()
+        };
+        let redactor = Redactor::from_config(&config).unwrap().unwrap();
+
+        let value = redactor.redacted(&user(), None);
+        assert_eq!(value["name"], "***");
+        assert_eq!(value["password"], "***");
+        // Field options need a descriptor
+        assert_eq!(value["payments"][0]["card_number"], "4111");
+
+        let value = redactor.redacted(&user(), Some(&user_descriptor()));
+        assert_eq!(value["password"], "***");
+        assert_eq!(value["payments"][0]["card_number"], "***");
+    }
+
+    #[test]
+    fn test_disabled_and_invalid_configs() {
+        let config = RedactionConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(Redactor::from_config(&config).unwrap().is_none());
+
+        let config = RedactionConfig {
+            fields: vec!["(".to_string()],
+            ..Default::default()
+        };
+        assert!(Redactor::from_config(&config).is_err());
+
+        let config: RedactionConfig =
+            serde_yaml::from_str("paths: [\"$..ssn\"]\nmask: hidden\n").unwrap();
+        assert_eq!(config.field_options, vec!["debug_redact"]);
+        assert_eq!(config.mask, "hidden");
+    }
+
+    #[test]
+    fn test_redacts_credential_headers() {
+        let redactor = Redactor::from_config(&RedactionConfig::default())
+            .unwrap()
+            .unwrap();
+        let headers = [
+            ("Authorization", "Bearer abc"),
+            ("cookie", "session=abc"),
+            ("x-refresh-token", "abc"),
+            ("x-api-key", "abc"),
+            ("trace-bin", "YWJj"),
+            ("x-tenant", "acme"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let redacted = redactor.redacted_headers(&headers);
+        let values: Vec<&str> = redacted.iter().map(|(_, value)| value.as_str()).collect();
+        assert_eq!(
+            values,
+            [
+                "[REDACTED]",
+                "[REDACTED]",
+                "[REDACTED]",
+                "[REDACTED]",
+                "[REDACTED]",
+                "acme"
+            ]
+        );
+    }
+}
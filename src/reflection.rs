@@ -1,4 +1,5 @@
 use crate::domain::{MethodName, ServiceName};
+use crate::metadata::insert_headers;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 use prost::Message;
@@ -19,6 +20,13 @@ pub enum StreamingType {
     BiDirectional, // multiple requests → multiple responses
 }
 
+impl StreamingType {
+    /// Whether the server sends a stream of responses
+    pub fn is_server_streaming(&self) -> bool {
+        matches!(self, Self::ServerStream | Self::BiDirectional)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type")]
 pub enum Symbol {
@@ -70,26 +78,42 @@ pub trait SchemaSource: Send + Sync {
     async fn list_methods(&mut self, service: &ServiceName) -> Result<Vec<MethodDescriptor>>;
     async fn get_file_containing_symbol(&mut self, symbol: &str) -> Result<FileDescriptorProto>;
     async fn get_file_by_filename(&mut self, name: &str) -> Result<FileDescriptorProto>;
+
+    /// Serialized form of a previously fetched file, keeping field options
+    /// (e.g. `debug_redact` or custom extensions) that `FileDescriptorProto` drops
+    fn raw_file(&self, _name: &str) -> Option<Vec<u8>> {
+        None
+    }
 }
 
 pub struct ReflectionClient {
     client: ServerReflectionClient<Channel>,
+    metadata: Vec<(String, String)>,
     service_cache: HashMap<String, ServiceDescriptor>,
     file_cache: HashMap<String, FileDescriptorProto>,
+    raw_files: HashMap<String, Vec<u8>>,
 }
 
 impl ReflectionClient {
     pub fn new(channel: Channel) -> Self {
+        Self::with_metadata(channel, Vec::new())
+    }
+
+    /// Create a reflection client that sends the given metadata with every request
+    pub fn with_metadata(channel: Channel, metadata: Vec<(String, String)>) -> Self {
         Self {
             client: ServerReflectionClient::new(channel),
+            metadata,
             service_cache: HashMap::new(),
             file_cache: HashMap::new(),
+            raw_files: HashMap::new(),
         }
     }
 
     pub fn clear_cache(&mut self) {
         self.service_cache.clear();
         self.file_cache.clear();
+        self.raw_files.clear();
     }
 
     async fn make_request(&mut self, request: MessageRequest) -> Result<MessageResponse> {
@@ -98,18 +122,21 @@ impl ReflectionClient {
             message_request: Some(request),
         };
 
+        let mut request = tonic::Request::new(tokio_stream::once(request));
+        insert_headers(request.metadata_mut(), &self.metadata)?;
+
         let response = self
             .client
-            .server_reflection_info(tokio_stream::once(request))
+            .server_reflection_info(request)
             .await
             .context("Failed to connect to reflection service")?;
 
         let mut stream = response.into_inner();
 
-        if let Some(response) = stream.message().await? {
-            if let Some(message_response) = response.message_response {
-                return Ok(message_response);
-            }
+        if let Some(response) = stream.message().await?
+            && let Some(message_response) = response.message_response
+        {
+            return Ok(message_response);
         }
 
         anyhow::bail!("No response received from reflection service")
@@ -321,6 +348,8 @@ impl SchemaSource for ReflectionClient {
                     // Cache the file descriptor by its name for future lookups
                     self.file_cache
                         .insert(file_desc.name().to_string(), file_desc.clone());
+                    self.raw_files
+                        .insert(file_desc.name().to_string(), file_data.clone());
                     Ok(file_desc)
                 } else {
                     anyhow::bail!("No file descriptor found for symbol: {}", symbol)
@@ -337,6 +366,10 @@ impl SchemaSource for ReflectionClient {
         }
     }
 
+    fn raw_file(&self, name: &str) -> Option<Vec<u8>> {
+        self.raw_files.get(name).cloned()
+    }
+
     async fn get_file_by_filename(&mut self, name: &str) -> Result<FileDescriptorProto> {
         if let Some(file_desc) = self.file_cache.get(name) {
             return Ok(file_desc.clone());
@@ -351,6 +384,7 @@ impl SchemaSource for ReflectionClient {
                     let file_desc = FileDescriptorProto::decode(&file_data[..])
                         .context("Failed to decode file descriptor")?;
                     self.file_cache.insert(name.to_string(), file_desc.clone());
+                    self.raw_files.insert(name.to_string(), file_data.clone());
                     Ok(file_desc)
                 } else {
                     anyhow::bail!("No file descriptor found for filename: {}", name)
@@ -377,6 +411,14 @@ pub fn create_reflection_client(channel: Channel) -> Box<dyn SchemaSource> {
     Box::new(ReflectionClient::new(channel))
 }
 
+/// Create a reflection client that attaches metadata to every reflection request
+pub fn create_reflection_client_with_metadata(
+    channel: Channel,
+    metadata: Vec<(String, String)>,
+) -> Box<dyn SchemaSource> {
+    Box::new(ReflectionClient::with_metadata(channel, metadata))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
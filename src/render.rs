@@ -0,0 +1,445 @@
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use prost_reflect::{FieldDescriptor, Kind, MessageDescriptor};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const BLUE: &str = "\x1b[34m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const YELLOW: &str = "\x1b[33m";
+const MAGENTA: &str = "\x1b[35m";
+
+/// When to color text output
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+        }
+    }
+}
+
+/// How `bytes` fields are shown in text output
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BytesEncoding {
+    #[default]
+    Base64,
+    Hex,
+}
+
+/// Renders decoded messages as indented `field: value` text.
+///
+/// With the message descriptor, values are shown according to their field
+/// type: enum names with their numbers, `Timestamp` and `Duration` values in
+/// human-readable form, 64-bit integers as numbers and bytes as hex or base64.
+/// Without one (e.g. for projected values) only the JSON types are used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextRenderer {
+    pub color: bool,
+    pub bytes: BytesEncoding,
+}
+
+impl TextRenderer {
+    pub fn new(color: bool, bytes: BytesEncoding) -> Self {
+        Self { color, bytes }
+    }
+
+    pub fn render(&self, value: &Value, descriptor: Option<&MessageDescriptor>) -> String {
+        let mut out = String::new();
+        match value {
+            Value::Object(_) => self.render_message(&mut out, value, descriptor, 0),
+            Value::Array(_) => {
+                self.render_list(&mut out, value, descriptor.cloned().map(Field::Message), 0)
+            }
+            scalar => {
+                let _ = writeln!(out, "{}", self.scalar(scalar, None));
+            }
+        }
+        out
+    }
+
+    fn render_message(
+        &self,
+        out: &mut String,
+        value: &Value,
+        descriptor: Option<&MessageDescriptor>,
+        indent: usize,
+    ) {
+        let Value::Object(map) = value else {
+            let _ = writeln!(out, "{}{}", "  ".repeat(indent), self.scalar(value, None));
+            return;
+        };
+
+        for (key, child) in map {
+            let field = descriptor.and_then(|descriptor| {
+                descriptor
+                    .get_field_by_json_name(key)
+                    .or_else(|| descriptor.get_field_by_name(key))
+            });
+            let field = field.map(Field::Field);
+            let prefix = format!("{}{}:", "  ".repeat(indent), self.paint(key, BLUE));
+
+            match child {
+                Value::Object(_) if !is_map(field.as_ref()) => {
+                    let _ = writeln!(out, "{}", prefix);
+                    self.render_message(
+                        out,
+                        child,
+                        message_of(field.as_ref()).as_ref(),
+                        indent + 1,
+                    );
+                }
+                Value::Object(entries) if is_map(field.as_ref()) => {
+                    let _ = writeln!(out, "{}", prefix);
+                    let value_field = field.as_ref().and_then(map_value_field);
+                    for (entry_key, entry) in entries {
+                        self.render_entry(out, entry_key, entry, value_field.as_ref(), indent + 1);
+                    }
+                }
+                Value::Array(_) => {
+                    let _ = writeln!(out, "{}", prefix);
+                    self.render_list(out, child, field, indent + 1);
+                }
+                _ => {
+                    let _ = writeln!(out, "{} {}", prefix, self.scalar(child, field.as_ref()));
+                }
+            }
+        }
+    }
+
+    fn render_list(&self, out: &mut String, value: &Value, field: Option<Field>, indent: usize) {
+        let Value::Array(items) = value else {
+            return;
+        };
+        for (i, item) in items.iter().enumerate() {
+            self.render_entry(out, &format!("[{}]", i), item, field.as_ref(), indent);
+        }
+    }
+
+    fn render_entry(
+        &self,
+        out: &mut String,
+        label: &str,
+        value: &Value,
+        field: Option<&Field>,
+        indent: usize,
+    ) {
+        let prefix = format!("{}{}:", "  ".repeat(indent), self.paint(label, DIM));
+        match value {
+            Value::Object(_) => {
+                let _ = writeln!(out, "{}", prefix);
+                self.render_message(out, value, message_of(field).as_ref(), indent + 1);
+            }
+            Value::Array(_) => {
+                let _ = writeln!(out, "{}", prefix);
+                self.render_list(out, value, None, indent + 1);
+            }
+            _ => {
+                let _ = writeln!(out, "{} {}", prefix, self.scalar(value, field));
+            }
+        }
+    }
+
+    /// A single value, formatted by its field type when known
+    fn scalar(&self, value: &Value, field: Option<&Field>) -> String {
+        let kind = field.map(Field::kind);
+        match (kind, value) {
+            (Some(Kind::Enum(enum_desc)), value) => {
+                let number = match value {
+                    Value::String(name) => enum_desc.get_value_by_name(name).map(|v| v.number()),
+                    Value::Number(n) => n.as_i64().and_then(|n| i32::try_from(n).ok()),
+                    _ => None,
+                };
+                let name = match (value, number) {
+                    (Value::String(name), _) => name.clone(),
+                    (_, Some(number)) => enum_desc
+                        .get_value(number)
+                        .map(|v| v.name().to_string())
+                        .unwrap_or_else(|| "UNKNOWN".to_string()),
+                    _ => value.to_string(),
+                };
+                match number {
+                    Some(number) => format!(
+                        "{} {}",
+                        self.paint(&name, MAGENTA),
+                        self.paint(&format!("({})", number), DIM)
+                    ),
+                    None => self.paint(&name, MAGENTA),
+                }
+            }
+            (Some(Kind::Message(message)), Value::String(text)) => match message.full_name() {
+                "google.protobuf.Timestamp" => self.timestamp(text),
+                "google.protobuf.Duration" => self.duration(text),
+                _ => self.paint(text, GREEN),
+            },
+            (Some(Kind::Bytes), Value::String(encoded)) => self.bytes(encoded),
+            // 64-bit integers are strings in JSON
+            (
+                Some(Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 | Kind::Uint64 | Kind::Fixed64),
+                Value::String(number),
+            ) => self.paint(number, CYAN),
+            (_, Value::String(text)) => self.paint(text, GREEN),
+            (_, Value::Number(n)) => self.paint(&n.to_string(), CYAN),
+            (_, Value::Bool(b)) => self.paint(&b.to_string(), YELLOW),
+            (_, Value::Null) => self.paint("null", DIM),
+            (_, other) => other.to_string(),
+        }
+    }
+
+    fn timestamp(&self, text: &str) -> String {
+        let Ok(time) = DateTime::parse_from_rfc3339(text) else {
+            return self.paint(text, BLUE);
+        };
+        let time = time.with_timezone(&Utc);
+        let formatted = time.format("%Y-%m-%d %H:%M:%S%.f UTC").to_string();
+        let relative = relative_time(time, Utc::now());
+        format!(
+            "{} {}",
+            self.paint(&formatted, BLUE),
+            self.paint(&format!("({})", relative), DIM)
+        )
+    }
+
+    fn duration(&self, text: &str) -> String {
+        let seconds = text
+            .strip_suffix('s')
+            .and_then(|seconds| seconds.parse::<f64>().ok());
+        match seconds {
+            Some(seconds) => {
+                let sign = if seconds < 0.0 { "-" } else { "" };
+                let duration = Duration::from_secs_f64(seconds.abs());
+                // Round to microseconds so tiny float errors don't show up
+                let duration = Duration::from_micros(duration.as_micros() as u64);
+                if duration.is_zero() {
+                    self.paint("0s", BLUE)
+                } else {
+                    let formatted = humantime::format_duration(duration).to_string();
+                    self.paint(&format!("{}{}", sign, formatted), BLUE)
+                }
+            }
+            None => self.paint(text, BLUE),
+        }
+    }
+
+    fn bytes(&self, encoded: &str) -> String {
+        match (self.bytes, STANDARD.decode(encoded)) {
+            (BytesEncoding::Hex, Ok(bytes)) => {
+                let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                format!(
+                    "{} {}",
+                    self.paint(&format!("0x{}", hex), YELLOW),
+                    self.paint(&format!("({} bytes)", bytes.len()), DIM)
+                )
+            }
+            (_, Ok(bytes)) => format!(
+                "{} {}",
+                self.paint(encoded, YELLOW),
+                self.paint(&format!("({} bytes)", bytes.len()), DIM)
+            ),
+            (_, Err(_)) => self.paint(encoded, YELLOW),
+        }
+    }
+
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Heading shown before a rendered message
+    pub fn heading(&self, text: &str) -> String {
+        self.paint(text, BOLD)
+    }
+}
+
+/// Type information for a value: a message field, or the message type of
+/// top-level values
+enum Field {
+    Field(FieldDescriptor),
+    Message(MessageDescriptor),
+}
+
+impl Field {
+    fn kind(&self) -> Kind {
+        match self {
+            Field::Field(field) => field.kind(),
+            Field::Message(message) => Kind::Message(message.clone()),
+        }
+    }
+}
+
+fn message_of(field: Option<&Field>) -> Option<MessageDescriptor> {
+    match field?.kind() {
+        Kind::Message(message) => Some(message),
+        _ => None,
+    }
+}
+
+fn is_map(field: Option<&Field>) -> bool {
+    matches!(field, Some(Field::Field(field)) if field.is_map())
+}
+
+fn map_value_field(field: &Field) -> Option<Field> {
+    let message = message_of(Some(field))?;
+    Some(Field::Field(message.map_entry_value_field()))
+}
+
+fn relative_time(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let delta = now.signed_duration_since(time);
+    let seconds = delta.num_seconds();
+    let magnitude = humantime::format_duration(Duration::from_secs(seconds.unsigned_abs()))
+        .to_string()
+        .split(' ')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    match seconds {
+        0 => "now".to_string(),
+        s if s > 0 => format!("{} ago", magnitude),
+        _ => format!("in {}", magnitude),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use prost_reflect::{DescriptorPool, DynamicMessage};
+    use serde_json::json;
+
+    fn event_descriptor() -> MessageDescriptor {
+        let field = |name: &str, number: u32, kind: &str, type_name: Option<&str>| {
+            let mut field = json!({
+                "name": name,
+                "jsonName": name,
+                "number": number,
+                "label": "LABEL_OPTIONAL",
+                "type": kind
+            });
+            if let Some(type_name) = type_name {
+                field["typeName"] = json!(type_name);
+            }
+            field
+        };
+        let set = json!({
+            "file": [{
+                "name": "event.proto",
+                "package": "test",
+                "syntax": "proto3",
+                "dependency": ["google/protobuf/timestamp.proto", "google/protobuf/duration.proto"],
+                "messageType": [{
+                    "name": "Event",
+                    "field": [
+                        field("kind", 1, "TYPE_ENUM", Some(".test.Kind")),
+                        field("at", 2, "TYPE_MESSAGE", Some(".google.protobuf.Timestamp")),
+                        field("took", 3, "TYPE_MESSAGE", Some(".google.protobuf.Duration")),
+                        field("payload", 4, "TYPE_BYTES", None),
+                        field("count", 5, "TYPE_INT64", None),
+                        field("name", 6, "TYPE_STRING", None)
+                    ]
+                }],
+                "enumType": [{
+                    "name": "Kind",
+                    "value": [{"name": "CREATED", "number": 0}, {"name": "DELETED", "number": 2}]
+                }]
+            }]
+        });
+
+        let mut pool = DescriptorPool::global();
+        let set_descriptor = pool
+            .get_message_by_name("google.protobuf.FileDescriptorSet")
+            .unwrap();
+        let set = DynamicMessage::deserialize(set_descriptor, set).unwrap();
+        pool.decode_file_descriptor_set(set.encode_to_vec().as_slice())
+            .unwrap();
+        pool.get_message_by_name("test.Event").unwrap()
+    }
+
+    fn event() -> Value {
+        json!({
+            "kind": "DELETED",
+            "at": "2024-03-01T12:30:00Z",
+            "took": "90.500s",
+            "payload": "3q2+7w==",
+            "count": "42",
+            "name": "import"
+        })
+    }
+
+    #[test]
+    fn test_render_with_descriptor() {
+        let renderer = TextRenderer::new(false, BytesEncoding::Hex);
+        let text = renderer.render(&event(), Some(&event_descriptor()));
+
+        assert!(text.contains("kind: DELETED (2)\n"));
+        assert!(text.contains("at: 2024-03-01 12:30:00 UTC ("));
+        assert!(text.contains("took: 1m 30s 500ms\n"));
+        assert!(text.contains("payload: 0xdeadbeef (4 bytes)\n"));
+        assert!(text.contains("count: 42\n"));
+        assert!(text.contains("name: import\n"));
+        assert!(!text.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_render_without_descriptor() {
+        let renderer = TextRenderer::default();
+        let value = json!({"user": {"name": "Jane", "tags": ["a", 1, true, null]}});
+        let text = renderer.render(&value, None);
+
+        assert_eq!(
+            text,
+            "user:\n  name: Jane\n  tags:\n    [0]: a\n    [1]: 1\n    [2]: true\n    [3]: null\n"
+        );
+        let text = renderer.render(&event(), None);
+        assert!(text.contains("payload: 3q2+7w==\n"));
+        assert_eq!(renderer.render(&json!("plain"), None), "plain\n");
+    }
+
+    #[test]
+    fn test_render_with_color() {
+        let renderer = TextRenderer::new(true, BytesEncoding::Base64);
+        let text = renderer.render(&event(), Some(&event_descriptor()));
+
+        assert!(text.contains(&format!("{}kind{}:", BLUE, RESET)));
+        assert!(text.contains(&format!("{}DELETED{}", MAGENTA, RESET)));
+        assert!(text.contains(&format!("{}3q2+7w=={}", YELLOW, RESET)));
+    }
+
+    #[test]
+    fn test_relative_time() {
+        let now = DateTime::parse_from_rfc3339("2024-03-01T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(relative_time(now, now), "now");
+        assert_eq!(
+            relative_time(now - chrono::Duration::hours(3), now),
+            "3h ago"
+        );
+        assert_eq!(
+            relative_time(now + chrono::Duration::days(2), now),
+            "in 2days"
+        );
+    }
+}
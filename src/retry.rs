@@ -0,0 +1,274 @@
+use crate::domain::{duration_str, parse_status_code, status_code_name};
+use anyhow::{Result, bail};
+use prost::Message;
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::time::Duration;
+use tonic::{Code, Status};
+
+const RETRY_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.RetryInfo";
+
+/// Retry policy for unary calls that fail with a transient status code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first call; at least 1
+    #[serde(deserialize_with = "deserialize_max_attempts")]
+    pub max_attempts: u32,
+    /// Backoff before the first retry (e.g. "100ms")
+    #[serde(with = "duration_str")]
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between attempts, including one asked for by
+    /// the server
+    #[serde(with = "duration_str")]
+    pub max_backoff: Duration,
+    /// Factor the backoff grows by after each attempt; at least 1
+    #[serde(deserialize_with = "deserialize_multiplier")]
+    pub backoff_multiplier: f64,
+    /// Status codes that trigger a retry (e.g. UNAVAILABLE)
+    pub retryable_codes: Vec<RetryCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            backoff_multiplier: 2.0,
+            retryable_codes: vec![
+                RetryCode(Code::Unavailable),
+                RetryCode(Code::ResourceExhausted),
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retrying after `attempt` (1-based) failed with `status`,
+    /// or `None` if the call should not be retried. A `google.rpc.RetryInfo`
+    /// delay sent by the server takes precedence over the computed backoff,
+    /// up to `max_backoff`.
+    pub fn retry_delay(&self, status: &Status, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts
+            || !self.retryable_codes.iter().any(|c| c.0 == status.code())
+        {
+            return None;
+        }
+
+        Some(match server_retry_delay(status) {
+            Some(delay) => delay.min(self.max_backoff),
+            None => self.backoff(attempt),
+        })
+    }
+
+    /// Exponential backoff with full jitter: a random delay up to
+    /// `initial_backoff * multiplier^(attempt - 1)`, capped at `max_backoff`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exponent);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+        Duration::from_secs_f64(rand::rng().random_range(0.0..=backoff))
+    }
+}
+
+fn deserialize_max_attempts<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    match u32::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("max_attempts must be at least 1")),
+        attempts => Ok(attempts),
+    }
+}
+
+/// The multiplier must keep the backoff growing and finite, as the jittered
+/// delay is drawn from `0..=backoff`
+fn deserialize_multiplier<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let multiplier = f64::deserialize(deserializer)?;
+    if !multiplier.is_finite() || multiplier < 1.0 {
+        return Err(serde::de::Error::custom(format!(
+            "backoff_multiplier must be a number of at least 1, got {}",
+            multiplier
+        )));
+    }
+    Ok(multiplier)
+}
+
+/// A gRPC status code in a retry policy, written in the canonical
+/// `UPPER_SNAKE_CASE` form (e.g. `RESOURCE_EXHAUSTED`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryCode(pub Code);
+
+impl RetryCode {
+    pub fn parse(name: &str) -> Result<Self> {
+        match parse_status_code(name)? {
+            Code::Ok => bail!("OK is not a retryable status code"),
+            code => Ok(Self(code)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        status_code_name(self.0)
+    }
+}
+
+impl fmt::Display for RetryCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for RetryCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for RetryCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        RetryCode::parse(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+/// `google.rpc.Status`, carried in the `grpc-status-details-bin` trailer
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<prost_types::Any>,
+}
+
+/// `google.rpc.RetryInfo`
+#[derive(Clone, PartialEq, Message)]
+struct RetryInfo {
+    #[prost(message, optional, tag = "1")]
+    retry_delay: Option<prost_types::Duration>,
+}
+
+/// Retry delay requested by the server through `google.rpc.RetryInfo`
+pub fn server_retry_delay(status: &Status) -> Option<Duration> {
+    let rpc_status = RpcStatus::decode(status.details()).ok()?;
+    rpc_status
+        .details
+        .iter()
+        .filter(|any| any.type_url == RETRY_INFO_TYPE_URL)
+        .find_map(|any| RetryInfo::decode(any.value.as_slice()).ok()?.retry_delay)
+        .and_then(|delay| Duration::try_from(delay).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_with_retry_info(delay: Duration) -> Status {
+        let retry_info = RetryInfo {
+            retry_delay: Some(delay.try_into().unwrap()),
+        };
+        let details = RpcStatus {
+            code: Code::Unavailable as i32,
+            message: "try later".to_string(),
+            details: vec![prost_types::Any {
+                type_url: RETRY_INFO_TYPE_URL.to_string(),
+                value: retry_info.encode_to_vec(),
+            }],
+        };
+        Status::with_details(
+            Code::Unavailable,
+            "try later",
+            details.encode_to_vec().into(),
+        )
+    }
+
+    #[test]
+    fn test_retry_only_selected_codes() {
+        let policy = RetryPolicy::default();
+
+        assert!(
+            policy
+                .retry_delay(&Status::unavailable("down"), 1)
+                .is_some()
+        );
+        assert!(
+            policy
+                .retry_delay(&Status::resource_exhausted("quota"), 2)
+                .is_some()
+        );
+        assert!(
+            policy
+                .retry_delay(&Status::invalid_argument("bad"), 1)
+                .is_none()
+        );
+        // Attempts are exhausted after max_attempts calls
+        assert!(
+            policy
+                .retry_delay(&Status::unavailable("down"), 3)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            ..Default::default()
+        };
+
+        for attempt in 1..10 {
+            assert!(policy.backoff(attempt) <= Duration::from_millis(300));
+        }
+        assert!(policy.backoff(1) <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_server_retry_info_overrides_backoff() {
+        let status = status_with_retry_info(Duration::from_secs(3));
+        assert_eq!(server_retry_delay(&status), Some(Duration::from_secs(3)));
+
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.retry_delay(&status, 1), Some(Duration::from_secs(3)));
+        assert_eq!(server_retry_delay(&Status::unavailable("down")), None);
+
+        // A server asking for a day's wait is held to max_backoff
+        let status = status_with_retry_info(Duration::from_secs(86400));
+        assert_eq!(policy.retry_delay(&status, 1), Some(policy.max_backoff));
+    }
+
+    #[test]
+    fn test_policy_from_yaml() {
+        let policy: RetryPolicy = serde_yaml::from_str(
+            "max_attempts: 5\ninitial_backoff: 250ms\nretryable_codes: [unavailable, ABORTED]\n",
+        )
+        .unwrap();
+
+        assert_eq!(policy.max_attempts, 5);
+        assert_eq!(policy.initial_backoff, Duration::from_millis(250));
+        assert_eq!(policy.max_backoff, Duration::from_secs(5));
+        assert_eq!(
+            policy.retryable_codes,
+            vec![RetryCode(Code::Unavailable), RetryCode(Code::Aborted)]
+        );
+
+        let result: Result<RetryPolicy, _> = serde_yaml::from_str("retryable_codes: [NOPE]");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_policy_rejects_unusable_settings() {
+        for yaml in [
+            "max_attempts: 0",
+            "backoff_multiplier: 0.5",
+            "backoff_multiplier: -2",
+            "backoff_multiplier: .nan",
+            "backoff_multiplier: .inf",
+        ] {
+            let result: Result<RetryPolicy, _> = serde_yaml::from_str(yaml);
+            assert!(result.is_err(), "{} was accepted", yaml);
+        }
+        let policy: RetryPolicy = serde_yaml::from_str("backoff_multiplier: 1").unwrap();
+        assert_eq!(policy.backoff_multiplier, 1.0);
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use dashmap::{DashMap, mapref::entry::Entry};
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio_util::sync::CancellationToken;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A call in progress, for API responses
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActiveCall {
+    /// Call identifier, used to cancel the call
+    pub id: String,
+    /// Server the call was made on
+    pub server_id: String,
+    /// Full method name (e.g., "package.service/method")
+    pub method: String,
+    /// When the call started
+    pub started_at: DateTime<Utc>,
+    /// Response messages received so far
+    pub messages_received: u64,
+}
+
+struct RegisteredCall {
+    server_id: String,
+    method: String,
+    started_at: DateTime<Utc>,
+    received: Arc<AtomicU64>,
+    cancel: CancellationToken,
+}
+
+/// Calls started through the web API that are still running
+#[derive(Clone, Default)]
+pub struct CallRegistry {
+    calls: Arc<DashMap<String, RegisteredCall>>,
+}
+
+impl CallRegistry {
+    /// Track a new call under `id`, or a generated ID. The call is listed
+    /// until the returned handle is dropped.
+    pub fn register(
+        &self,
+        id: Option<String>,
+        server_id: &str,
+        method: &str,
+    ) -> Result<CallHandle> {
+        let id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        let received = Arc::new(AtomicU64::new(0));
+        let cancel = CancellationToken::new();
+
+        match self.calls.entry(id.clone()) {
+            Entry::Occupied(_) => bail!("A call with ID '{}' is already running", id),
+            Entry::Vacant(entry) => {
+                entry.insert(RegisteredCall {
+                    server_id: server_id.to_string(),
+                    method: method.to_string(),
+                    started_at: Utc::now(),
+                    received: received.clone(),
+                    cancel: cancel.clone(),
+                });
+            }
+        }
+
+        Ok(CallHandle {
+            id,
+            calls: self.calls.clone(),
+            received,
+            cancel,
+        })
+    }
+
+    /// Running calls, oldest first
+    pub fn list(&self) -> Vec<ActiveCall> {
+        let mut calls: Vec<ActiveCall> = self
+            .calls
+            .iter()
+            .map(|entry| ActiveCall {
+                id: entry.key().clone(),
+                server_id: entry.server_id.clone(),
+                method: entry.method.clone(),
+                started_at: entry.started_at,
+                messages_received: entry.received.load(Ordering::Relaxed),
+            })
+            .collect();
+        calls.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        calls
+    }
+
+    /// Cancel a running call. Returns false when no call has this ID.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.calls.remove(id) {
+            Some((_, call)) => {
+                call.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Registration of a running call; dropping it removes the call from the
+/// registry
+pub struct CallHandle {
+    id: String,
+    calls: Arc<DashMap<String, RegisteredCall>>,
+    received: Arc<AtomicU64>,
+    cancel: CancellationToken,
+}
+
+impl CallHandle {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Counter of received messages, shared with the client making the call
+    pub fn counter(&self) -> Arc<AtomicU64> {
+        self.received.clone()
+    }
+
+    /// Count a received response message
+    pub fn record_message(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Completes when the call is cancelled through the registry
+    pub async fn cancelled(&self) {
+        self.cancel.cancelled().await
+    }
+}
+
+impl Drop for CallHandle {
+    fn drop(&mut self) {
+        // The ID may already belong to a newer call if this one was cancelled
+        self.calls.remove_if(&self.id, |_, call| {
+            Arc::ptr_eq(&call.received, &self.received)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_register_list_and_cancel() {
+        let registry = CallRegistry::default();
+        let call = registry
+            .register(
+                Some("watch".to_string()),
+                "local",
+                "grpc.health.v1.Health/Watch",
+            )
+            .unwrap();
+        call.record_message();
+        call.record_message();
+
+        assert!(
+            registry
+                .register(Some("watch".to_string()), "local", "x/y")
+                .is_err()
+        );
+        let listed = registry.list();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "watch");
+        assert_eq!(listed[0].server_id, "local");
+        assert_eq!(listed[0].messages_received, 2);
+
+        assert!(registry.cancel("watch"));
+        call.cancelled().await;
+        assert!(registry.list().is_empty());
+        assert!(!registry.cancel("watch"));
+    }
+
+    #[test]
+    fn test_dropped_handle_keeps_newer_call() {
+        let registry = CallRegistry::default();
+        let first = registry
+            .register(Some("a".to_string()), "local", "x/y")
+            .unwrap();
+        assert!(registry.cancel("a"));
+        let _second = registry
+            .register(Some("a".to_string()), "local", "x/y")
+            .unwrap();
+
+        drop(first);
+        assert_eq!(registry.list().len(), 1);
+
+        let generated = registry.register(None, "local", "x/y").unwrap();
+        assert!(!generated.id().is_empty());
+        drop(generated);
+        assert_eq!(registry.list().len(), 1);
+    }
+}
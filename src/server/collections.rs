@@ -0,0 +1,728 @@
+use crate::collection::{Collection, SavedRequest, collection_path, load_collection_file};
+use crate::query::Query;
+use crate::server::access::{Action, Identity, authorize, authorize_admin, can_access};
+use crate::server::environments::{call_template, request_environment};
+use crate::server::handlers::{ATTEMPTS_HEADER, CALL_ID_HEADER, ErrorResponse, run_call};
+use crate::server::state::AppState;
+use anyhow::{Context, Result};
+use axum::{
+    Extension, Json as RequestJson,
+    extract::{Path, Query as QueryParams, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::RwLock;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+/// Saved request collections, kept as one YAML file each in a directory
+#[derive(Clone, Default)]
+pub struct CollectionStore {
+    collections: Arc<RwLock<BTreeMap<String, Collection>>>,
+    dir: Option<PathBuf>,
+}
+
+impl CollectionStore {
+    /// Collections that are lost on restart
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load the collections saved in `dir`, which is created on the first
+    /// change. Files that can't be loaded are skipped with a warning.
+    pub async fn open(dir: impl AsRef<FsPath>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let mut collections = BTreeMap::new();
+
+        match fs::read_dir(&dir).await {
+            Ok(mut entries) => {
+                while let Some(entry) = entries.next_entry().await? {
+                    let path = entry.path();
+                    let is_yaml = path
+                        .extension()
+                        .is_some_and(|ext| ext == "yml" || ext == "yaml");
+                    if !is_yaml {
+                        continue;
+                    }
+                    match load_collection_file(&path) {
+                        Ok(collection) => {
+                            collections.insert(collection.name.clone(), collection);
+                        }
+                        Err(e) => warn!("Skipping collection: {:#}", e),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read collections directory: {}", dir.display())
+                });
+            }
+        }
+
+        Ok(Self {
+            collections: Arc::new(RwLock::new(collections)),
+            dir: Some(dir),
+        })
+    }
+
+    pub async fn list(&self) -> Vec<Collection> {
+        self.collections.read().await.values().cloned().collect()
+    }
+
+    pub async fn get(&self, name: &str) -> Option<Collection> {
+        self.collections.read().await.get(name).cloned()
+    }
+
+    /// Validate a collection and save it, replacing one with the same name
+    pub async fn save(&self, collection: Collection) -> Result<()> {
+        collection.validate()?;
+        let mut collections = self.collections.write().await;
+        self.write_file(&collection).await?;
+        collections.insert(collection.name.clone(), collection);
+        Ok(())
+    }
+
+    /// Change a collection in place and save it. Returns `None` when there is
+    /// no collection with this name.
+    pub async fn update(
+        &self,
+        name: &str,
+        change: impl FnOnce(&mut Collection),
+    ) -> Result<Option<Collection>> {
+        let mut collections = self.collections.write().await;
+        let Some(mut collection) = collections.get(name).cloned() else {
+            return Ok(None);
+        };
+        change(&mut collection);
+        collection.validate()?;
+        self.write_file(&collection).await?;
+        collections.insert(name.to_string(), collection.clone());
+        Ok(Some(collection))
+    }
+
+    /// Remove a collection and its file. Returns false when there is no
+    /// collection with this name.
+    pub async fn remove(&self, name: &str) -> Result<bool> {
+        let mut collections = self.collections.write().await;
+        if !collections.contains_key(name) {
+            return Ok(false);
+        }
+        if let Some(dir) = &self.dir {
+            let path = collection_path(dir, name);
+            match fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("Failed to remove collection file: {}", path.display())
+                    });
+                }
+            }
+        }
+        collections.remove(name);
+        Ok(true)
+    }
+
+    /// Write a collection file next to the target and rename it over it, so
+    /// readers never see a partial file
+    async fn write_file(&self, collection: &Collection) -> Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+        fs::create_dir_all(dir).await.with_context(|| {
+            format!("Failed to create collections directory: {}", dir.display())
+        })?;
+
+        let path = collection_path(dir, &collection.name);
+        let temp_path = path.with_extension(format!("yml.tmp-{}", std::process::id()));
+        fs::write(&temp_path, collection.to_yaml()?)
+            .await
+            .with_context(|| format!("Failed to write collection file: {}", temp_path.display()))?;
+        if let Err(e) = fs::rename(&temp_path, &path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e)
+                .with_context(|| format!("Failed to replace collection file: {}", path.display()));
+        }
+        Ok(())
+    }
+}
+
+fn api_error(status: StatusCode, error: &str, details: Option<String>) -> ApiError {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            details,
+        }),
+    )
+}
+
+fn collection_not_found() -> ApiError {
+    api_error(StatusCode::NOT_FOUND, "Collection not found", None)
+}
+
+/// Whether the caller may see a collection: they must be able to use every
+/// server its requests name
+fn collection_visible(state: &AppState, identity: &Identity, collection: &Collection) -> bool {
+    collection
+        .requests
+        .iter()
+        .filter_map(|request| request.server.as_deref())
+        .all(|server_id| can_access(state, identity, server_id, Action::View))
+}
+
+/// A collection the caller may see
+async fn visible_collection(
+    state: &AppState,
+    identity: &Identity,
+    name: &str,
+) -> Result<Collection, ApiError> {
+    state
+        .collections
+        .get(name)
+        .await
+        .filter(|collection| collection_visible(state, identity, collection))
+        .ok_or_else(collection_not_found)
+}
+
+fn authorize_change(state: &AppState, identity: &Identity) -> Result<(), ApiError> {
+    authorize_admin(state, identity, "change collections")
+}
+
+/// Map a failed save to 400 for invalid collections, 500 otherwise
+fn save_error(e: anyhow::Error) -> ApiError {
+    let invalid = e
+        .chain()
+        .all(|cause| cause.downcast_ref::<std::io::Error>().is_none());
+    if invalid {
+        api_error(
+            StatusCode::BAD_REQUEST,
+            "Invalid collection",
+            Some(format!("{:#}", e)),
+        )
+    } else {
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to save collection",
+            Some(format!("{:#}", e)),
+        )
+    }
+}
+
+/// List saved request collections
+#[utoipa::path(
+    get,
+    path = "/api/collections",
+    responses(
+        (status = 200, description = "Collections the caller may see, by name", body = Vec<Collection>)
+    ),
+    tag = "collections"
+)]
+pub async fn list_collections(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+) -> Json<Vec<Collection>> {
+    let mut collections = state.collections.list().await;
+    collections.retain(|collection| collection_visible(&state, &identity, collection));
+    Json(collections)
+}
+
+/// Get a collection
+#[utoipa::path(
+    get,
+    path = "/api/collections/{collection}",
+    params(
+        ("collection" = String, Path, description = "Collection name")
+    ),
+    responses(
+        (status = 200, description = "Collection", body = Collection),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn get_collection(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(name): Path<String>,
+) -> Result<Json<Collection>, ApiError> {
+    visible_collection(&state, &identity, &name).await.map(Json)
+}
+
+/// Create a collection; its name is taken from the path
+#[utoipa::path(
+    post,
+    path = "/api/collections/{collection}",
+    params(
+        ("collection" = String, Path, description = "Collection name")
+    ),
+    request_body = Collection,
+    responses(
+        (status = 201, description = "Collection created", body = Collection),
+        (status = 400, description = "Invalid collection", body = ErrorResponse),
+        (status = 403, description = "Changing collections is not allowed", body = ErrorResponse),
+        (status = 409, description = "Collection already exists", body = ErrorResponse),
+        (status = 500, description = "Failed to save collection", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn create_collection(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(name): Path<String>,
+    RequestJson(mut collection): RequestJson<Collection>,
+) -> Result<(StatusCode, Json<Collection>), ApiError> {
+    authorize_change(&state, &identity)?;
+    if state.collections.get(&name).await.is_some() {
+        return Err(api_error(
+            StatusCode::CONFLICT,
+            "Collection already exists",
+            Some(format!("Use PUT to update collection '{}'", name)),
+        ));
+    }
+
+    collection.name = name;
+    state
+        .collections
+        .save(collection.clone())
+        .await
+        .map_err(save_error)?;
+    Ok((StatusCode::CREATED, Json(collection)))
+}
+
+/// Replace a collection
+#[utoipa::path(
+    put,
+    path = "/api/collections/{collection}",
+    params(
+        ("collection" = String, Path, description = "Collection name")
+    ),
+    request_body = Collection,
+    responses(
+        (status = 200, description = "Collection updated", body = Collection),
+        (status = 400, description = "Invalid collection", body = ErrorResponse),
+        (status = 403, description = "Changing collections is not allowed", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 500, description = "Failed to save collection", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn update_collection(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(name): Path<String>,
+    RequestJson(mut collection): RequestJson<Collection>,
+) -> Result<Json<Collection>, ApiError> {
+    authorize_change(&state, &identity)?;
+    collection.name = name.clone();
+    state
+        .collections
+        .update(&name, |existing| *existing = collection)
+        .await
+        .map_err(save_error)?
+        .map(Json)
+        .ok_or_else(collection_not_found)
+}
+
+/// Remove a collection
+#[utoipa::path(
+    delete,
+    path = "/api/collections/{collection}",
+    params(
+        ("collection" = String, Path, description = "Collection name")
+    ),
+    responses(
+        (status = 204, description = "Collection removed"),
+        (status = 403, description = "Changing collections is not allowed", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 500, description = "Failed to remove collection", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn delete_collection(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    authorize_change(&state, &identity)?;
+    match state.collections.remove(&name).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(collection_not_found()),
+        Err(e) => Err(api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to remove collection",
+            Some(format!("{:#}", e)),
+        )),
+    }
+}
+
+/// Add or replace a saved request; its name is taken from the path
+#[utoipa::path(
+    put,
+    path = "/api/collections/{collection}/requests/{request_name}",
+    params(
+        ("collection" = String, Path, description = "Collection name"),
+        ("request_name" = String, Path, description = "Saved request name")
+    ),
+    request_body = SavedRequest,
+    responses(
+        (status = 200, description = "Request saved; returns the collection", body = Collection),
+        (status = 400, description = "Invalid request", body = ErrorResponse),
+        (status = 403, description = "Changing collections is not allowed", body = ErrorResponse),
+        (status = 404, description = "Collection not found", body = ErrorResponse),
+        (status = 500, description = "Failed to save collection", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn save_request(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path((name, request_name)): Path<(String, String)>,
+    RequestJson(mut request): RequestJson<SavedRequest>,
+) -> Result<Json<Collection>, ApiError> {
+    authorize_change(&state, &identity)?;
+    request.name = request_name;
+    state
+        .collections
+        .update(&name, |collection| collection.upsert_request(request))
+        .await
+        .map_err(save_error)?
+        .map(Json)
+        .ok_or_else(collection_not_found)
+}
+
+/// Remove a saved request
+#[utoipa::path(
+    delete,
+    path = "/api/collections/{collection}/requests/{request_name}",
+    params(
+        ("collection" = String, Path, description = "Collection name"),
+        ("request_name" = String, Path, description = "Saved request name")
+    ),
+    responses(
+        (status = 204, description = "Request removed"),
+        (status = 403, description = "Changing collections is not allowed", body = ErrorResponse),
+        (status = 404, description = "Collection or request not found", body = ErrorResponse),
+        (status = 500, description = "Failed to save collection", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn delete_request(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path((name, request_name)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    authorize_change(&state, &identity)?;
+    let mut removed = false;
+    state
+        .collections
+        .update(&name, |collection| {
+            let before = collection.requests.len();
+            collection.requests.retain(|r| r.name != request_name);
+            removed = collection.requests.len() < before;
+        })
+        .await
+        .map_err(save_error)?
+        .ok_or_else(collection_not_found)?;
+
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(api_error(
+            StatusCode::NOT_FOUND,
+            "Saved request not found",
+            None,
+        ))
+    }
+}
+
+/// Options for running a saved request
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSavedRequest {
+    /// Template variables referenced as {{var.name}} in the request and headers
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// JSONPath or jq-style query applied to each response message
+    #[serde(default)]
+    pub query: Option<String>,
+    /// ID to track the call under; generated when absent
+    #[serde(default)]
+    pub call_id: Option<String>,
+}
+
+/// Run a saved request on its server
+#[utoipa::path(
+    post,
+    path = "/api/collections/{collection}/requests/{request_name}/run",
+    params(
+        ("collection" = String, Path, description = "Collection name"),
+        ("request_name" = String, Path, description = "Saved request name")
+    ),
+    request_body(content = Option<RunSavedRequest>, description = "Optional variables, query and call ID"),
+    responses(
+        (status = 200, description = "Method call successful", body = Vec<Value>,
+            headers(
+                ("x-grpc-attempts" = u32, description = "Number of attempts made, greater than 1 when the call was retried"),
+                ("x-call-id" = String, description = "ID the call was tracked under")
+            )),
+        (status = 400, description = "No server, invalid query or templates, or connection failed", body = ErrorResponse),
+        (status = 403, description = "Calling the method is not allowed", body = ErrorResponse),
+        (status = 404, description = "Collection, request or server not found", body = ErrorResponse),
+        (status = 409, description = "A call with this ID is already running", body = ErrorResponse),
+        (status = 499, description = "Call cancelled", body = ErrorResponse),
+        (status = 500, description = "Call failed", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn run_saved_request(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path((name, request_name)): Path<(String, String)>,
+    session: HeaderMap,
+    options: Option<RequestJson<RunSavedRequest>>,
+) -> Result<(HeaderMap, Json<Vec<Value>>), ApiError> {
+    let RequestJson(options) = options.unwrap_or_default();
+    let collection = visible_collection(&state, &identity, &name).await?;
+    let saved = collection
+        .request(&request_name)
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "Saved request not found", None))?;
+
+    // The server and method are checked as they will be called, with
+    // templates expanded
+    let environment = request_environment(&state, &identity, &session);
+    let template = call_template(&state, environment.as_deref(), options.variables);
+    let saved = saved.render(&template).map_err(|e| {
+        api_error(
+            StatusCode::BAD_REQUEST,
+            "Failed to expand request templates",
+            Some(format!("{:#}", e)),
+        )
+    })?;
+    let server_id = saved.server.as_deref().ok_or_else(|| {
+        api_error(
+            StatusCode::BAD_REQUEST,
+            "The saved request has no server",
+            Some(format!("Set `server` on '{}/{}'", name, request_name)),
+        )
+    })?;
+    if state.get_server_config(server_id).is_none() {
+        return Err(api_error(
+            StatusCode::NOT_FOUND,
+            "Server configuration not found",
+            Some(format!("Server '{}' is not configured", server_id)),
+        ));
+    }
+    authorize(&state, &identity, server_id, Action::Call(&saved.method))?;
+
+    let query = options
+        .query
+        .as_deref()
+        .map(Query::parse)
+        .transpose()
+        .map_err(|e| {
+            api_error(
+                StatusCode::BAD_REQUEST,
+                "Invalid query",
+                Some(format!("{:#}", e)),
+            )
+        })?;
+    let headers: Vec<(String, String)> = saved.headers.clone().into_iter().collect();
+
+    let client = state
+        .get_client_in(server_id, environment.as_deref())
+        .await
+        .map_err(|e| {
+            api_error(
+                StatusCode::BAD_REQUEST,
+                "Failed to connect to server",
+                Some(e.to_string()),
+            )
+        })?;
+    let run = run_call(
+        &state,
+        server_id,
+        &client,
+        &saved.method,
+        saved.request.clone(),
+        headers,
+        options.call_id,
+    )
+    .await?;
+    let outcome = run.outcome?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ATTEMPTS_HEADER, HeaderValue::from(outcome.attempts));
+    if let Ok(call_id) = HeaderValue::from_str(&run.call_id) {
+        headers.insert(CALL_ID_HEADER, call_id);
+    }
+    let responses = match query {
+        Some(query) => query.apply_all(&outcome.responses),
+        None => outcome.responses,
+    };
+    Ok((headers, Json(responses)))
+}
+
+/// Download a collection as a YAML file
+#[utoipa::path(
+    get,
+    path = "/api/collections/{collection}/export",
+    params(
+        ("collection" = String, Path, description = "Collection name")
+    ),
+    responses(
+        (status = 200, description = "Collection file", content_type = "application/yaml", body = String),
+        (status = 404, description = "Collection not found", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn export_collection(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(name): Path<String>,
+) -> Result<(HeaderMap, String), ApiError> {
+    let collection = visible_collection(&state, &identity, &name).await?;
+    let yaml = collection.to_yaml().map_err(|e| {
+        api_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to export collection",
+            Some(format!("{:#}", e)),
+        )
+    })?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/yaml"),
+    );
+    if let Ok(disposition) =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}.yml\"", name))
+    {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+    Ok((headers, yaml))
+}
+
+/// Options for importing a collection file
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportOptions {
+    /// Replace a collection with the same name
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+/// Import a collection file, in YAML or JSON
+#[utoipa::path(
+    post,
+    path = "/api/collections/import",
+    params(ImportOptions),
+    request_body(content = String, description = "Collection file", content_type = "application/yaml"),
+    responses(
+        (status = 201, description = "Collection imported", body = Collection),
+        (status = 400, description = "Invalid collection file", body = ErrorResponse),
+        (status = 403, description = "Changing collections is not allowed", body = ErrorResponse),
+        (status = 409, description = "Collection already exists", body = ErrorResponse),
+        (status = 500, description = "Failed to save collection", body = ErrorResponse)
+    ),
+    tag = "collections"
+)]
+pub async fn import_collection(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    QueryParams(options): QueryParams<ImportOptions>,
+    body: String,
+) -> Result<(StatusCode, Json<Collection>), ApiError> {
+    authorize_change(&state, &identity)?;
+    let collection = Collection::from_yaml(&body).map_err(|e| {
+        api_error(
+            StatusCode::BAD_REQUEST,
+            "Invalid collection file",
+            Some(format!("{:#}", e)),
+        )
+    })?;
+    if !options.overwrite && state.collections.get(&collection.name).await.is_some() {
+        return Err(api_error(
+            StatusCode::CONFLICT,
+            "Collection already exists",
+            Some(format!(
+                "Pass overwrite=true to replace collection '{}'",
+                collection.name
+            )),
+        ));
+    }
+
+    state
+        .collections
+        .save(collection.clone())
+        .await
+        .map_err(save_error)?;
+    Ok((StatusCode::CREATED, Json(collection)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn users() -> Collection {
+        Collection {
+            name: "users".to_string(),
+            description: None,
+            requests: vec![SavedRequest {
+                name: "get-user".to_string(),
+                description: None,
+                server: Some("local".to_string()),
+                method: "example.UserService/GetUser".to_string(),
+                request: json!({"userId": "1"}),
+                headers: BTreeMap::new(),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_persists_collections() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CollectionStore::open(dir.path()).await.unwrap();
+        store.save(users()).await.unwrap();
+        store
+            .update("users", |c| c.description = Some("User calls".to_string()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(store.update("missing", |_| {}).await.unwrap().is_none());
+
+        let invalid = store
+            .update("users", |c| c.requests[0].method = "GetUser".to_string())
+            .await;
+        assert!(invalid.is_err());
+
+        let reopened = CollectionStore::open(dir.path()).await.unwrap();
+        let collection = reopened.get("users").await.unwrap();
+        assert_eq!(collection.description.as_deref(), Some("User calls"));
+        assert_eq!(collection.requests, users().requests);
+
+        assert!(reopened.remove("users").await.unwrap());
+        assert!(!reopened.remove("users").await.unwrap());
+        assert!(!dir.path().join("users.yml").exists());
+    }
+
+    #[test]
+    fn test_save_error_status() {
+        let mut invalid = users();
+        invalid.name = "..".to_string();
+        let invalid = invalid.validate().unwrap_err();
+        assert_eq!(save_error(invalid).0, StatusCode::BAD_REQUEST);
+
+        let io = anyhow::Error::new(std::io::Error::other("disk full")).context("Failed to write");
+        assert_eq!(save_error(io).0, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -1,17 +1,51 @@
-use anyhow::{Context, Result};
+use crate::auth::AuthConfig;
+use crate::client::GrpcClient;
+use crate::redact::RedactionConfig;
+use crate::retry::RetryPolicy;
+use crate::server::access::{AccessPolicy, Principals, WebConfig};
+use crate::template::TemplateEngine;
+use anyhow::{Context, Result, bail};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use tokio::fs;
+use utoipa::ToSchema;
 
 /// Configuration for the web server
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ServerConfig {
     /// Map of server ID to server configuration
     pub servers: HashMap<String, GrpcServerConfig>,
+    /// Map of environment name (e.g. dev, staging, prod) to its variables
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environments: HashMap<String, Environment>,
+    /// Environment used by sessions that have not selected one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_environment: Option<String>,
+    /// Listen address, CORS origins, authentication and admins of the web API
+    #[serde(default, skip_serializing_if = "WebConfig::is_default")]
+    pub web: WebConfig,
+}
+
+/// A set of variables referenced as `{{var.name}}` from server endpoints,
+/// headers and request bodies
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Environment {
+    /// Optional description of the environment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Variable values, such as tokens, tenant IDs and hosts
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// Users and roles who may use the environment through the web API;
+    /// anyone when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub access: Option<Principals>,
 }
 
 /// Configuration for a single gRPC server
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct GrpcServerConfig {
     /// Human-readable name for the server
     pub name: String,
@@ -22,11 +56,30 @@ pub struct GrpcServerConfig {
     pub plaintext: bool,
     /// Path to CA certificate file for TLS verification
     pub ca_cert: Option<String>,
-    /// Default headers to include with requests
+    /// Default headers to include with requests (`-bin` keys take base64 values)
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Extra headers sent only on reflection requests (in addition to `headers`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub reflect_headers: HashMap<String, String>,
     /// Optional description of the server
     pub description: Option<String>,
+    /// Authentication provider injecting credentials into every call
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub auth: Option<AuthConfig>,
+    /// Retry policy for unary calls failing with transient status codes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub retry: Option<RetryPolicy>,
+    /// Fields to mask in responses and logs (fields marked `debug_redact` by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub redaction: Option<RedactionConfig>,
+    /// Who may use this server through the web API, and whether it is read-only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub access: Option<AccessPolicy>,
 }
 
 impl ServerConfig {
@@ -49,14 +102,29 @@ impl ServerConfig {
         }
     }
 
-    /// Save configuration to a YAML file
+    /// Load configuration from an existing YAML file without creating a default one
+    pub fn from_file(config_path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(config_path)
+            .with_context(|| format!("Failed to read config file: {}", config_path))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse YAML config file: {}", config_path))
+    }
+
+    /// Save configuration to a YAML file. The file is written next to the
+    /// target and renamed over it, so readers never see a partial file.
     pub async fn save(&self, config_path: &str) -> Result<()> {
         let yaml_content =
             serde_yaml::to_string(self).context("Failed to serialize config to YAML")?;
 
-        fs::write(config_path, yaml_content)
+        let temp_path = format!("{}.tmp-{}", config_path, std::process::id());
+        fs::write(&temp_path, yaml_content)
             .await
-            .with_context(|| format!("Failed to write config file: {}", config_path))?;
+            .with_context(|| format!("Failed to write config file: {}", temp_path))?;
+        if let Err(e) = fs::rename(&temp_path, config_path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e)
+                .with_context(|| format!("Failed to replace config file: {}", config_path));
+        }
 
         println!("✅ Configuration saved to: {}", config_path);
         Ok(())
@@ -71,6 +139,87 @@ impl ServerConfig {
     pub fn server_ids(&self) -> Vec<&String> {
         self.servers.keys().collect()
     }
+
+    /// Template engine with the variables of an environment; without one,
+    /// or for an unknown one, no variables are defined
+    pub fn template(&self, environment: Option<&str>) -> TemplateEngine {
+        let vars = environment
+            .and_then(|name| self.environments.get(name))
+            .map(|env| env.variables.clone())
+            .unwrap_or_default();
+        TemplateEngine::with_vars(vars)
+    }
+
+    /// Check that a server configuration sent to the web API is valid in
+    /// every environment, or without variables when there are no
+    /// environments. `{{env.NAME}}` is rejected, so API callers can't read
+    /// the server's environment.
+    pub fn validate_server(&self, conf: &GrpcServerConfig) -> Result<()> {
+        if self.environments.is_empty() {
+            return conf.validate_with_template(&TemplateEngine::new().without_env());
+        }
+        let mut names: Vec<&String> = self.environments.keys().collect();
+        names.sort();
+        for name in names {
+            conf.validate_with_template(&self.template(Some(name)).without_env())
+                .with_context(|| format!("Invalid in environment '{}'", name))?;
+        }
+        Ok(())
+    }
+}
+
+/// Check that a server ID can be used in API paths
+pub fn validate_server_id(server_id: &str) -> Result<()> {
+    let valid = !server_id.is_empty()
+        && server_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "Invalid server ID '{}': use letters, digits, '-', '_' and '.'",
+            server_id
+        );
+    }
+    Ok(())
+}
+
+impl GrpcServerConfig {
+    /// Whether a client built from `other` would connect or behave
+    /// differently, i.e. anything besides the name, description and access
+    /// policy changed
+    pub fn affects_client(&self, other: &Self) -> bool {
+        let connection = |conf: &Self| Self {
+            name: String::new(),
+            description: None,
+            access: None,
+            ..conf.clone()
+        };
+        connection(self) != connection(other)
+    }
+
+    /// Check that a client can be built from this configuration: the endpoint,
+    /// headers, auth and redaction settings must be valid and the CA
+    /// certificate must exist.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_with_template(&TemplateEngine::new())
+    }
+
+    /// Like [`GrpcServerConfig::validate`], expanding templates with `template`
+    pub fn validate_with_template(&self, template: &TemplateEngine) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Server name must not be empty");
+        }
+        if self.endpoint.trim().is_empty() {
+            bail!("Server endpoint must not be empty");
+        }
+        if let Some(ca_cert) = &self.ca_cert
+            && !Path::new(ca_cert).is_file()
+        {
+            bail!("CA certificate not found: {}", ca_cert);
+        }
+        GrpcClient::from_config_with_template(self, template)?;
+        Ok(())
+    }
 }
 
 impl Default for ServerConfig {
@@ -87,6 +236,11 @@ impl Default for ServerConfig {
                 ca_cert: None,
                 headers: HashMap::new(),
                 description: Some("Local development gRPC server".to_string()),
+                auth: None,
+                retry: None,
+                redaction: None,
+                reflect_headers: HashMap::new(),
+                access: None,
             },
         );
 
@@ -99,10 +253,20 @@ impl Default for ServerConfig {
                 ca_cert: None,
                 headers: HashMap::new(),
                 description: Some("Public gRPC server with reflection enabled".to_string()),
+                auth: None,
+                retry: None,
+                redaction: None,
+                reflect_headers: HashMap::new(),
+                access: None,
             },
         );
 
-        Self { servers }
+        Self {
+            servers,
+            environments: HashMap::new(),
+            default_environment: None,
+            web: WebConfig::default(),
+        }
     }
 }
 
@@ -118,6 +282,29 @@ mod tests {
         assert!(config.servers.contains_key("reflection-demo"));
     }
 
+    #[test]
+    fn test_environments_yaml() {
+        let config: ServerConfig = serde_yaml::from_str(
+            r#"
+servers: {}
+default_environment: dev
+environments:
+  dev:
+    variables:
+      tenant: acme
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.default_environment.as_deref(), Some("dev"));
+        let template = config.template(Some("dev"));
+        assert_eq!(template.render("{{var.tenant}}").unwrap(), "acme");
+        assert!(config.template(Some("prod")).vars().is_empty());
+
+        // Files without environments are unchanged when saved
+        let yaml = serde_yaml::to_string(&ServerConfig::default()).unwrap();
+        assert!(!yaml.contains("environments"));
+    }
+
     #[tokio::test]
     async fn test_config_save_load() {
         let config = ServerConfig::default();
@@ -133,4 +320,59 @@ mod tests {
         assert_eq!(config.servers.len(), loaded_config.servers.len());
         assert!(loaded_config.servers.contains_key("local"));
     }
+
+    #[test]
+    fn test_validate_server_config() {
+        let mut config = ServerConfig::default().servers.remove("local").unwrap();
+        assert!(config.validate().is_ok());
+
+        config.endpoint = "localhost:grpc".to_string();
+        assert!(config.validate().is_err());
+        config.endpoint = " ".to_string();
+        assert!(config.validate().is_err());
+
+        config.endpoint = "localhost:9090".to_string();
+        config.ca_cert = Some("/nonexistent/ca.pem".to_string());
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("CA certificate not found"));
+
+        // Templates are expanded with the variables of every environment
+        let mut config_file = ServerConfig::default();
+        config.ca_cert = None;
+        config.endpoint = "{{var.host}}:9090".to_string();
+        assert!(config.validate().is_err());
+        assert!(config_file.validate_server(&config).is_err());
+        for (name, host) in [("dev", "localhost"), ("prod", "grpc.example.com")] {
+            config_file.environments.insert(
+                name.to_string(),
+                Environment {
+                    description: None,
+                    variables: HashMap::from([("host".to_string(), host.to_string())]),
+                    access: None,
+                },
+            );
+        }
+        assert!(config_file.validate_server(&config).is_ok());
+        config_file
+            .environments
+            .get_mut("prod")
+            .unwrap()
+            .variables
+            .clear();
+        let err = config_file.validate_server(&config).unwrap_err();
+        assert!(format!("{:#}", err).contains("environment 'prod'"));
+
+        // Configurations from the API can't read the server's environment
+        config.endpoint = "localhost:9090".to_string();
+        config
+            .headers
+            .insert("x-home".to_string(), "{{env.HOME}}".to_string());
+        assert!(config.validate().is_ok());
+        assert!(ServerConfig::default().validate_server(&config).is_err());
+        assert!(config_file.validate_server(&config).is_err());
+
+        assert!(validate_server_id("user-service_2.prod").is_ok());
+        assert!(validate_server_id("").is_err());
+        assert!(validate_server_id("a/b").is_err());
+    }
 }
@@ -0,0 +1,176 @@
+use crate::server::access::{Identity, authorize_environment, can_use_environment};
+use crate::server::handlers::ErrorResponse;
+use crate::server::state::{AppState, SessionKey};
+use crate::template::TemplateEngine;
+use axum::{
+    Extension, Json as RequestJson,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Request header identifying the session whose environment applies.
+/// Requests without it share one session.
+pub const SESSION_HEADER: &str = "x-session-id";
+
+/// Session of a request, from the caller and the `x-session-id` header
+pub fn session_key(identity: &Identity, headers: &HeaderMap) -> SessionKey {
+    let id = headers
+        .get(SESSION_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    (identity.user.clone(), id.to_string())
+}
+
+/// Environment active for the session of a request, unless the caller may
+/// no longer use it
+pub fn request_environment(
+    state: &AppState,
+    identity: &Identity,
+    headers: &HeaderMap,
+) -> Option<String> {
+    state
+        .active_environment(&session_key(identity, headers))
+        .filter(|name| can_use_environment(state, identity, name))
+}
+
+/// Template engine for the request of a call: the environment's variables,
+/// overridden by the variables sent with the call. Callers can't read the
+/// server's environment variables.
+pub fn call_template(
+    state: &AppState,
+    environment: Option<&str>,
+    variables: HashMap<String, String>,
+) -> TemplateEngine {
+    let mut template = state.environment_template(environment).without_env();
+    for (key, value) in variables {
+        template.set(key, value);
+    }
+    template
+}
+
+/// An environment, for API responses. Variable values are left out, as they
+/// often hold credentials.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentInfo {
+    /// Environment name
+    pub name: String,
+    /// Optional description of the environment
+    pub description: Option<String>,
+    /// Names of the variables the environment defines
+    pub variables: Vec<String>,
+}
+
+/// Environments the caller may use and the one active for the session
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentList {
+    /// Environment applied to the session's calls, if any
+    pub active: Option<String>,
+    /// Environment used by sessions that have not selected one
+    pub default: Option<String>,
+    /// Environments the caller may use, by name
+    pub environments: Vec<EnvironmentInfo>,
+}
+
+/// Request to switch the session's environment
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SelectEnvironment {
+    /// Environment to activate; `null` returns to the default environment
+    pub name: Option<String>,
+}
+
+fn environment_list(state: &AppState, identity: &Identity, headers: &HeaderMap) -> EnvironmentList {
+    let mut environments: Vec<EnvironmentInfo> = state
+        .config
+        .read()
+        .unwrap()
+        .environments
+        .iter()
+        .filter(|(_, env)| {
+            env.access
+                .as_ref()
+                .is_none_or(|principals| principals.matches(identity))
+        })
+        .map(|(name, env)| {
+            let mut variables: Vec<String> = env.variables.keys().cloned().collect();
+            variables.sort();
+            EnvironmentInfo {
+                name: name.clone(),
+                description: env.description.clone(),
+                variables,
+            }
+        })
+        .collect();
+    environments.sort_by(|a, b| a.name.cmp(&b.name));
+
+    EnvironmentList {
+        active: request_environment(state, identity, headers),
+        default: state
+            .default_environment()
+            .filter(|name| can_use_environment(state, identity, name)),
+        environments,
+    }
+}
+
+/// List environments and the one active for the session
+#[utoipa::path(
+    get,
+    path = "/api/environments",
+    params(
+        ("x-session-id" = Option<String>, Header, description = "Session ID; requests without one share a session")
+    ),
+    responses(
+        (status = 200, description = "Environments", body = EnvironmentList)
+    ),
+    tag = "environments"
+)]
+pub async fn list_environments(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    headers: HeaderMap,
+) -> Json<EnvironmentList> {
+    Json(environment_list(&state, &identity, &headers))
+}
+
+/// Switch the environment whose variables apply to the session's calls
+#[utoipa::path(
+    put,
+    path = "/api/environments/active",
+    params(
+        ("x-session-id" = Option<String>, Header, description = "Session ID; requests without one share a session")
+    ),
+    request_body = SelectEnvironment,
+    responses(
+        (status = 200, description = "Environment switched", body = EnvironmentList),
+        (status = 403, description = "Using the environment is not allowed", body = ErrorResponse),
+        (status = 404, description = "Environment not found", body = ErrorResponse)
+    ),
+    tag = "environments"
+)]
+pub async fn select_environment(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    headers: HeaderMap,
+    RequestJson(request): RequestJson<SelectEnvironment>,
+) -> Result<Json<EnvironmentList>, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(name) = &request.name {
+        authorize_environment(&state, &identity, name)?;
+    }
+    state
+        .select_environment(&session_key(&identity, &headers), request.name.as_deref())
+        .map_err(|e| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Environment not found".to_string(),
+                    details: Some(e.to_string()),
+                }),
+            )
+        })?;
+    Ok(Json(environment_list(&state, &identity, &headers)))
+}
@@ -1,16 +1,44 @@
+use crate::client::{CallOutcome, GrpcClient};
+use crate::query::Query;
+use crate::server::access::{
+    Action, Identity, authorize, authorize_admin, authorize_server_auth, can_access,
+};
+use crate::server::calls::ActiveCall;
+use crate::server::config::{GrpcServerConfig, validate_server_id};
+use crate::server::environments::{call_template, request_environment};
+use crate::server::history::{CallRecorder, HistoryPage, HistoryQuery, HistoryRecord};
 use crate::server::schema::SchemaProcessor;
-use crate::server::state::{AppState, ServerStatus};
+use crate::server::state::{AppState, ConfigEvent, ServerStatus};
 use axum::{
-    Json as RequestJson,
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    Extension, Json as RequestJson,
+    extract::{Path, Query as QueryParams, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{
+        Json,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
+use futures::Stream;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::HashMap;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::{Code, Status};
 use utoipa::ToSchema;
 
+/// Response header reporting how many attempts a call took, including retries
+pub const ATTEMPTS_HEADER: &str = "x-grpc-attempts";
+
+/// Response header carrying the ID a call was registered under
+pub const CALL_ID_HEADER: &str = "x-call-id";
+
+/// Status of a call cancelled with `DELETE /api/calls/{id}`, after nginx's
+/// "client closed request"
+pub fn cancelled_status() -> StatusCode {
+    StatusCode::from_u16(499).expect("499 is a valid status code")
+}
+
 /// Response structure for API errors
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -29,12 +57,23 @@ pub struct CallRequest {
     pub method: String,
     /// JSON request data
     pub data: Value,
-    /// Custom headers to include in the gRPC call
+    /// Custom headers to include in the gRPC call (keys ending in `-bin` take base64 values)
     #[serde(default)]
     pub headers: HashMap<String, String>,
     /// Whether to emit default values in the response
     #[serde(default)]
     pub emit_defaults: bool,
+    /// Template variables referenced as {{var.name}} in data and headers
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+    /// JSONPath or jq-style query applied to each response message; the
+    /// projected values are returned in place of the responses
+    #[serde(default)]
+    pub query: Option<String>,
+    /// ID to track the call under, for cancelling it with
+    /// `DELETE /api/calls/{id}`; generated when absent
+    #[serde(default)]
+    pub call_id: Option<String>,
 }
 
 /// Service information for API responses
@@ -96,8 +135,221 @@ pub async fn health_check() -> Json<Value> {
     ),
     tag = "servers"
 )]
-pub async fn list_servers(State(state): State<AppState>) -> Json<Vec<ServerStatus>> {
-    Json(state.get_connection_status())
+pub async fn list_servers(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+) -> Json<Vec<ServerStatus>> {
+    let mut servers = state.get_connection_status();
+    servers.retain(|server| can_access(&state, &identity, &server.id, Action::View));
+    Json(servers)
+}
+
+/// Add a server to the configuration
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}",
+    params(
+        ("server_id" = String, Path, description = "Server identifier")
+    ),
+    request_body = GrpcServerConfig,
+    responses(
+        (status = 201, description = "Server added", body = ServerStatus),
+        (status = 400, description = "Invalid server configuration", body = ErrorResponse),
+        (status = 403, description = "Changing servers is not allowed", body = ErrorResponse),
+        (status = 409, description = "Server already exists", body = ErrorResponse),
+        (status = 500, description = "Failed to save configuration", body = ErrorResponse)
+    ),
+    tag = "servers"
+)]
+pub async fn create_server(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(server_id): Path<String>,
+    RequestJson(conf): RequestJson<GrpcServerConfig>,
+) -> Result<(StatusCode, Json<ServerStatus>), (StatusCode, Json<ErrorResponse>)> {
+    authorize_admin(&state, &identity, "change the server configuration")?;
+    if state.get_server_config(&server_id).is_some() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "Server already exists".to_string(),
+                details: Some(format!("Use PUT to update server '{}'", server_id)),
+            }),
+        ));
+    }
+
+    let status = save_server(&state, &server_id, conf).await?;
+    Ok((StatusCode::CREATED, Json(status)))
+}
+
+/// Replace the configuration of a server
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}",
+    params(
+        ("server_id" = String, Path, description = "Server identifier")
+    ),
+    request_body = GrpcServerConfig,
+    responses(
+        (status = 200, description = "Server updated", body = ServerStatus),
+        (status = 400, description = "Invalid server configuration", body = ErrorResponse),
+        (status = 403, description = "Changing servers is not allowed", body = ErrorResponse),
+        (status = 404, description = "Server not found", body = ErrorResponse),
+        (status = 500, description = "Failed to save configuration", body = ErrorResponse)
+    ),
+    tag = "servers"
+)]
+pub async fn update_server(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(server_id): Path<String>,
+    RequestJson(conf): RequestJson<GrpcServerConfig>,
+) -> Result<Json<ServerStatus>, (StatusCode, Json<ErrorResponse>)> {
+    authorize_admin(&state, &identity, "change the server configuration")?;
+    if state.get_server_config(&server_id).is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Server configuration not found".to_string(),
+                details: None,
+            }),
+        ));
+    }
+
+    let status = save_server(&state, &server_id, conf).await?;
+    Ok(Json(status))
+}
+
+/// Remove a server from the configuration
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{server_id}",
+    params(
+        ("server_id" = String, Path, description = "Server identifier")
+    ),
+    responses(
+        (status = 204, description = "Server removed"),
+        (status = 403, description = "Changing servers is not allowed", body = ErrorResponse),
+        (status = 404, description = "Server not found", body = ErrorResponse),
+        (status = 500, description = "Failed to save configuration", body = ErrorResponse)
+    ),
+    tag = "servers"
+)]
+pub async fn delete_server(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(server_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    authorize_admin(&state, &identity, "change the server configuration")?;
+    match state.remove_server(&server_id).await {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Server configuration not found".to_string(),
+                details: None,
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "Failed to save configuration".to_string(),
+                details: Some(format!("{:#}", e)),
+            }),
+        )),
+    }
+}
+
+/// Validate a server configuration and store it
+async fn save_server(
+    state: &AppState,
+    server_id: &str,
+    conf: GrpcServerConfig,
+) -> Result<ServerStatus, (StatusCode, Json<ErrorResponse>)> {
+    authorize_server_auth(state, server_id, &conf)?;
+    let valid = validate_server_id(server_id)
+        .and_then(|_| state.config.read().unwrap().validate_server(&conf));
+    if let Err(e) = valid {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Invalid server configuration".to_string(),
+                details: Some(format!("{:#}", e)),
+            }),
+        ));
+    }
+
+    if let Err(e) = state.put_server(server_id, conf).await {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: "Failed to save configuration".to_string(),
+                details: Some(format!("{:#}", e)),
+            }),
+        ));
+    }
+
+    state.get_server_status(server_id).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Server configuration not found".to_string(),
+                details: None,
+            }),
+        )
+    })
+}
+
+/// Stream configuration changes as server-sent events
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    responses(
+        (status = 200, description = "Stream of `config` events, one JSON ConfigEvent each",
+            content_type = "text/event-stream", body = ConfigEvent)
+    ),
+    tag = "servers"
+)]
+pub async fn config_events(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.subscribe();
+    let events = futures::stream::unfold(receiver, move |mut receiver| {
+        let state = state.clone();
+        let identity = identity.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if !event_visible(&state, &identity, &event) {
+                            continue;
+                        }
+                        let event = Event::default()
+                            .event("config")
+                            .json_data(&event)
+                            .unwrap_or_default();
+                        return Some((Ok(event), receiver));
+                    }
+                    // Slow subscribers miss old events but keep receiving new ones
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Whether the caller may see a configuration event. Events of removed
+/// servers are sent to everyone, as their access policy is gone.
+fn event_visible(state: &AppState, identity: &Identity, event: &ConfigEvent) -> bool {
+    match event {
+        ConfigEvent::ServerAdded { server_id } | ConfigEvent::ServerUpdated { server_id, .. } => {
+            can_access(state, identity, server_id, Action::View)
+        }
+        ConfigEvent::ServerRemoved { .. } | ConfigEvent::ReloadFailed { .. } => true,
+    }
 }
 
 /// List services for a specific server
@@ -109,6 +361,7 @@ pub async fn list_servers(State(state): State<AppState>) -> Json<Vec<ServerStatu
     ),
     responses(
         (status = 200, description = "List of services for the server"),
+        (status = 403, description = "Access to the server denied", body = ErrorResponse),
         (status = 404, description = "Server not found", body = ErrorResponse),
         (status = 400, description = "Failed to connect to server", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
@@ -117,7 +370,9 @@ pub async fn list_servers(State(state): State<AppState>) -> Json<Vec<ServerStatu
 )]
 pub async fn list_services(
     State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
     Path(server_id): Path<String>,
+    headers: HeaderMap,
 ) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
     // Verify server exists
     if state.get_server_config(&server_id).is_none() {
@@ -129,9 +384,14 @@ pub async fn list_services(
             }),
         ));
     }
+    authorize(&state, &identity, &server_id, Action::View)?;
 
-    // Get client for the server
-    let client = match state.get_client(&server_id).await {
+    // Get client for the server, in the session's environment
+    let environment = request_environment(&state, &identity, &headers);
+    let client = match state
+        .get_client_in(&server_id, environment.as_deref())
+        .await
+    {
         Ok(client) => client,
         Err(e) => {
             return Err((
@@ -170,6 +430,7 @@ pub async fn list_services(
     ),
     responses(
         (status = 200, description = "Service description with methods", body = ServiceInfo),
+        (status = 403, description = "Access to the server denied", body = ErrorResponse),
         (status = 404, description = "Server not found", body = ErrorResponse),
         (status = 400, description = "Failed to connect to server", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
@@ -178,7 +439,9 @@ pub async fn list_services(
 )]
 pub async fn describe_service(
     State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
     Path((server_id, service_name)): Path<(String, String)>,
+    headers: HeaderMap,
 ) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
     // Verify server exists
     if state.get_server_config(&server_id).is_none() {
@@ -190,9 +453,14 @@ pub async fn describe_service(
             }),
         ));
     }
+    authorize(&state, &identity, &server_id, Action::View)?;
 
-    // Get client for the server
-    let client = match state.get_client(&server_id).await {
+    // Get client for the server, in the session's environment
+    let environment = request_environment(&state, &identity, &headers);
+    let client = match state
+        .get_client_in(&server_id, environment.as_deref())
+        .await
+    {
         Ok(client) => client,
         Err(e) => {
             return Err((
@@ -231,18 +499,27 @@ pub async fn describe_service(
     ),
     request_body = CallRequest,
     responses(
-        (status = 200, description = "Method call successful", body = Vec<Value>),
+        (status = 200, description = "Method call successful", body = Vec<Value>,
+            headers(
+                ("x-grpc-attempts" = u32, description = "Number of attempts made, greater than 1 when the call was retried"),
+                ("x-call-id" = String, description = "ID the call was tracked under")
+            )),
+        (status = 403, description = "Calling the method is not allowed", body = ErrorResponse),
         (status = 404, description = "Server not found", body = ErrorResponse),
-        (status = 400, description = "Invalid request or connection failed", body = ErrorResponse),
+        (status = 400, description = "Invalid request, query or connection failed", body = ErrorResponse),
+        (status = 409, description = "A call with this ID is already running", body = ErrorResponse),
+        (status = 499, description = "Call cancelled", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     ),
     tag = "grpc"
 )]
 pub async fn call_method(
     State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
     Path(server_id): Path<String>,
+    session: HeaderMap,
     RequestJson(request): RequestJson<CallRequest>,
-) -> Result<Json<Vec<Value>>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<(HeaderMap, Json<Vec<Value>>), (StatusCode, Json<ErrorResponse>)> {
     if state.get_server_config(&server_id).is_none() {
         return Err((
             StatusCode::NOT_FOUND,
@@ -252,8 +529,26 @@ pub async fn call_method(
             }),
         ));
     }
+    authorize(&state, &identity, &server_id, Action::Call(&request.method))?;
+
+    let query = match request.query.as_deref().map(Query::parse).transpose() {
+        Ok(query) => query,
+        Err(e) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "Invalid query".to_string(),
+                    details: Some(format!("{:#}", e)),
+                }),
+            ));
+        }
+    };
 
-    let client = match state.get_client(&server_id).await {
+    let environment = request_environment(&state, &identity, &session);
+    let client = match state
+        .get_client_in(&server_id, environment.as_deref())
+        .await
+    {
         Ok(client) => client,
         Err(e) => {
             return Err((
@@ -266,20 +561,354 @@ pub async fn call_method(
         }
     };
 
-    let ret = client
-        .handle_call(&request.method, request.data)
-        .await
+    let template = call_template(&state, environment.as_deref(), request.variables);
+    let headers: Vec<(String, String)> = request.headers.into_iter().collect();
+    let (data, headers) = match template
+        .render_value(request.data)
+        .and_then(|data| Ok((data, template.render_headers(&headers)?)))
+    {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "Failed to expand request templates".to_string(),
+                    details: Some(e.to_string()),
+                }),
+            ));
+        }
+    };
+    let run = run_call(
+        &state,
+        &server_id,
+        &client,
+        &request.method,
+        data,
+        headers,
+        request.call_id,
+    )
+    .await?;
+    let outcome = run.outcome?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ATTEMPTS_HEADER, HeaderValue::from(outcome.attempts));
+    if let Ok(call_id) = HeaderValue::from_str(&run.call_id) {
+        headers.insert(CALL_ID_HEADER, call_id);
+    }
+
+    let responses = match query {
+        Some(query) => query.apply_all(&outcome.responses),
+        None => outcome.responses,
+    };
+    Ok((headers, Json(responses)))
+}
+
+/// A call made for the API, after it was recorded in history
+pub(crate) struct CallRun {
+    pub call_id: String,
+    pub outcome: Result<CallOutcome, (StatusCode, Json<ErrorResponse>)>,
+    pub record: HistoryRecord,
+}
+
+/// Make a call on a server, tracking it in the call registry while it runs
+/// and recording it in history once it ends
+pub(crate) async fn run_call(
+    state: &AppState,
+    server_id: &str,
+    client: &GrpcClient,
+    method: &str,
+    data: Value,
+    headers: Vec<(String, String)>,
+    call_id: Option<String>,
+) -> Result<CallRun, (StatusCode, Json<ErrorResponse>)> {
+    let call = state
+        .calls
+        .register(call_id, server_id, method)
         .map_err(|e| {
             (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse {
+                    error: "Call ID already in use".to_string(),
+                    details: Some(e.to_string()),
+                }),
+            )
+        })?;
+    let mut recorder = CallRecorder::new(&state.history, server_id, method, &headers)
+        .with_redactor(client.redactor.clone());
+    recorder.request(data.clone());
+    let client = client.with_headers(headers).with_counter(call.counter());
+
+    // Cancelling drops the call future, which resets its HTTP/2 stream
+    let result = tokio::select! {
+        outcome = client.execute_call(method, data) => Some(outcome),
+        _ = call.cancelled() => None,
+    };
+
+    let outcome = match result {
+        Some(Ok(outcome)) => {
+            if let Some(descriptor) = &outcome.input_descriptor {
+                recorder.set_input_descriptor(descriptor.clone());
+            }
+            for response in &outcome.responses {
+                recorder.response(response.clone());
+            }
+            Ok(outcome)
+        }
+        Some(Err(e)) => {
+            let code = e
+                .chain()
+                .find_map(|cause| cause.downcast_ref::<Status>())
+                .map_or(Code::Unknown, Status::code);
+            Err((
+                code,
                 StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to call method",
+                format!("{:#}", e),
+            ))
+        }
+        None => Err((
+            Code::Cancelled,
+            cancelled_status(),
+            "Call cancelled",
+            format!("Call '{}' was cancelled", call.id()),
+        )),
+    };
+
+    // Failed calls may still have sent the request, so mask it all the same
+    if outcome.is_err()
+        && client.redactor.is_some()
+        && let Ok(descriptor) = client.input_descriptor(method).await
+    {
+        recorder.set_input_descriptor(descriptor);
+    }
+    let record = match &outcome {
+        Ok(_) => recorder.finish(Code::Ok, None).await,
+        Err((code, _, _, details)) => recorder.finish(*code, Some(details.clone())).await,
+    };
+
+    Ok(CallRun {
+        call_id: call.id().to_string(),
+        outcome: outcome.map_err(|(_, status, error, details)| {
+            (
+                status,
                 Json(ErrorResponse {
-                    error: "Failed to call method".to_string(),
+                    error: error.to_string(),
+                    details: Some(details),
+                }),
+            )
+        }),
+        record,
+    })
+}
+
+/// Search the calls made through the API, newest first
+#[utoipa::path(
+    get,
+    path = "/api/history",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "Page of matching calls", body = HistoryPage)
+    ),
+    tag = "history"
+)]
+pub async fn list_history(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    QueryParams(query): QueryParams<HistoryQuery>,
+) -> Json<HistoryPage> {
+    let page = state
+        .history
+        .search_visible(&query, |record| {
+            can_access(&state, &identity, &record.server_id, Action::View)
+        })
+        .await;
+    Json(page)
+}
+
+/// Get a recorded call
+#[utoipa::path(
+    get,
+    path = "/api/history/{record_id}",
+    params(
+        ("record_id" = String, Path, description = "History record identifier")
+    ),
+    responses(
+        (status = 200, description = "Recorded call", body = HistoryRecord),
+        (status = 404, description = "Record not found", body = ErrorResponse)
+    ),
+    tag = "history"
+)]
+pub async fn get_history(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(record_id): Path<String>,
+) -> Result<Json<HistoryRecord>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .history
+        .get(&record_id)
+        .await
+        .filter(|record| can_access(&state, &identity, &record.server_id, Action::View))
+        .map(Json)
+        .ok_or_else(history_not_found)
+}
+
+/// Make a recorded call again with the same request and headers
+#[utoipa::path(
+    post,
+    path = "/api/history/{record_id}/replay",
+    params(
+        ("record_id" = String, Path, description = "History record identifier")
+    ),
+    responses(
+        (status = 200, description = "Record of the new call, whatever its status", body = HistoryRecord),
+        (status = 403, description = "Calling the method is not allowed", body = ErrorResponse),
+        (status = 404, description = "Record or server not found", body = ErrorResponse),
+        (status = 409, description = "The record has masked values", body = ErrorResponse),
+        (status = 400, description = "Connection failed", body = ErrorResponse)
+    ),
+    tag = "history"
+)]
+pub async fn replay_history(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(record_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<HistoryRecord>, (StatusCode, Json<ErrorResponse>)> {
+    let record = state
+        .history
+        .get(&record_id)
+        .await
+        .filter(|record| can_access(&state, &identity, &record.server_id, Action::View))
+        .ok_or_else(history_not_found)?;
+
+    if state.get_server_config(&record.server_id).is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Server configuration not found".to_string(),
+                details: Some(format!("Server '{}' was removed", record.server_id)),
+            }),
+        ));
+    }
+    authorize(
+        &state,
+        &identity,
+        &record.server_id,
+        Action::Call(&record.method),
+    )?;
+    // Masked values would be sent as "[REDACTED]", overriding credentials
+    // and corrupting data
+    if record.redacted {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: "The record can't be replayed".to_string(),
+                details: Some(
+                    "Sensitive request fields or headers were masked when the call was recorded"
+                        .to_string(),
+                ),
+            }),
+        ));
+    }
+    let environment = request_environment(&state, &identity, &headers);
+    let client = state
+        .get_client_in(&record.server_id, environment.as_deref())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "Failed to connect to server".to_string(),
                     details: Some(e.to_string()),
                 }),
             )
         })?;
 
-    Ok(Json(ret))
+    let run = run_call(
+        &state,
+        &record.server_id,
+        &client,
+        &record.method,
+        record.request,
+        record.headers.into_iter().collect(),
+        None,
+    )
+    .await?;
+    Ok(Json(run.record))
+}
+
+fn history_not_found() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: "History record not found".to_string(),
+            details: None,
+        }),
+    )
+}
+
+/// List calls started through the API that are still running
+#[utoipa::path(
+    get,
+    path = "/api/calls",
+    responses(
+        (status = 200, description = "Running calls, oldest first", body = Vec<ActiveCall>)
+    ),
+    tag = "grpc"
+)]
+pub async fn list_calls(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+) -> Json<Vec<ActiveCall>> {
+    let mut calls = state.calls.list();
+    calls.retain(|call| can_access(&state, &identity, &call.server_id, Action::View));
+    Json(calls)
+}
+
+/// Cancel a running call
+#[utoipa::path(
+    delete,
+    path = "/api/calls/{call_id}",
+    params(
+        ("call_id" = String, Path, description = "Call identifier")
+    ),
+    responses(
+        (status = 204, description = "Call cancelled"),
+        (status = 403, description = "Calling the method is not allowed", body = ErrorResponse),
+        (status = 404, description = "No running call has this ID", body = ErrorResponse)
+    ),
+    tag = "grpc"
+)]
+pub async fn cancel_call(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(call_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<ErrorResponse>)> {
+    // Only those who may make a call may cancel it
+    if let Some(call) = state
+        .calls
+        .list()
+        .into_iter()
+        .find(|call| call.id == call_id)
+    {
+        authorize(
+            &state,
+            &identity,
+            &call.server_id,
+            Action::Call(&call.method),
+        )?;
+    }
+    if state.calls.cancel(&call_id) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: "Call not found".to_string(),
+                details: None,
+            }),
+        ))
+    }
 }
 
 /// Generate JSON schema for a method's input type
@@ -293,6 +922,7 @@ pub async fn call_method(
     ),
     responses(
         (status = 200, description = "JSON schema for method input"),
+        (status = 403, description = "Access to the server denied", body = ErrorResponse),
         (status = 404, description = "Server, service, or method not found", body = ErrorResponse),
         (status = 400, description = "Failed to connect to server", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
@@ -301,7 +931,9 @@ pub async fn call_method(
 )]
 pub async fn describe_method(
     State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
     Path((server_id, service_name, method_name)): Path<(String, String, String)>,
+    headers: HeaderMap,
 ) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
     // Verify server exists
     if state.get_server_config(&server_id).is_none() {
@@ -313,9 +945,14 @@ pub async fn describe_method(
             }),
         ));
     }
+    authorize(&state, &identity, &server_id, Action::View)?;
 
-    // Get client for the server
-    let client = match state.get_client(&server_id).await {
+    // Get client for the server, in the session's environment
+    let environment = request_environment(&state, &identity, &headers);
+    let client = match state
+        .get_client_in(&server_id, environment.as_deref())
+        .await
+    {
         Ok(client) => client,
         Err(e) => {
             return Err((
@@ -458,7 +1095,7 @@ mod tests {
         let config = ServerConfig::default();
         let state = AppState::new(config).await.unwrap();
 
-        let response = list_servers(State(state)).await;
+        let response = list_servers(State(state), Extension(Identity::default())).await;
         let servers = response.0;
 
         assert!(!servers.is_empty());
@@ -0,0 +1,530 @@
+use crate::domain::status_code_name;
+use crate::redact::Redactor;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use prost_reflect::MessageDescriptor;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use tonic::Code;
+use tracing::warn;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Records per page when the request doesn't say
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page that can be requested
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Default history file, next to the CLI profiles
+pub fn default_history_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+        home.join(".config")
+            .join("grpc-client")
+            .join("history.jsonl")
+    })
+}
+
+/// A call made through the web API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryRecord {
+    /// Record identifier
+    pub id: String,
+    /// When the call started
+    pub timestamp: DateTime<Utc>,
+    /// Server the call was made on
+    pub server_id: String,
+    /// Full method name (e.g., "package.service/method")
+    pub method: String,
+    /// Request message as sent, or an array of the messages of a streaming call
+    pub request: Value,
+    /// Per-call headers as sent, in addition to the server's configured headers
+    pub headers: BTreeMap<String, String>,
+    /// Response messages, with sensitive fields masked
+    pub responses: Vec<Value>,
+    /// Canonical status code name (e.g. `OK`, `NOT_FOUND`)
+    pub status: String,
+    /// Error message of a failed call
+    pub error: Option<String>,
+    /// How long the call took in milliseconds
+    pub duration_ms: f64,
+    /// Size of the request as JSON, in bytes
+    pub request_size: usize,
+    /// Size of the responses as JSON, in bytes
+    pub response_size: usize,
+    /// Whether sensitive request fields or headers were masked, in which
+    /// case the call can't be replayed from the record
+    #[serde(default)]
+    pub redacted: bool,
+}
+
+/// Filters and page of a history search
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// Case-insensitive text matched against the method, server, status,
+    /// error, request and responses
+    pub q: Option<String>,
+    /// Only calls made on this server
+    pub server_id: Option<String>,
+    /// Only calls of this method
+    pub method: Option<String>,
+    /// Only calls that ended with this status code name (e.g. `OK`)
+    pub status: Option<String>,
+    /// Page number, starting at 1
+    pub page: Option<usize>,
+    /// Records per page (default 50, at most 500)
+    pub per_page: Option<usize>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, record: &HistoryRecord) -> bool {
+        if self
+            .server_id
+            .as_ref()
+            .is_some_and(|id| *id != record.server_id)
+            || self.method.as_ref().is_some_and(|m| *m != record.method)
+            || self
+                .status
+                .as_ref()
+                .is_some_and(|s| !s.eq_ignore_ascii_case(&record.status))
+        {
+            return false;
+        }
+
+        let Some(text) = self.q.as_deref().filter(|q| !q.is_empty()) else {
+            return true;
+        };
+        let text = text.to_lowercase();
+        let contains = |value: &str| value.to_lowercase().contains(&text);
+        contains(&record.method)
+            || contains(&record.server_id)
+            || contains(&record.status)
+            || record.error.as_deref().is_some_and(contains)
+            || contains(&record.request.to_string())
+            || record.responses.iter().any(|r| contains(&r.to_string()))
+    }
+}
+
+/// A page of history records, newest first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPage {
+    pub items: Vec<HistoryRecord>,
+    /// Number of records matching the search
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+/// Call history, kept in memory and appended to a JSON Lines file
+#[derive(Clone, Default)]
+pub struct HistoryStore {
+    records: Arc<RwLock<Vec<HistoryRecord>>>,
+    path: Option<PathBuf>,
+}
+
+impl HistoryStore {
+    /// History that is lost on restart
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Load the history saved in `path`, which is created on the first call.
+    /// Lines that can't be parsed, such as one cut short by a crash, are
+    /// skipped.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut records = Vec::new();
+
+        match tokio::fs::read_to_string(&path).await {
+            Ok(content) => {
+                for (number, line) in content.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_str(line) {
+                        Ok(record) => records.push(record),
+                        Err(e) => warn!(
+                            "Skipping history record at {}:{}: {}",
+                            path.display(),
+                            number + 1,
+                            e
+                        ),
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read history file: {}", path.display()));
+            }
+        }
+
+        Ok(Self {
+            records: Arc::new(RwLock::new(records)),
+            path: Some(path),
+        })
+    }
+
+    /// Add a record and append it to the history file
+    pub async fn record(&self, record: HistoryRecord) -> Result<()> {
+        // The write lock also keeps lines from concurrent calls apart
+        let mut records = self.records.write().await;
+        if let Some(path) = &self.path {
+            append_line(path, &record).await?;
+        }
+        records.push(record);
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Option<HistoryRecord> {
+        let records = self.records.read().await;
+        records.iter().find(|record| record.id == id).cloned()
+    }
+
+    /// Records matching the query, newest first
+    pub async fn search(&self, query: &HistoryQuery) -> HistoryPage {
+        self.search_visible(query, |_| true).await
+    }
+
+    /// Records matching the query for which `visible` holds, newest first
+    pub async fn search_visible(
+        &self,
+        query: &HistoryQuery,
+        visible: impl Fn(&HistoryRecord) -> bool,
+    ) -> HistoryPage {
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query
+            .per_page
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+
+        let records = self.records.read().await;
+        let matching: Vec<&HistoryRecord> = records
+            .iter()
+            .rev()
+            .filter(|record| query.matches(record) && visible(record))
+            .collect();
+
+        HistoryPage {
+            total: matching.len(),
+            items: matching
+                .into_iter()
+                .skip((page - 1) * per_page)
+                .take(per_page)
+                .cloned()
+                .collect(),
+            page,
+            per_page,
+        }
+    }
+}
+
+async fn append_line(path: &Path, record: &HistoryRecord) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("Failed to open history file: {}", path.display()))?;
+    file.write_all(line.as_bytes()).await?;
+    // Tokio files write in the background until flushed
+    file.flush().await?;
+    Ok(())
+}
+
+/// Collects the messages of a call as it runs. A recorder dropped before
+/// [`CallRecorder::finish`], when the browser goes away, records the call as
+/// cancelled.
+pub struct CallRecorder {
+    history: HistoryStore,
+    started: Instant,
+    record: Option<HistoryRecord>,
+    requests: Vec<Value>,
+    /// Masks credentials and sensitive request fields before they are stored
+    redactor: Option<Arc<Redactor>>,
+    input_descriptor: Option<MessageDescriptor>,
+}
+
+impl CallRecorder {
+    pub fn new(
+        history: &HistoryStore,
+        server_id: &str,
+        method: &str,
+        headers: &[(String, String)],
+    ) -> Self {
+        Self {
+            history: history.clone(),
+            started: Instant::now(),
+            record: Some(HistoryRecord {
+                id: Uuid::new_v4().to_string(),
+                timestamp: Utc::now(),
+                server_id: server_id.to_string(),
+                method: method.to_string(),
+                request: Value::Null,
+                headers: headers.iter().cloned().collect(),
+                responses: Vec::new(),
+                status: String::new(),
+                error: None,
+                duration_ms: 0.0,
+                request_size: 0,
+                response_size: 0,
+                redacted: false,
+            }),
+            requests: Vec::new(),
+            redactor: None,
+            input_descriptor: None,
+        }
+    }
+
+    /// Mask credential headers and, once the call completes, sensitive
+    /// request fields with the server's redactor
+    pub fn with_redactor(mut self, redactor: Option<Arc<Redactor>>) -> Self {
+        if let (Some(redactor), Some(record)) = (&redactor, &mut self.record) {
+            let headers: Vec<(String, String)> =
+                std::mem::take(&mut record.headers).into_iter().collect();
+            let masked = redactor.redacted_headers(&headers);
+            record.redacted |= masked != headers;
+            record.headers = masked.into_iter().collect();
+        }
+        self.redactor = redactor;
+        self
+    }
+
+    /// Set the descriptor of the request messages, so fields marked
+    /// sensitive in it are masked
+    pub fn set_input_descriptor(&mut self, descriptor: MessageDescriptor) {
+        self.input_descriptor = Some(descriptor);
+    }
+
+    /// Note a request message sent on the call
+    pub fn request(&mut self, request: Value) {
+        self.requests.push(request);
+    }
+
+    /// Note a response message received on the call
+    pub fn response(&mut self, response: Value) {
+        if let Some(record) = &mut self.record {
+            record.responses.push(response);
+        }
+    }
+
+    /// Complete the record with the call's status and save it. Failing to
+    /// save is logged rather than failing the call.
+    pub async fn finish(mut self, code: Code, error: Option<String>) -> HistoryRecord {
+        let record = self.complete(code, error);
+        if let Err(e) = self.history.record(record.clone()).await {
+            warn!("Failed to save call history: {:#}", e);
+        }
+        record
+    }
+
+    /// Drop the record of a call that never reached the server
+    pub fn discard(mut self) {
+        self.record = None;
+    }
+
+    fn complete(&mut self, code: Code, error: Option<String>) -> HistoryRecord {
+        let mut record = self.record.take().expect("a call is completed once");
+        if let Some(redactor) = &self.redactor {
+            let original = self.requests.clone();
+            for request in &mut self.requests {
+                redactor.redact(request, self.input_descriptor.as_ref());
+            }
+            record.redacted |= self.requests != original;
+        }
+        // A single message is kept as is, so it can be replayed on any method
+        record.request = match self.requests.len() {
+            1 => self.requests.remove(0),
+            _ => Value::Array(std::mem::take(&mut self.requests)),
+        };
+        record.status = status_code_name(code).to_string();
+        record.error = error;
+        // Whole microseconds read back from the history file unchanged
+        record.duration_ms = self.started.elapsed().as_micros() as f64 / 1000.0;
+        record.request_size = record.request.to_string().len();
+        record.response_size = record.responses.iter().map(|r| r.to_string().len()).sum();
+        record
+    }
+}
+
+impl Drop for CallRecorder {
+    fn drop(&mut self) {
+        if self.record.is_none() {
+            return;
+        }
+        let record = self.complete(
+            Code::Cancelled,
+            Some("Connection closed before the call finished".to_string()),
+        );
+        let history = self.history.clone();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                if let Err(e) = history.record(record).await {
+                    warn!("Failed to save call history: {:#}", e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redact::RedactionConfig;
+    use serde_json::json;
+
+    fn recorder(history: &HistoryStore, method: &str) -> CallRecorder {
+        CallRecorder::new(
+            history,
+            "local",
+            method,
+            &[("x-tenant".to_string(), "acme".to_string())],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_record_and_search() {
+        let history = HistoryStore::in_memory();
+        for id in ["1", "2", "3"] {
+            let mut call = recorder(&history, "example.UserService/GetUser");
+            call.request(json!({"userId": id}));
+            call.response(json!({"id": id, "name": format!("User {}", id)}));
+            call.finish(Code::Ok, None).await;
+        }
+        let call = recorder(&history, "example.UserService/ListUsers");
+        call.finish(Code::NotFound, Some("no users".to_string()))
+            .await;
+
+        let page = history.search(&HistoryQuery::default()).await;
+        assert_eq!(page.total, 4);
+        assert_eq!(page.items[0].method, "example.UserService/ListUsers");
+        assert_eq!(page.items[0].status, "NOT_FOUND");
+        assert_eq!(page.items[0].request, json!([]));
+        assert_eq!(page.items[1].request, json!({"userId": "3"}));
+        assert_eq!(page.items[1].headers["x-tenant"], "acme");
+        assert_eq!(page.items[1].request_size, r#"{"userId":"3"}"#.len());
+
+        let query = HistoryQuery {
+            q: Some("user 2".to_string()),
+            ..Default::default()
+        };
+        let page = history.search(&query).await;
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].request, json!({"userId": "2"}));
+
+        let query = HistoryQuery {
+            status: Some("ok".to_string()),
+            page: Some(2),
+            per_page: Some(2),
+            ..Default::default()
+        };
+        let page = history.search(&query).await;
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].request, json!({"userId": "1"}));
+
+        let id = &page.items[0].id;
+        assert_eq!(history.get(id).await.unwrap().id, *id);
+        assert!(history.get("missing").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_history_file_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("history.jsonl");
+
+        let history = HistoryStore::open(&path).await.unwrap();
+        let mut call = recorder(&history, "example.UserService/GetUser");
+        call.request(json!({"userId": "1"}));
+        let record = call.finish(Code::Ok, None).await;
+
+        // A line cut short by a crash is skipped
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str("{\"id\": \"trunc");
+        std::fs::write(&path, content).unwrap();
+
+        let history = HistoryStore::open(&path).await.unwrap();
+        let page = history.search(&HistoryQuery::default()).await;
+        assert_eq!(page.items, vec![record]);
+    }
+
+    #[tokio::test]
+    async fn test_history_file_masks_credentials() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let config = RedactionConfig {
+            fields: vec!["^password$".to_string()],
+            ..Default::default()
+        };
+        let redactor = Redactor::from_config(&config).unwrap().map(Arc::new);
+
+        let history = HistoryStore::open(&path).await.unwrap();
+        let headers = [
+            ("authorization", "Bearer s3cret-token"),
+            ("x-session-token", "s3cret-session"),
+            ("x-trace-bin", "c2VjcmV0"),
+            ("x-tenant", "acme"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let mut call = CallRecorder::new(&history, "local", "example.AuthService/Login", &headers)
+            .with_redactor(redactor);
+        call.request(json!({"user": "jane", "password": "hunter2"}));
+        call.finish(Code::Ok, None).await;
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        for secret in ["s3cret", "c2VjcmV0", "hunter2"] {
+            assert!(!content.contains(secret), "{} in {}", secret, content);
+        }
+        let record: HistoryRecord = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(record.headers["authorization"], "[REDACTED]");
+        assert_eq!(record.headers["x-tenant"], "acme");
+        assert_eq!(
+            record.request,
+            json!({"user": "jane", "password": "[REDACTED]"})
+        );
+        assert!(record.redacted);
+
+        let redactor = Redactor::from_config(&config).unwrap().map(Arc::new);
+        let mut call = recorder(&history, "example.AuthService/Login").with_redactor(redactor);
+        call.request(json!({"user": "jane"}));
+        assert!(!call.finish(Code::Ok, None).await.redacted);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_recorder_records_cancelled_call() {
+        let history = HistoryStore::in_memory();
+        let mut call = recorder(&history, "example.UserService/GetUser");
+        call.request(json!({"userId": "1"}));
+        call.request(json!({"userId": "2"}));
+        drop(call);
+
+        for _ in 0..50 {
+            if history.search(&HistoryQuery::default()).await.total > 0 {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        let page = history.search(&HistoryQuery::default()).await;
+        assert_eq!(page.items[0].status, "CANCELLED");
+        assert_eq!(
+            page.items[0].request,
+            json!([{"userId": "1"}, {"userId": "2"}])
+        );
+    }
+}
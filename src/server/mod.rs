@@ -1,44 +1,102 @@
+pub mod access;
+pub mod calls;
+pub mod collections;
 pub mod config;
+pub mod environments;
 pub mod handlers;
+pub mod history;
 pub mod openapi;
+pub mod probe;
+pub mod reload;
 pub mod routes;
 pub mod schema;
 pub mod state;
+pub mod streaming;
 
 pub use config::ServerConfig;
 pub use routes::create_router;
 pub use state::AppState;
 
-use anyhow::Result;
-use std::net::SocketAddr;
+use anyhow::{Context, Result};
+use collections::CollectionStore;
+use history::HistoryStore;
+use std::path::Path;
 use tokio::signal;
 use tracing::{info, warn};
 
-/// Start the web server with the given configuration
-pub async fn start_server(port: u16, config_path: &str, ui_path: &str) -> Result<()> {
+/// Address the web server listens on when none is configured
+pub const DEFAULT_BIND: &str = "0.0.0.0";
+
+/// Start the web server with the given configuration, listening on `bind`
+/// or the configured address. With `redact` false, sensitive fields are
+/// returned unmasked for all servers. Calls are recorded in `history_path`
+/// and saved requests kept in `collections_dir`; without them they are kept
+/// in memory only.
+pub async fn start_server(
+    bind: Option<&str>,
+    port: u16,
+    config_path: &str,
+    ui_path: &str,
+    history_path: Option<&Path>,
+    collections_dir: Option<&Path>,
+    redact: bool,
+) -> Result<()> {
     tracing_subscriber::fmt::init();
 
     // Load configuration
     let config = ServerConfig::load(config_path).await?;
+    let host = bind
+        .or(config.web.bind.as_deref())
+        .unwrap_or(DEFAULT_BIND)
+        .to_string();
+    let open_api = config.web.auth.is_none();
 
     // Create application state
-    let state = AppState::new(config).await?;
+    let history = match history_path {
+        Some(path) => HistoryStore::open(path).await?,
+        None => HistoryStore::in_memory(),
+    };
+    let collections = match collections_dir {
+        Some(dir) => CollectionStore::open(dir).await?,
+        None => CollectionStore::in_memory(),
+    };
+    let state = AppState::with_redaction(config, redact)
+        .await?
+        .with_config_path(config_path)
+        .with_history(history)
+        .with_collections(collections);
+    reload::watch_config(
+        state.clone(),
+        config_path.to_string(),
+        reload::WATCH_INTERVAL,
+    );
+    probe::spawn_prober(state.clone(), probe::PROBE_INTERVAL);
 
     // Create router with all routes
     let app = create_router(state, ui_path);
 
-    // Configure server address
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    // Create TCP listener
+    let listener = tokio::net::TcpListener::bind((host.as_str(), port))
+        .await
+        .with_context(|| format!("Failed to listen on {}:{}", host, port))?;
+    let addr = listener.local_addr()?;
 
-    info!(
-        "🚀 gRPC Client Web UI starting on http://localhost:{}",
-        port
-    );
+    info!("🚀 gRPC Client Web UI starting on http://{}", addr);
+    if open_api && !addr.ip().is_loopback() {
+        warn!(
+            "🔓 The web API has no authentication and is reachable from the network; \
+             set web.auth in the config or use --bind 127.0.0.1"
+        );
+    }
     info!("📁 Serving UI from: {}", ui_path);
-    info!("📋 Using config file: {}", config_path);
-
-    // Create TCP listener
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("📋 Using config file: {} (reloaded on change)", config_path);
+    match history_path {
+        Some(path) => info!("🕘 Recording call history in: {}", path.display()),
+        None => info!("🕘 Keeping call history in memory"),
+    }
+    if let Some(dir) = collections_dir {
+        info!("📚 Saving request collections in: {}", dir.display());
+    }
 
     // Start server with graceful shutdown
     axum::serve(listener, app)
@@ -1,8 +1,16 @@
 use utoipa::OpenApi;
 
+use crate::collection::{Collection, SavedRequest};
+use crate::server::calls::ActiveCall;
+use crate::server::collections::RunSavedRequest;
+use crate::server::config::GrpcServerConfig;
+use crate::server::environments::{EnvironmentInfo, EnvironmentList, SelectEnvironment};
 use crate::server::handlers::{CallRequest, ErrorResponse, MethodInfo, ServiceInfo};
+use crate::server::history::{HistoryPage, HistoryRecord};
+use crate::server::probe::HealthState;
 // Note: JsonSchema and JsonSchemaProperty are excluded from OpenAPI due to recursive structure
-use crate::server::state::ServerStatus;
+use crate::server::state::{ConfigEvent, ServerStatus};
+use crate::server::streaming::{StreamEvent, StreamRequest};
 
 /// OpenAPI documentation for the gRPC Client Web API
 #[derive(OpenApi)]
@@ -23,9 +31,32 @@ use crate::server::state::ServerStatus;
     paths(
         crate::server::handlers::health_check,
         crate::server::handlers::list_servers,
+        crate::server::handlers::create_server,
+        crate::server::handlers::update_server,
+        crate::server::handlers::delete_server,
+        crate::server::handlers::config_events,
         crate::server::handlers::list_services,
         crate::server::handlers::describe_service,
         crate::server::handlers::call_method,
+        crate::server::streaming::call_websocket,
+        crate::server::streaming::call_sse,
+        crate::server::environments::list_environments,
+        crate::server::environments::select_environment,
+        crate::server::collections::list_collections,
+        crate::server::collections::get_collection,
+        crate::server::collections::create_collection,
+        crate::server::collections::update_collection,
+        crate::server::collections::delete_collection,
+        crate::server::collections::save_request,
+        crate::server::collections::delete_request,
+        crate::server::collections::run_saved_request,
+        crate::server::collections::export_collection,
+        crate::server::collections::import_collection,
+        crate::server::handlers::list_calls,
+        crate::server::handlers::cancel_call,
+        crate::server::handlers::list_history,
+        crate::server::handlers::get_history,
+        crate::server::handlers::replay_history,
         crate::server::handlers::describe_method
     ),
     components(
@@ -35,6 +66,20 @@ use crate::server::state::ServerStatus;
             ServiceInfo,
             MethodInfo,
             ServerStatus,
+            HealthState,
+            GrpcServerConfig,
+            ConfigEvent,
+            StreamRequest,
+            StreamEvent,
+            ActiveCall,
+            HistoryRecord,
+            HistoryPage,
+            Collection,
+            SavedRequest,
+            RunSavedRequest,
+            EnvironmentInfo,
+            EnvironmentList,
+            SelectEnvironment,
         )
     ),
     tags(
@@ -42,6 +87,9 @@ use crate::server::state::ServerStatus;
         (name = "servers", description = "Server management operations"),
         (name = "services", description = "gRPC service discovery and information"),
         (name = "grpc", description = "gRPC method execution"),
+        (name = "history", description = "Record of calls made through the API"),
+        (name = "environments", description = "Variable sets applied to calls"),
+        (name = "collections", description = "Saved request collections"),
         (name = "schema", description = "JSON schema generation for gRPC methods")
     ),
     servers(
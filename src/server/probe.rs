@@ -0,0 +1,104 @@
+use crate::client::GrpcClient;
+use crate::health::ServingStatus;
+use crate::server::state::AppState;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tonic::Code;
+use utoipa::ToSchema;
+
+/// How often configured servers are probed
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a probe may take before the server counts as unreachable
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Result of the last probe of a server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    /// Not probed yet
+    #[default]
+    Unknown,
+    /// The health service reports SERVING, or reflection answered
+    Serving,
+    /// The health service reports a status other than SERVING
+    NotServing,
+    /// The probe failed; see the last error
+    Unreachable,
+}
+
+/// Probe results kept per server
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerHealth {
+    pub state: HealthState,
+    pub last_checked: DateTime<Utc>,
+    pub latency: Duration,
+    /// Error of the most recent failed probe, kept after the server recovers
+    pub last_error: Option<String>,
+}
+
+/// Check a server with `grpc.health.v1.Health/Check`, falling back to a
+/// reflection `ListServices` call for servers without the health service
+pub async fn probe(client: &GrpcClient) -> (HealthState, Duration, Option<String>) {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(PROBE_TIMEOUT, check(client)).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("No response within {:?}", PROBE_TIMEOUT)),
+    };
+    let latency = started.elapsed();
+
+    match result {
+        Ok(state) => (state, latency, None),
+        Err(error) => (HealthState::Unreachable, latency, Some(error)),
+    }
+}
+
+async fn check(client: &GrpcClient) -> Result<HealthState, String> {
+    match client.health_check("").await {
+        Ok(ServingStatus::Serving) => Ok(HealthState::Serving),
+        Ok(_) => Ok(HealthState::NotServing),
+        Err(status) if matches!(status.code(), Code::Unimplemented | Code::NotFound) => client
+            .handle_service_list()
+            .await
+            .map(|_| HealthState::Serving)
+            .map_err(|e| format!("{:#}", e)),
+        Err(status) => Err(format!("{:?}: {}", status.code(), status.message())),
+    }
+}
+
+/// Probe all configured servers concurrently and record the results
+pub async fn probe_all(state: &AppState) {
+    let probes = state
+        .get_server_ids()
+        .into_iter()
+        .map(|server_id| async move {
+            let environment = state.default_environment();
+            let client = state
+                .get_client_in(&server_id, environment.as_deref())
+                .await;
+            let (health, latency, error) = match client {
+                Ok(client) => probe(&client).await,
+                Err(e) => (
+                    HealthState::Unreachable,
+                    Duration::ZERO,
+                    Some(format!("{:#}", e)),
+                ),
+            };
+            state.record_health(&server_id, health, latency, error);
+        });
+    join_all(probes).await;
+}
+
+/// Probe all servers now and then every `interval`
+pub fn spawn_prober(state: AppState, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            probe_all(&state).await;
+        }
+    })
+}
@@ -0,0 +1,147 @@
+use crate::server::config::ServerConfig;
+use crate::server::state::{AppState, ConfigEvent};
+use anyhow::{Result, bail};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// How often the config file is checked for changes
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Re-read the config file and apply the changes. When the file can't be
+/// parsed the previous configuration stays active and a
+/// [`ConfigEvent::ReloadFailed`] event is sent.
+pub async fn reload_config(state: &AppState, config_path: &str) -> Result<Vec<ConfigEvent>> {
+    // `ServerConfig::load` writes a default config for missing files, which
+    // would replace a file an editor is in the middle of saving
+    if !Path::new(config_path).exists() {
+        bail!("Config file not found: {}", config_path);
+    }
+
+    match ServerConfig::load(config_path).await {
+        Ok(config) => Ok(state.apply_config(config).await),
+        Err(e) => {
+            state.notify(ConfigEvent::ReloadFailed {
+                error: format!("{:#}", e),
+            });
+            Err(e)
+        }
+    }
+}
+
+/// Watch the config file and reload it whenever its modification time
+/// changes
+pub fn watch_config(state: AppState, config_path: String, interval: Duration) -> JoinHandle<()> {
+    let mut last_modified = modified(&config_path);
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let current = modified(&config_path);
+            if current.is_none() || current == last_modified {
+                continue;
+            }
+            last_modified = current;
+
+            match reload_config(&state, &config_path).await {
+                Ok(events) => info!(
+                    "🔄 Reloaded {} ({} server changes)",
+                    config_path,
+                    events.len()
+                ),
+                Err(e) => warn!(
+                    "⚠️  Keeping previous configuration, failed to reload {}: {:#}",
+                    config_path, e
+                ),
+            }
+        }
+    })
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+servers:
+  users:
+    name: Users
+    endpoint: localhost:9090
+    plaintext: true
+"#;
+
+    async fn state_for(path: &str) -> AppState {
+        let config = ServerConfig::from_file(path).unwrap();
+        AppState::new(config).await.unwrap().with_config_path(path)
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_previous_config_on_invalid_yaml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.yml");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, CONFIG).unwrap();
+        let state = state_for(path).await;
+        let mut events = state.subscribe();
+
+        std::fs::write(path, "servers: [not a map").unwrap();
+        assert!(reload_config(&state, path).await.is_err());
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            ConfigEvent::ReloadFailed { .. }
+        ));
+        assert_eq!(state.get_server_ids(), vec!["users".to_string()]);
+
+        std::fs::write(path, CONFIG.replace("9090", "9191")).unwrap();
+        let changes = reload_config(&state, path).await.unwrap();
+        assert_eq!(
+            changes,
+            vec![ConfigEvent::ServerUpdated {
+                server_id: "users".to_string(),
+                reconnected: true
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_config_sends_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.yml");
+        let path = path.to_str().unwrap().to_string();
+        std::fs::write(&path, CONFIG).unwrap();
+        let state = state_for(&path).await;
+        let mut events = state.subscribe();
+
+        let watcher = watch_config(state.clone(), path.clone(), Duration::from_millis(20));
+        // Modification times have limited resolution
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        std::fs::write(
+            &path,
+            format!(
+                "{}  orders:\n    name: Orders\n    endpoint: localhost:9292\n",
+                CONFIG
+            ),
+        )
+        .unwrap();
+
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event,
+            ConfigEvent::ServerAdded {
+                server_id: "orders".to_string()
+            }
+        );
+        assert!(state.get_server_config("orders").is_some());
+        watcher.abort();
+    }
+}
@@ -1,13 +1,17 @@
 use crate::server::handlers;
 use crate::server::openapi::ApiDoc;
 use crate::server::state::AppState;
+use crate::server::{access, collections, environments, streaming};
 use axum::{
     Router,
-    routing::{get, post},
+    http::{HeaderName, HeaderValue},
+    middleware,
+    routing::{delete, get, post, put},
 };
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
 use tower_http::services::ServeDir;
+use tracing::warn;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
@@ -21,6 +25,12 @@ pub fn create_router_with_swagger(state: AppState, ui_path: &str, enable_swagger
     // API routes
     let api_routes = Router::new()
         .route("/servers", get(handlers::list_servers))
+        .route(
+            "/servers/{server_id}",
+            post(handlers::create_server)
+                .put(handlers::update_server)
+                .delete(handlers::delete_server),
+        )
         .route(
             "/servers/{server_id}/services",
             get(handlers::list_services),
@@ -34,14 +44,58 @@ pub fn create_router_with_swagger(state: AppState, ui_path: &str, enable_swagger
             get(handlers::describe_method),
         )
         .route("/servers/{server_id}/call", post(handlers::call_method))
+        .route(
+            "/servers/{server_id}/call/ws",
+            get(streaming::call_websocket),
+        )
+        .route(
+            "/servers/{server_id}/call/stream",
+            post(streaming::call_sse),
+        )
+        .route("/environments", get(environments::list_environments))
+        .route(
+            "/environments/active",
+            put(environments::select_environment),
+        )
+        .route("/collections", get(collections::list_collections))
+        .route("/collections/import", post(collections::import_collection))
+        .route(
+            "/collections/{collection}",
+            get(collections::get_collection)
+                .post(collections::create_collection)
+                .put(collections::update_collection)
+                .delete(collections::delete_collection),
+        )
+        .route(
+            "/collections/{collection}/export",
+            get(collections::export_collection),
+        )
+        .route(
+            "/collections/{collection}/requests/{request_name}",
+            put(collections::save_request).delete(collections::delete_request),
+        )
+        .route(
+            "/collections/{collection}/requests/{request_name}/run",
+            post(collections::run_saved_request),
+        )
+        .route("/calls", get(handlers::list_calls))
+        .route("/calls/{call_id}", delete(handlers::cancel_call))
+        .route("/history", get(handlers::list_history))
+        .route("/history/{record_id}", get(handlers::get_history))
+        .route(
+            "/history/{record_id}/replay",
+            post(handlers::replay_history),
+        )
+        .route("/events", get(handlers::config_events))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            access::authenticate,
+        ))
+        // Left open for load balancers and uptime checks
         .route("/health", get(handlers::health_check))
-        .with_state(state);
+        .with_state(state.clone());
 
-    // CORS middleware for development
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let cors = cors_layer(&state.config.read().unwrap().web.cors_origins);
 
     // Main router with API routes and static file serving as fallback
     let mut router = Router::new()
@@ -59,6 +113,35 @@ pub fn create_router_with_swagger(state: AppState, ui_path: &str, enable_swagger
     router
 }
 
+/// Allow browsers on `origins` to call the API, or any origin when empty.
+/// Credentials are only allowed for listed origins.
+fn cors_layer(origins: &[String]) -> CorsLayer {
+    let cors = CorsLayer::new().expose_headers([
+        HeaderName::from_static(handlers::ATTEMPTS_HEADER),
+        HeaderName::from_static(handlers::CALL_ID_HEADER),
+    ]);
+    if origins.is_empty() {
+        return cors.allow_origin(Any).allow_methods(Any).allow_headers(Any);
+    }
+
+    let origins: Vec<HeaderValue> = origins
+        .iter()
+        .filter_map(
+            |origin| match HeaderValue::from_str(origin.trim_end_matches('/')) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    warn!("Ignoring invalid CORS origin '{}'", origin);
+                    None
+                }
+            },
+        )
+        .collect();
+    cors.allow_origin(AllowOrigin::list(origins))
+        .allow_methods(AllowMethods::mirror_request())
+        .allow_headers(AllowHeaders::mirror_request())
+        .allow_credentials(true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
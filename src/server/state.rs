@@ -1,29 +1,73 @@
 use crate::client::GrpcClient;
+use crate::server::access::Authenticator;
+use crate::server::calls::CallRegistry;
+use crate::server::collections::CollectionStore;
 use crate::server::config::{GrpcServerConfig, ServerConfig};
-use anyhow::{Context, Result};
+use crate::server::history::HistoryStore;
+use crate::server::probe::{HealthState, ServerHealth};
+use crate::template::TemplateEngine;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use serde::Serialize;
-use std::sync::Arc;
-use tracing::info;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast};
+use tracing::{info, warn};
 use utoipa::ToSchema;
 
+/// A session: the caller's user name and the session ID they sent. Sessions
+/// of different users are kept apart, so nobody can switch the environment
+/// of someone else's calls.
+pub type SessionKey = (Option<String>, String);
+
 /// Application state shared across all request handlers
 #[derive(Clone)]
 pub struct AppState {
     /// Configuration loaded from YAML file
-    pub config: ServerConfig,
+    pub config: Arc<RwLock<ServerConfig>>,
     /// Map of server ID to initialized gRPC clients
     pub clients: Arc<DashMap<String, Arc<GrpcClient>>>,
+    /// Clients built with the variables of an environment, by server ID and
+    /// environment name
+    environment_clients: Arc<DashMap<(String, String), Arc<GrpcClient>>>,
+    /// Map of session to the environment the session selected
+    sessions: Arc<DashMap<SessionKey, String>>,
+    /// Map of server ID to the result of its last health probe
+    pub health: Arc<DashMap<String, ServerHealth>>,
+    /// Calls started through the API that are still running
+    pub calls: CallRegistry,
+    /// Record of the calls made through the API
+    pub history: HistoryStore,
+    /// Saved request collections
+    pub collections: CollectionStore,
+    /// Checks credentials of web API callers
+    pub authenticator: Authenticator,
+    /// Whether sensitive fields are masked in responses
+    pub redact: bool,
+    /// File that server changes are saved to; without one they are kept in
+    /// memory only
+    pub config_path: Option<String>,
+    /// Serializes changes to the configuration
+    updates: Arc<Mutex<()>>,
+    /// Configuration changes, for UI subscribers
+    events: broadcast::Sender<ConfigEvent>,
 }
 
 impl AppState {
     /// Create new application state from configuration
     pub async fn new(config: ServerConfig) -> Result<Self> {
+        Self::with_redaction(config, true).await
+    }
+
+    /// Create application state, optionally disabling redaction of sensitive
+    /// fields for all servers
+    pub async fn with_redaction(config: ServerConfig, redact: bool) -> Result<Self> {
         let clients = Arc::new(DashMap::new());
 
         // Pre-initialize clients for all configured servers
         for (server_id, conf) in &config.servers {
-            match GrpcClient::from_config(conf) {
+            match create_client(conf, redact, &TemplateEngine::new()) {
                 Ok(client) => {
                     clients.insert(server_id.clone(), Arc::new(client));
                     println!(
@@ -41,7 +85,39 @@ impl AppState {
             }
         }
 
-        Ok(Self { config, clients })
+        Ok(Self {
+            config: Arc::new(RwLock::new(config)),
+            clients,
+            environment_clients: Arc::new(DashMap::new()),
+            sessions: Arc::new(DashMap::new()),
+            health: Arc::new(DashMap::new()),
+            calls: CallRegistry::default(),
+            history: HistoryStore::in_memory(),
+            collections: CollectionStore::in_memory(),
+            authenticator: Authenticator::default(),
+            redact,
+            config_path: None,
+            updates: Arc::new(Mutex::new(())),
+            events: broadcast::channel(EVENT_BUFFER).0,
+        })
+    }
+
+    /// Save server changes to this file
+    pub fn with_config_path(mut self, config_path: impl Into<String>) -> Self {
+        self.config_path = Some(config_path.into());
+        self
+    }
+
+    /// Record calls in this history store
+    pub fn with_history(mut self, history: HistoryStore) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Keep saved requests in this collection store
+    pub fn with_collections(mut self, collections: CollectionStore) -> Self {
+        self.collections = collections;
+        self
     }
 
     /// Get or create a gRPC client for the specified server
@@ -53,13 +129,12 @@ impl AppState {
 
         // Get server configuration
         let conf = self
-            .config
-            .get_server(server_id)
+            .get_server_config(server_id)
             .with_context(|| format!("Server '{}' not found in configuration", server_id))?;
 
         info!("Creating client for server: {:?}", conf);
         // Create new client
-        let client = GrpcClient::from_config(conf)
+        let client = create_client(&conf, self.redact, &TemplateEngine::new())
             .with_context(|| format!("Failed to create client for server '{}'", server_id))?;
 
         let client_arc = Arc::new(client);
@@ -71,14 +146,256 @@ impl AppState {
         Ok(client_arc)
     }
 
+    /// Get or create a client for a server with the variables of
+    /// `environment` expanded in its endpoint, headers and auth settings
+    pub async fn get_client_in(
+        &self,
+        server_id: &str,
+        environment: Option<&str>,
+    ) -> Result<Arc<GrpcClient>> {
+        let Some(environment) = environment else {
+            return self.get_client(server_id).await;
+        };
+        let key = (server_id.to_string(), environment.to_string());
+        if let Some(client) = self.environment_clients.get(&key) {
+            return Ok(client.clone());
+        }
+
+        let (conf, template) = {
+            let config = self.config.read().unwrap();
+            let conf = config.get_server(server_id).cloned();
+            (conf, config.template(Some(environment)))
+        };
+        let conf =
+            conf.with_context(|| format!("Server '{}' not found in configuration", server_id))?;
+        let client = create_client(&conf, self.redact, &template).with_context(|| {
+            format!(
+                "Failed to create client for server '{}' in environment '{}'",
+                server_id, environment
+            )
+        })?;
+
+        let client = Arc::new(client);
+        self.environment_clients.insert(key, client.clone());
+        Ok(client)
+    }
+
+    /// Environment of sessions that have not selected one, if it exists
+    pub fn default_environment(&self) -> Option<String> {
+        let config = self.config.read().unwrap();
+        config
+            .default_environment
+            .clone()
+            .filter(|name| config.environments.contains_key(name))
+    }
+
+    /// Environment active for a session: the one it selected, or the default
+    pub fn active_environment(&self, session: &SessionKey) -> Option<String> {
+        let selected = self.sessions.get(session).map(|name| name.clone());
+        match selected {
+            Some(name) if self.config.read().unwrap().environments.contains_key(&name) => {
+                Some(name)
+            }
+            _ => self.default_environment(),
+        }
+    }
+
+    /// Select the environment of a session; `None` returns it to the default
+    pub fn select_environment(
+        &self,
+        session: &SessionKey,
+        environment: Option<&str>,
+    ) -> Result<()> {
+        match environment {
+            Some(name) => {
+                if !self.config.read().unwrap().environments.contains_key(name) {
+                    bail!("Environment '{}' not found", name);
+                }
+                self.sessions.insert(session.clone(), name.to_string());
+            }
+            None => {
+                self.sessions.remove(session);
+            }
+        }
+        Ok(())
+    }
+
+    /// Template engine with the variables of an environment
+    pub fn environment_template(&self, environment: Option<&str>) -> TemplateEngine {
+        self.config.read().unwrap().template(environment)
+    }
+
     /// Get all configured server IDs
     pub fn get_server_ids(&self) -> Vec<String> {
-        self.config.server_ids().into_iter().cloned().collect()
+        self.config
+            .read()
+            .unwrap()
+            .server_ids()
+            .into_iter()
+            .cloned()
+            .collect()
     }
 
     /// Get server configuration by ID
-    pub fn get_server_config(&self, server_id: &str) -> Option<&GrpcServerConfig> {
-        self.config.get_server(server_id)
+    pub fn get_server_config(&self, server_id: &str) -> Option<GrpcServerConfig> {
+        self.config.read().unwrap().get_server(server_id).cloned()
+    }
+
+    /// Add or replace a server. The configuration is saved before it takes
+    /// effect.
+    pub async fn put_server(&self, server_id: &str, conf: GrpcServerConfig) -> Result<()> {
+        self.update_config(|config| {
+            config.servers.insert(server_id.to_string(), conf);
+        })
+        .await
+    }
+
+    /// Remove a server, returning whether it existed
+    pub async fn remove_server(&self, server_id: &str) -> Result<bool> {
+        let mut removed = false;
+        self.update_config(|config| {
+            removed = config.servers.remove(server_id).is_some();
+        })
+        .await?;
+        Ok(removed)
+    }
+
+    async fn update_config(&self, change: impl FnOnce(&mut ServerConfig)) -> Result<()> {
+        let _guard = self.updates.lock().await;
+
+        let mut config = self.config.read().unwrap().clone();
+        change(&mut config);
+        if let Some(path) = &self.config_path {
+            config.save(path).await?;
+        }
+
+        self.replace_config(config);
+        Ok(())
+    }
+
+    /// Switch to a new configuration, e.g. after the config file changed.
+    /// Returns the changes, which are also sent to subscribers.
+    pub async fn apply_config(&self, config: ServerConfig) -> Vec<ConfigEvent> {
+        let _guard = self.updates.lock().await;
+        self.replace_config(config)
+    }
+
+    /// Receive configuration changes
+    pub fn subscribe(&self) -> broadcast::Receiver<ConfigEvent> {
+        self.events.subscribe()
+    }
+
+    /// Send an event to subscribers
+    pub fn notify(&self, event: ConfigEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    /// Replace the configuration and rebuild the clients of servers whose
+    /// connection settings changed; other cached clients are kept.
+    fn replace_config(&self, config: ServerConfig) -> Vec<ConfigEvent> {
+        let previous = std::mem::replace(&mut *self.config.write().unwrap(), config.clone());
+        let mut events = Vec::new();
+        if previous.environments != config.environments {
+            self.environment_clients.clear();
+        }
+
+        let mut removed: Vec<&String> = previous
+            .servers
+            .keys()
+            .filter(|id| !config.servers.contains_key(*id))
+            .collect();
+        removed.sort();
+        for server_id in removed {
+            self.clients.remove(server_id);
+            self.health.remove(server_id);
+            self.environment_clients
+                .retain(|(id, _), _| id != server_id);
+            events.push(ConfigEvent::ServerRemoved {
+                server_id: server_id.clone(),
+            });
+        }
+
+        let mut servers: Vec<(&String, &GrpcServerConfig)> = config.servers.iter().collect();
+        servers.sort_by_key(|(id, _)| *id);
+        for (server_id, conf) in servers {
+            match previous.servers.get(server_id) {
+                None => {
+                    self.rebuild_client(server_id, conf);
+                    events.push(ConfigEvent::ServerAdded {
+                        server_id: server_id.clone(),
+                    });
+                }
+                Some(old) if old == conf => {}
+                Some(old) => {
+                    let reconnected = old.affects_client(conf);
+                    if reconnected {
+                        self.rebuild_client(server_id, conf);
+                    }
+                    events.push(ConfigEvent::ServerUpdated {
+                        server_id: server_id.clone(),
+                        reconnected,
+                    });
+                }
+            }
+        }
+
+        for event in &events {
+            info!("Configuration changed: {:?}", event);
+            self.notify(event.clone());
+        }
+        events
+    }
+
+    fn rebuild_client(&self, server_id: &str, conf: &GrpcServerConfig) {
+        self.clients.remove(server_id);
+        // Environment clients are rebuilt on first use
+        self.environment_clients
+            .retain(|(id, _), _| id != server_id);
+        // Probe results of the old connection no longer apply
+        self.health.remove(server_id);
+        match create_client(conf, self.redact, &TemplateEngine::new()) {
+            Ok(client) => {
+                self.clients.insert(server_id.to_string(), Arc::new(client));
+            }
+            // Retried, and reported, on first use
+            Err(e) => warn!(
+                "Failed to initialize client for server '{}': {}",
+                server_id, e
+            ),
+        }
+    }
+
+    /// Record the result of a health probe. The last error is kept when a
+    /// probe succeeds, so it stays visible after the server recovers.
+    pub fn record_health(
+        &self,
+        server_id: &str,
+        state: HealthState,
+        latency: Duration,
+        error: Option<String>,
+    ) {
+        // Servers removed while the probe was running stay removed
+        if self.get_server_config(server_id).is_none() {
+            return;
+        }
+
+        let last_error = match error {
+            Some(error) => Some(error),
+            None => self
+                .health
+                .get(server_id)
+                .and_then(|health| health.last_error.clone()),
+        };
+        self.health.insert(
+            server_id.to_string(),
+            ServerHealth {
+                state,
+                last_checked: Utc::now(),
+                latency,
+                last_error,
+            },
+        );
     }
 
     /// Check if a server client is initialized and available
@@ -89,17 +406,73 @@ impl AppState {
     /// Get connection status for all servers
     pub fn get_connection_status(&self) -> Vec<ServerStatus> {
         self.config
+            .read()
+            .unwrap()
             .servers
             .iter()
-            .map(|(id, config)| ServerStatus {
-                id: id.clone(),
-                name: config.name.clone(),
-                endpoint: config.endpoint.clone(),
-                connected: self.clients.contains_key(id),
-                description: config.description.clone(),
-            })
+            .map(|(id, config)| self.server_status(id, config))
             .collect()
     }
+
+    /// Get connection status for one server
+    pub fn get_server_status(&self, server_id: &str) -> Option<ServerStatus> {
+        let config = self.get_server_config(server_id)?;
+        Some(self.server_status(server_id, &config))
+    }
+
+    fn server_status(&self, id: &str, config: &GrpcServerConfig) -> ServerStatus {
+        let health = self.health.get(id).map(|health| health.clone());
+        let state = health.as_ref().map(|h| h.state).unwrap_or_default();
+        ServerStatus {
+            id: id.to_string(),
+            name: config.name.clone(),
+            endpoint: config.endpoint.clone(),
+            connected: matches!(state, HealthState::Serving | HealthState::NotServing),
+            description: config.description.clone(),
+            state,
+            last_checked: health.as_ref().map(|h| h.last_checked),
+            latency_ms: health.as_ref().map(|h| h.latency.as_secs_f64() * 1000.0),
+            last_error: health.and_then(|h| h.last_error),
+        }
+    }
+}
+
+/// Number of configuration events kept for slow subscribers
+const EVENT_BUFFER: usize = 64;
+
+/// A change to the server configuration
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ConfigEvent {
+    /// A server was added
+    #[serde(rename_all = "camelCase")]
+    ServerAdded { server_id: String },
+    /// A server's settings changed; `reconnected` when its client was
+    /// rebuilt because the endpoint, TLS, headers or auth changed
+    #[serde(rename_all = "camelCase")]
+    ServerUpdated {
+        server_id: String,
+        reconnected: bool,
+    },
+    /// A server was removed
+    #[serde(rename_all = "camelCase")]
+    ServerRemoved { server_id: String },
+    /// The config file could not be loaded; the previous configuration stays
+    /// active
+    ReloadFailed { error: String },
+}
+
+/// Create a client for a server, without a redactor when redaction is off
+fn create_client(
+    conf: &GrpcServerConfig,
+    redact: bool,
+    template: &TemplateEngine,
+) -> Result<GrpcClient> {
+    let mut client = GrpcClient::from_config_with_template(conf, template)?;
+    if !redact {
+        client.redactor = None;
+    }
+    Ok(client)
 }
 
 /// Server connection status for API responses
@@ -112,15 +485,24 @@ pub struct ServerStatus {
     pub name: String,
     /// Server endpoint (host:port)
     pub endpoint: String,
-    /// Whether the server is currently connected
+    /// Whether the last probe reached the server
     pub connected: bool,
     /// Optional server description
     pub description: Option<String>,
+    /// Result of the last health probe
+    pub state: HealthState,
+    /// When the server was last probed
+    pub last_checked: Option<DateTime<Utc>>,
+    /// Round-trip time of the last probe in milliseconds
+    pub latency_ms: Option<f64>,
+    /// Error of the most recent failed probe
+    pub last_error: Option<String>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::server::config::Environment;
     use std::collections::HashMap;
 
     fn create_test_config() -> ServerConfig {
@@ -134,9 +516,19 @@ mod tests {
                 ca_cert: None,
                 headers: HashMap::new(),
                 description: Some("Test server".to_string()),
+                auth: None,
+                retry: None,
+                redaction: None,
+                reflect_headers: HashMap::new(),
+                access: None,
             },
         );
-        ServerConfig { servers }
+        ServerConfig {
+            servers,
+            environments: HashMap::new(),
+            default_environment: None,
+            web: Default::default(),
+        }
     }
 
     #[tokio::test]
@@ -157,5 +549,177 @@ mod tests {
         assert_eq!(statuses.len(), 1);
         assert_eq!(statuses[0].id, "test");
         assert_eq!(statuses[0].name, "Test Server");
+        assert_eq!(statuses[0].state, HealthState::Unknown);
+        assert!(!statuses[0].connected);
+
+        state.record_health(
+            "test",
+            HealthState::Unreachable,
+            Duration::from_millis(3),
+            Some("connection refused".to_string()),
+        );
+        state.record_health("test", HealthState::Serving, Duration::from_millis(2), None);
+        let status = state.get_server_status("test").unwrap();
+        assert_eq!(status.state, HealthState::Serving);
+        assert!(status.connected);
+        assert!(status.last_checked.is_some());
+        assert_eq!(status.latency_ms, Some(2.0));
+        assert_eq!(status.last_error.as_deref(), Some("connection refused"));
+
+        state.record_health("missing", HealthState::Serving, Duration::ZERO, None);
+        assert!(!state.health.contains_key("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_session_environments() {
+        let mut config = create_test_config();
+        config.servers.get_mut("test").unwrap().endpoint = "localhost:{{var.port}}".to_string();
+        for (name, port) in [("dev", "9091"), ("prod", "9092")] {
+            config.environments.insert(
+                name.to_string(),
+                Environment {
+                    description: None,
+                    variables: HashMap::from([("port".to_string(), port.to_string())]),
+                    access: None,
+                },
+            );
+        }
+        config.default_environment = Some("dev".to_string());
+        let state = AppState::new(config.clone()).await.unwrap();
+
+        let session = |user: Option<&str>, id: &str| (user.map(str::to_string), id.to_string());
+        let a = session(None, "a");
+        assert_eq!(state.active_environment(&a).as_deref(), Some("dev"));
+        state.select_environment(&a, Some("prod")).unwrap();
+        assert!(state.select_environment(&a, Some("missing")).is_err());
+        assert_eq!(state.active_environment(&a).as_deref(), Some("prod"));
+        assert_eq!(
+            state.active_environment(&session(None, "b")).as_deref(),
+            Some("dev")
+        );
+        assert_eq!(
+            state
+                .active_environment(&session(Some("bob"), "a"))
+                .as_deref(),
+            Some("dev")
+        );
+
+        let client = state.get_client_in("test", Some("prod")).await.unwrap();
+        assert_eq!(client.endpoint.port, 9092);
+        assert!(state.get_client("test").await.is_err());
+
+        // Changed variables apply to new calls
+        config
+            .environments
+            .get_mut("prod")
+            .unwrap()
+            .variables
+            .insert("port".to_string(), "9093".to_string());
+        config.environments.remove("dev");
+        state.apply_config(config).await;
+        let client = state.get_client_in("test", Some("prod")).await.unwrap();
+        assert_eq!(client.endpoint.port, 9093);
+        assert_eq!(state.active_environment(&session(None, "b")), None);
+
+        state.select_environment(&a, None).unwrap();
+        assert_eq!(state.active_environment(&a), None);
+    }
+
+    #[tokio::test]
+    async fn test_server_changes_are_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.yml");
+        let path = path.to_str().unwrap();
+        let state = AppState::new(create_test_config())
+            .await
+            .unwrap()
+            .with_config_path(path);
+        assert!(state.is_client_available("test"));
+
+        let mut events = state.subscribe();
+        let mut conf = state.get_server_config("test").unwrap();
+        conf.endpoint = "localhost:9191".to_string();
+        state.put_server("test", conf).await.unwrap();
+        assert_eq!(
+            events.try_recv().unwrap(),
+            ConfigEvent::ServerUpdated {
+                server_id: "test".to_string(),
+                reconnected: true
+            }
+        );
+        assert_eq!(state.get_client("test").await.unwrap().endpoint.port, 9191);
+        assert_eq!(
+            ServerConfig::from_file(path).unwrap().servers["test"].endpoint,
+            "localhost:9191"
+        );
+
+        assert!(state.remove_server("test").await.unwrap());
+        assert!(!state.is_client_available("test"));
+        assert!(!state.remove_server("test").await.unwrap());
+        assert!(state.get_server_ids().is_empty());
+        assert!(ServerConfig::from_file(path).unwrap().servers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_config_rebuilds_changed_clients() {
+        let state = AppState::new(create_test_config()).await.unwrap();
+        let original = state.get_client("test").await.unwrap();
+
+        // Renaming keeps the client
+        let mut config = create_test_config();
+        config.servers.get_mut("test").unwrap().name = "Renamed".to_string();
+        let events = state.apply_config(config.clone()).await;
+        assert_eq!(
+            events,
+            vec![ConfigEvent::ServerUpdated {
+                server_id: "test".to_string(),
+                reconnected: false
+            }]
+        );
+        assert!(Arc::ptr_eq(
+            &original,
+            &state.get_client("test").await.unwrap()
+        ));
+
+        // Unchanged servers produce no events
+        assert!(state.apply_config(config.clone()).await.is_empty());
+
+        let mut headers = config.clone();
+        headers
+            .servers
+            .get_mut("test")
+            .unwrap()
+            .headers
+            .insert("x-team".to_string(), "a".to_string());
+        let events = state.apply_config(headers).await;
+        assert_eq!(
+            events,
+            vec![ConfigEvent::ServerUpdated {
+                server_id: "test".to_string(),
+                reconnected: true
+            }]
+        );
+        assert!(!Arc::ptr_eq(
+            &original,
+            &state.get_client("test").await.unwrap()
+        ));
+
+        let mut replaced = config;
+        let conf = replaced.servers.remove("test").unwrap();
+        replaced.servers.insert("other".to_string(), conf);
+        let events = state.apply_config(replaced).await;
+        assert_eq!(
+            events,
+            vec![
+                ConfigEvent::ServerRemoved {
+                    server_id: "test".to_string()
+                },
+                ConfigEvent::ServerAdded {
+                    server_id: "other".to_string()
+                },
+            ]
+        );
+        assert!(state.is_client_available("other"));
+        assert!(!state.is_client_available("test"));
     }
 }
@@ -0,0 +1,691 @@
+use crate::domain::status_code_name;
+use crate::live::{CallEvent, CallStatus, LiveCall};
+use crate::query::Query;
+use crate::reflection::StreamingType;
+use crate::server::access::{Action, Identity, authorize};
+use crate::server::calls::CallHandle;
+use crate::server::environments::{call_template, request_environment};
+use crate::server::handlers::{CallRequest, ErrorResponse};
+use crate::server::history::CallRecorder;
+use crate::server::state::AppState;
+use crate::template::TemplateEngine;
+use axum::{
+    Extension, Json as RequestJson,
+    extract::{
+        Path, State,
+        ws::{
+            CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code,
+            rejection::WebSocketUpgradeRejection,
+        },
+    },
+    http::{HeaderMap, StatusCode, header},
+    response::{
+        IntoResponse, Json, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, Stream, StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use tonic::Code;
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// Messages sent by the browser on a streaming call WebSocket
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamRequest {
+    /// Start the call; must be the first message
+    #[serde(rename_all = "camelCase")]
+    Start {
+        /// Full method name (e.g., "package.service/method")
+        method: String,
+        /// Custom headers to include in the gRPC call
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        /// Template variables referenced as {{var.name}} in messages and headers
+        #[serde(default)]
+        variables: HashMap<String, String>,
+        /// JSONPath or jq-style query applied to each response message
+        #[serde(default)]
+        query: Option<String>,
+        /// ID to track the call under; generated when absent
+        #[serde(default)]
+        call_id: Option<String>,
+    },
+    /// Send a request message
+    Message { data: Value },
+    /// Half-close the call: no more request messages follow
+    End,
+}
+
+/// Events of a streaming call, sent as WebSocket messages or SSE events
+/// named after their type
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    /// The call started; it can be cancelled with `DELETE /api/calls/{id}`
+    #[serde(rename_all = "camelCase")]
+    Started { call_id: String },
+    /// Response headers sent by the server
+    Headers { metadata: BTreeMap<String, String> },
+    /// A response message, or a value projected from it by the query
+    Message { data: Value },
+    /// The call ended with this gRPC status; always the last event
+    Status {
+        /// Canonical status code name (e.g. `OK`, `NOT_FOUND`)
+        code: String,
+        message: String,
+        trailers: BTreeMap<String, String>,
+    },
+    /// A message from the browser was rejected; the call continues unless
+    /// it hasn't started
+    Error {
+        error: String,
+        details: Option<String>,
+    },
+}
+
+impl StreamEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Started { .. } => "started",
+            Self::Headers { .. } => "headers",
+            Self::Message { .. } => "message",
+            Self::Status { .. } => "status",
+            Self::Error { .. } => "error",
+        }
+    }
+
+    fn error(error: &str, details: impl ToString) -> Self {
+        Self::Error {
+            error: error.to_string(),
+            details: Some(details.to_string()),
+        }
+    }
+
+    /// Final status of a call cancelled through the API
+    fn cancelled() -> Self {
+        Self::status(CallStatus::cancelled())
+    }
+
+    fn status(status: CallStatus) -> Self {
+        Self::Status {
+            code: status_code_name(status.code).to_string(),
+            message: status.message,
+            trailers: status.trailers.into_iter().collect(),
+        }
+    }
+
+    /// Stream events for a call event, applying the query to responses
+    fn from_call(event: CallEvent, query: Option<&Query>) -> Vec<Self> {
+        match event {
+            CallEvent::Headers(metadata) => vec![Self::Headers {
+                metadata: metadata.into_iter().collect(),
+            }],
+            CallEvent::Response(data) => match query {
+                Some(query) => query
+                    .apply(&data)
+                    .into_iter()
+                    .map(|data| Self::Message { data })
+                    .collect(),
+                None => vec![Self::Message { data }],
+            },
+            CallEvent::Finished(status) => vec![Self::status(status)],
+        }
+    }
+}
+
+fn error_response(status: StatusCode, error: &str, details: Option<String>) -> Response {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+            details,
+        }),
+    )
+        .into_response()
+}
+
+/// Whether a WebSocket handshake comes from the UI's own origin or one listed
+/// in `cors_origins`. CORS doesn't apply to WebSockets and browsers send
+/// cookies and basic credentials along, so any page could otherwise make
+/// calls as the user. Clients other than browsers send no `Origin`.
+fn origin_allowed(state: &AppState, headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        return true;
+    };
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    let same_origin = origin
+        .split_once("://")
+        .zip(headers.get(header::HOST))
+        .is_some_and(|((_, authority), host)| host.as_bytes() == authority.as_bytes());
+    same_origin
+        || state
+            .config
+            .read()
+            .unwrap()
+            .web
+            .cors_origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/') == origin)
+}
+
+/// A call started for the browser, listed in the call registry while it runs
+/// and recorded in history once it ends
+struct TrackedCall {
+    call: LiveCall,
+    handle: CallHandle,
+    recorder: CallRecorder,
+}
+
+/// Start a call on a configured server with per-call headers
+async fn start_call(
+    state: &AppState,
+    server_id: &str,
+    method: &str,
+    call_id: Option<String>,
+    headers: HashMap<String, String>,
+    environment: Option<&str>,
+    template: &TemplateEngine,
+) -> Result<TrackedCall, StreamEvent> {
+    let handle = state
+        .calls
+        .register(call_id, server_id, method)
+        .map_err(|e| StreamEvent::error("Call ID already in use", e))?;
+    let client = state
+        .get_client_in(server_id, environment)
+        .await
+        .map_err(|e| StreamEvent::error("Failed to connect to server", e))?;
+    let headers: Vec<(String, String)> = headers.into_iter().collect();
+    let headers = template
+        .render_headers(&headers)
+        .map_err(|e| StreamEvent::error("Failed to expand request templates", e))?;
+
+    let mut recorder = CallRecorder::new(&state.history, server_id, method, &headers)
+        .with_redactor(client.redactor.clone());
+    let call = client
+        .with_headers(headers)
+        .start_call(method)
+        .await
+        .map_err(|e| StreamEvent::error("Failed to call method", format!("{:#}", e)))?;
+    recorder.set_input_descriptor(call.input_descriptor().clone());
+    Ok(TrackedCall {
+        call,
+        handle,
+        recorder,
+    })
+}
+
+/// Save the record of a finished call
+async fn finish_recording(recorder: CallRecorder, status: &CallStatus) {
+    let error = (status.code != Code::Ok).then(|| status.message.clone());
+    recorder.finish(status.code, error).await;
+}
+
+/// Call a method over a WebSocket
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/call/ws",
+    params(
+        ("server_id" = String, Path, description = "Server identifier")
+    ),
+    responses(
+        (status = 101, description = "Switched to the WebSocket protocol. The browser sends `StreamRequest` messages, starting with `start`, and receives `StreamEvent` messages as the call runs"),
+        (status = 400, description = "Not a WebSocket handshake", body = ErrorResponse),
+        (status = 403, description = "Access to the server denied, or the page's origin is not allowed", body = ErrorResponse),
+        (status = 404, description = "Server not found", body = ErrorResponse)
+    ),
+    tag = "grpc"
+)]
+pub async fn call_websocket(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(server_id): Path<String>,
+    headers: HeaderMap,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    if !origin_allowed(&state, &headers) {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            "Access denied",
+            Some("WebSocket connections from this origin are not allowed".to_string()),
+        );
+    }
+    if state.get_server_config(&server_id).is_none() {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            "Server configuration not found",
+            None,
+        );
+    }
+    // Calls are checked once the method is known
+    if let Err(denied) = authorize(&state, &identity, &server_id, Action::View) {
+        return denied.into_response();
+    }
+
+    let environment = request_environment(&state, &identity, &headers);
+    let upgrade = match upgrade {
+        Ok(upgrade) => upgrade,
+        Err(e) => {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "Invalid WebSocket handshake",
+                Some(e.body_text()),
+            );
+        }
+    };
+
+    upgrade
+        .on_failed_upgrade(|e| warn!("WebSocket upgrade failed: {}", e))
+        .on_upgrade(move |socket| async move {
+            let call = run_websocket_call(&state, &server_id, &identity, environment, socket);
+            if let Err(e) = call.await {
+                warn!("Streaming call WebSocket failed: {:#}", e);
+            }
+        })
+}
+
+type WebSocketWriter = SplitSink<WebSocket, Message>;
+type WebSocketReader = SplitStream<WebSocket>;
+
+/// Exchange messages between the browser and a call until the call ends or
+/// the browser disconnects, which cancels the call
+async fn run_websocket_call(
+    state: &AppState,
+    server_id: &str,
+    identity: &Identity,
+    environment: Option<String>,
+    socket: WebSocket,
+) -> anyhow::Result<()> {
+    let (mut writer, mut incoming) = socket.split();
+    let result = exchange(
+        state,
+        server_id,
+        identity,
+        environment.as_deref(),
+        &mut incoming,
+        &mut writer,
+    )
+    .await;
+    // Flushes the reply to a close sent by the browser
+    let _ = writer.close().await;
+    result
+}
+
+async fn exchange(
+    state: &AppState,
+    server_id: &str,
+    identity: &Identity,
+    environment: Option<&str>,
+    incoming: &mut WebSocketReader,
+    writer: &mut WebSocketWriter,
+) -> anyhow::Result<()> {
+    // Wait for the start message
+    let (tracked, query, template) = loop {
+        let text = match read_incoming(incoming.next().await, writer).await? {
+            Incoming::Text(text) => text,
+            Incoming::Ignore => continue,
+            Incoming::Closed => return Ok(()),
+        };
+
+        let started = match serde_json::from_str::<StreamRequest>(&text) {
+            Ok(StreamRequest::Start {
+                method,
+                headers,
+                variables,
+                query,
+                call_id,
+            }) => {
+                let template = call_template(state, environment, variables);
+                let query =
+                    authorize(state, identity, server_id, Action::Call(&method))
+                        .map_err(|(_, Json(denied))| StreamEvent::Error {
+                            error: denied.error,
+                            details: denied.details,
+                        })
+                        .and_then(|()| {
+                            query.as_deref().map(Query::parse).transpose().map_err(|e| {
+                                StreamEvent::error("Invalid query", format!("{:#}", e))
+                            })
+                        });
+                match query {
+                    Ok(query) => start_call(
+                        state,
+                        server_id,
+                        &method,
+                        call_id,
+                        headers,
+                        environment,
+                        &template,
+                    )
+                    .await
+                    .map(|tracked| (tracked, query, template)),
+                    Err(event) => Err(event),
+                }
+            }
+            Ok(_) => Err(StreamEvent::error(
+                "Invalid message",
+                "The first message must start the call",
+            )),
+            Err(e) => Err(StreamEvent::error("Invalid message", e)),
+        };
+
+        match started {
+            Ok(started) => break started,
+            Err(event) => {
+                send_event(writer, &event).await?;
+                writer.send(close(close_code::POLICY)).await?;
+                return Ok(());
+            }
+        }
+    };
+
+    let TrackedCall {
+        mut call,
+        handle,
+        mut recorder,
+    } = tracked;
+    let started = StreamEvent::Started {
+        call_id: handle.id().to_string(),
+    };
+    send_event(writer, &started).await?;
+
+    let status = loop {
+        tokio::select! {
+            message = incoming.next() => {
+                let text = match read_incoming(message, writer).await? {
+                    Incoming::Text(text) => text,
+                    Incoming::Ignore => continue,
+                    // Dropping the call cancels it
+                    Incoming::Closed => return Ok(()),
+                };
+
+                let result = match serde_json::from_str::<StreamRequest>(&text) {
+                    Ok(StreamRequest::Message { data }) => match template.render_value(data) {
+                        Ok(data) => match call.send(data.clone()).await {
+                            Ok(()) => {
+                                recorder.request(data);
+                                Ok(())
+                            }
+                            Err(e) => Err(StreamEvent::error(
+                                "Invalid request message",
+                                format!("{:#}", e),
+                            )),
+                        },
+                        Err(e) => Err(StreamEvent::error("Failed to expand request templates", e)),
+                    },
+                    Ok(StreamRequest::End) => {
+                        call.close_send();
+                        Ok(())
+                    }
+                    Ok(StreamRequest::Start { .. }) => Err(StreamEvent::error(
+                        "Invalid message",
+                        "The call has already started",
+                    )),
+                    Err(e) => Err(StreamEvent::error("Invalid message", e)),
+                };
+                if let Err(event) = result {
+                    send_event(writer, &event).await?;
+                }
+            }
+            event = call.next_event() => {
+                // The call task always finishes with a status
+                let Some(event) = event else {
+                    return Ok(());
+                };
+                let finished = match &event {
+                    CallEvent::Response(response) => {
+                        handle.record_message();
+                        recorder.response(response.clone());
+                        None
+                    }
+                    CallEvent::Finished(status) => Some(status.clone()),
+                    CallEvent::Headers(_) => None,
+                };
+                for event in StreamEvent::from_call(event, query.as_ref()) {
+                    send_event(writer, &event).await?;
+                }
+                if let Some(status) = finished {
+                    break status;
+                }
+            }
+            _ = handle.cancelled() => {
+                // Dropping the call resets its HTTP/2 stream
+                drop(call);
+                send_event(writer, &StreamEvent::cancelled()).await?;
+                break CallStatus::cancelled();
+            }
+        }
+    };
+
+    finish_recording(recorder, &status).await;
+    writer.send(close(close_code::NORMAL)).await?;
+    Ok(())
+}
+
+/// A message from the browser. Pings and closes are answered by the socket.
+enum Incoming {
+    Text(String),
+    Ignore,
+    Closed,
+}
+
+async fn read_incoming(
+    message: Option<Result<Message, axum::Error>>,
+    writer: &mut WebSocketWriter,
+) -> anyhow::Result<Incoming> {
+    match message {
+        Some(Ok(Message::Text(text))) => Ok(Incoming::Text(text.to_string())),
+        Some(Ok(Message::Ping(_) | Message::Pong(_))) => Ok(Incoming::Ignore),
+        Some(Ok(Message::Binary(_))) => {
+            let event = StreamEvent::error("Invalid message", "Binary messages are not supported");
+            send_event(writer, &event).await?;
+            Ok(Incoming::Ignore)
+        }
+        Some(Err(e)) => Err(e.into()),
+        Some(Ok(Message::Close(_))) | None => Ok(Incoming::Closed),
+    }
+}
+
+fn close(code: u16) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: Default::default(),
+    }))
+}
+
+async fn send_event(writer: &mut WebSocketWriter, event: &StreamEvent) -> anyhow::Result<()> {
+    writer
+        .send(Message::Text(serde_json::to_string(event)?.into()))
+        .await?;
+    Ok(())
+}
+
+/// Call a unary or server-streaming method, receiving responses as
+/// server-sent events while the call runs
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/call/stream",
+    params(
+        ("server_id" = String, Path, description = "Server identifier")
+    ),
+    request_body = CallRequest,
+    responses(
+        (status = 200, description = "Stream of `started`, `headers`, `message` and a final `status` event, each carrying a StreamEvent", content_type = "text/event-stream", body = StreamEvent),
+        (status = 403, description = "Calling the method is not allowed", body = ErrorResponse),
+        (status = 404, description = "Server not found", body = ErrorResponse),
+        (status = 400, description = "Invalid request, query or connection failed, or a client-streaming method", body = ErrorResponse)
+    ),
+    tag = "grpc"
+)]
+pub async fn call_sse(
+    State(state): State<AppState>,
+    Extension(identity): Extension<Identity>,
+    Path(server_id): Path<String>,
+    session: HeaderMap,
+    RequestJson(request): RequestJson<CallRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, Response> {
+    if state.get_server_config(&server_id).is_none() {
+        return Err(error_response(
+            StatusCode::NOT_FOUND,
+            "Server configuration not found",
+            None,
+        ));
+    }
+    authorize(&state, &identity, &server_id, Action::Call(&request.method))
+        .map_err(IntoResponse::into_response)?;
+
+    let bad_request = |event: StreamEvent| match event {
+        StreamEvent::Error { error, details } => {
+            error_response(StatusCode::BAD_REQUEST, &error, details)
+        }
+        _ => unreachable!("only errors are rejected"),
+    };
+
+    let query = request
+        .query
+        .as_deref()
+        .map(Query::parse)
+        .transpose()
+        .map_err(|e| bad_request(StreamEvent::error("Invalid query", format!("{:#}", e))))?;
+    let environment = request_environment(&state, &identity, &session);
+    let template = call_template(&state, environment.as_deref(), request.variables);
+    let TrackedCall {
+        mut call,
+        handle,
+        mut recorder,
+    } = start_call(
+        &state,
+        &server_id,
+        &request.method,
+        request.call_id,
+        request.headers,
+        environment.as_deref(),
+        &template,
+    )
+    .await
+    .map_err(bad_request)?;
+
+    if matches!(
+        call.streaming_type,
+        StreamingType::ClientStream | StreamingType::BiDirectional
+    ) {
+        recorder.discard();
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "Client-streaming methods need the WebSocket endpoint",
+            Some(format!("Use /api/servers/{}/call/ws", server_id)),
+        ));
+    }
+
+    let data = template
+        .render_value(request.data)
+        .map_err(|e| bad_request(StreamEvent::error("Failed to expand request templates", e)))?;
+    call.send(data.clone()).await.map_err(|e| {
+        bad_request(StreamEvent::error(
+            "Invalid request message",
+            format!("{:#}", e),
+        ))
+    })?;
+    recorder.request(data);
+    call.close_send();
+
+    let started = StreamEvent::Started {
+        call_id: handle.id().to_string(),
+    };
+    let running = Some((call, handle, recorder, query));
+    let events = stream::unfold(running, |running| async move {
+        let (mut call, handle, mut recorder, query) = running?;
+        let event = tokio::select! {
+            event = call.next_event() => event?,
+            _ = handle.cancelled() => {
+                drop(call);
+                finish_recording(recorder, &CallStatus::cancelled()).await;
+                return Some((vec![StreamEvent::cancelled()], None));
+            }
+        };
+        match &event {
+            CallEvent::Response(response) => {
+                handle.record_message();
+                recorder.response(response.clone());
+            }
+            CallEvent::Finished(status) => {
+                finish_recording(recorder, status).await;
+                return Some((StreamEvent::from_call(event, query.as_ref()), None));
+            }
+            CallEvent::Headers(_) => {}
+        }
+        let events = StreamEvent::from_call(event, query.as_ref());
+        Some((events, Some((call, handle, recorder, query))))
+    })
+    .map(stream::iter)
+    .flatten();
+    let events = stream::once(async { started }).chain(events).map(|event| {
+        let data = serde_json::to_string(&event).unwrap_or_default();
+        Ok(Event::default().event(event.name()).data(data))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_stream_request_parsing() {
+        let start: StreamRequest = serde_json::from_value(json!({
+            "type": "start",
+            "method": "example.UserService/GetUser",
+            "headers": {"x-tenant": "acme"}
+        }))
+        .unwrap();
+        assert!(
+            matches!(start, StreamRequest::Start { ref method, .. } if method == "example.UserService/GetUser")
+        );
+
+        let end: StreamRequest = serde_json::from_value(json!({"type": "end"})).unwrap();
+        assert!(matches!(end, StreamRequest::End));
+        assert!(serde_json::from_value::<StreamRequest>(json!({"type": "message"})).is_err());
+    }
+
+    #[test]
+    fn test_stream_events_from_call() {
+        let query = Query::parse(".name").unwrap();
+        let events = StreamEvent::from_call(
+            CallEvent::Response(json!({"id": "1", "name": "Jane"})),
+            Some(&query),
+        );
+        assert_eq!(
+            events,
+            vec![StreamEvent::Message {
+                data: json!("Jane")
+            }]
+        );
+
+        let events = StreamEvent::from_call(
+            CallEvent::Finished(CallStatus {
+                code: Code::NotFound,
+                message: "no user".to_string(),
+                trailers: vec![("x-trace".to_string(), "abc".to_string())],
+            }),
+            None,
+        );
+        assert_eq!(
+            serde_json::to_value(&events[0]).unwrap(),
+            json!({
+                "type": "status",
+                "code": "NOT_FOUND",
+                "message": "no user",
+                "trailers": {"x-trace": "abc"}
+            })
+        );
+    }
+}
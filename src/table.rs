@@ -0,0 +1,491 @@
+use crate::format::{ResponseFormatter, StreamingFormatter};
+use anyhow::Result;
+use async_trait::async_trait;
+use prost_reflect::{Kind, MessageDescriptor};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+
+/// Turns responses into rows and columns.
+///
+/// A response whose message has a single repeated message field (e.g. a
+/// `ListUsersResponse` with `repeated User users`) contributes one row per
+/// element; any other response is one row. Columns are the given field paths
+/// or else the scalar fields of the row message, with nested messages
+/// flattened into dotted names such as `address.city`.
+#[derive(Debug, Clone, Default)]
+pub struct Tabulator {
+    columns: Vec<String>,
+    descriptor: Option<MessageDescriptor>,
+}
+
+impl Tabulator {
+    pub fn new(columns: Vec<String>, descriptor: Option<MessageDescriptor>) -> Self {
+        Self {
+            columns,
+            descriptor,
+        }
+    }
+
+    /// The rows contributed by one response
+    pub fn rows(&self, response: &Value) -> Vec<Value> {
+        let list_key = match &self.descriptor {
+            Some(descriptor) => list_field(descriptor).map(|(key, _)| key),
+            None => list_key(response),
+        };
+        match list_key {
+            // Empty lists are omitted from JSON
+            Some(key) => match response.get(&key) {
+                Some(Value::Array(items)) => items.clone(),
+                _ => Vec::new(),
+            },
+            None => vec![response.clone()],
+        }
+    }
+
+    /// Column names for the given rows
+    pub fn columns(&self, rows: &[Value]) -> Vec<String> {
+        if !self.columns.is_empty() {
+            return self.columns.clone();
+        }
+
+        if let Some(descriptor) = &self.descriptor {
+            let row_descriptor = match list_field(descriptor) {
+                Some((_, element)) => element,
+                None => descriptor.clone(),
+            };
+            let mut columns = Vec::new();
+            descriptor_columns(&row_descriptor, "", &mut Vec::new(), &mut columns);
+            return columns;
+        }
+
+        let mut columns = Vec::new();
+        for row in rows {
+            let mut leaves = Vec::new();
+            flatten(row, "", &mut leaves);
+            for (name, _) in leaves {
+                if !columns.contains(&name) {
+                    columns.push(name);
+                }
+            }
+        }
+        columns
+    }
+
+    /// Cell values of a row for the given columns
+    pub fn cells(&self, row: &Value, columns: &[String]) -> Vec<String> {
+        columns
+            .iter()
+            .map(|column| lookup(row, column).map(cell).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// Buffers all rows and prints them as an aligned table once complete
+pub struct TableFormatter {
+    table: Tabulator,
+    rows: Mutex<Vec<Value>>,
+}
+
+impl TableFormatter {
+    pub fn new(columns: Vec<String>) -> Self {
+        Self {
+            table: Tabulator::new(columns, None),
+            rows: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Take columns from the fields of this message type
+    pub fn with_descriptor(mut self, descriptor: Option<MessageDescriptor>) -> Self {
+        self.table.descriptor = descriptor;
+        self
+    }
+
+    fn render(&self, rows: &[Value]) -> String {
+        let columns = self.table.columns(rows);
+        if columns.is_empty() {
+            return String::new();
+        }
+
+        let cells: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| self.table.cells(row, &columns))
+            .collect();
+        let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut out = String::new();
+        write_row(&mut out, &columns, &widths);
+        let separators: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        write_row(&mut out, &separators, &widths);
+        for row in &cells {
+            write_row(&mut out, row, &widths);
+        }
+        out.trim_end().to_string()
+    }
+}
+
+fn write_row(out: &mut String, cells: &[String], widths: &[usize]) {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+        .collect();
+    let _ = writeln!(out, "{}", line.join("  ").trim_end());
+}
+
+#[async_trait]
+impl ResponseFormatter for TableFormatter {
+    async fn format_response(&self, response: &Value) -> Result<String> {
+        Ok(self.render(&self.table.rows(response)))
+    }
+
+    async fn format_error(&self, status: &tonic::Status) -> Result<String> {
+        Ok(format!(
+            "Error {}: {}",
+            status.code() as i32,
+            status.message()
+        ))
+    }
+}
+
+#[async_trait]
+impl StreamingFormatter for TableFormatter {
+    async fn format_stream_response(&self, response: &Value, _sequence: usize) -> Result<String> {
+        // Column widths depend on all rows, so the table is printed on completion
+        let rows = self.table.rows(response);
+        self.rows.lock().unwrap().extend(rows);
+        Ok(String::new())
+    }
+
+    async fn format_stream_start(&self) -> Result<String> {
+        Ok(String::new())
+    }
+
+    async fn format_stream_complete(&self, _total_responses: usize) -> Result<String> {
+        let rows = std::mem::take(&mut *self.rows.lock().unwrap());
+        Ok(self.render(&rows))
+    }
+
+    async fn format_stream_progress(
+        &self,
+        _processed: usize,
+        _total: Option<usize>,
+    ) -> Result<String> {
+        Ok(String::new())
+    }
+
+    async fn format_stream_error(&self, status: &tonic::Status, sequence: usize) -> Result<String> {
+        Ok(format!(
+            "Stream error at sequence {}: {} - {}",
+            sequence,
+            status.code() as i32,
+            status.message()
+        ))
+    }
+}
+
+/// Prints rows as CSV, streaming each row as it arrives
+pub struct CsvFormatter {
+    table: Tabulator,
+    /// Columns fixed by the header once it has been written
+    header: Mutex<Option<Vec<String>>>,
+}
+
+impl CsvFormatter {
+    pub fn new(columns: Vec<String>) -> Self {
+        Self {
+            table: Tabulator::new(columns, None),
+            header: Mutex::new(None),
+        }
+    }
+
+    /// Take columns from the fields of this message type
+    pub fn with_descriptor(mut self, descriptor: Option<MessageDescriptor>) -> Self {
+        self.table.descriptor = descriptor;
+        self
+    }
+
+    /// Header line (when not yet written) followed by the rows of a response
+    fn render_rows(&self, rows: &[Value]) -> String {
+        let mut header = self.header.lock().unwrap();
+        let mut out = String::new();
+        let columns = match &*header {
+            Some(columns) => columns.clone(),
+            None => {
+                let columns = self.table.columns(rows);
+                let _ = writeln!(out, "{}", csv_line(&columns));
+                *header = Some(columns.clone());
+                columns
+            }
+        };
+        for row in rows {
+            let _ = writeln!(out, "{}", csv_line(&self.table.cells(row, &columns)));
+        }
+        out.trim_end_matches('\n').to_string()
+    }
+}
+
+#[async_trait]
+impl ResponseFormatter for CsvFormatter {
+    async fn format_response(&self, response: &Value) -> Result<String> {
+        let rows = self.table.rows(response);
+        let columns = self.table.columns(&rows);
+        let mut lines = vec![csv_line(&columns)];
+        lines.extend(
+            rows.iter()
+                .map(|row| csv_line(&self.table.cells(row, &columns))),
+        );
+        Ok(lines.join("\n"))
+    }
+
+    async fn format_error(&self, status: &tonic::Status) -> Result<String> {
+        Ok(format!(
+            "Error {}: {}",
+            status.code() as i32,
+            status.message()
+        ))
+    }
+}
+
+#[async_trait]
+impl StreamingFormatter for CsvFormatter {
+    async fn format_stream_response(&self, response: &Value, _sequence: usize) -> Result<String> {
+        Ok(self.render_rows(&self.table.rows(response)))
+    }
+
+    async fn format_stream_start(&self) -> Result<String> {
+        // The header can only be written up front when columns are known
+        // without seeing any rows
+        if self.table.columns.is_empty() && self.table.descriptor.is_none() {
+            return Ok(String::new());
+        }
+        Ok(self.render_rows(&[]))
+    }
+
+    async fn format_stream_complete(&self, _total_responses: usize) -> Result<String> {
+        Ok(String::new())
+    }
+
+    async fn format_stream_progress(
+        &self,
+        _processed: usize,
+        _total: Option<usize>,
+    ) -> Result<String> {
+        Ok(String::new())
+    }
+
+    async fn format_stream_error(&self, status: &tonic::Status, sequence: usize) -> Result<String> {
+        Ok(format!(
+            "Stream error at sequence {}: {} - {}",
+            sequence,
+            status.code() as i32,
+            status.message()
+        ))
+    }
+}
+
+fn csv_line(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The single repeated message field of a list response, as JSON key and
+/// element type
+fn list_field(descriptor: &MessageDescriptor) -> Option<(String, MessageDescriptor)> {
+    let mut lists = descriptor.fields().filter_map(|field| match field.kind() {
+        Kind::Message(element) if field.is_list() => Some((field.json_name().to_string(), element)),
+        _ => None,
+    });
+    match (lists.next(), lists.next()) {
+        (Some(list), None) => Some(list),
+        _ => None,
+    }
+}
+
+/// Without a descriptor, an object with a single array of objects is a list
+fn list_key(response: &Value) -> Option<String> {
+    let Value::Object(map) = response else {
+        return None;
+    };
+    let mut lists = map.iter().filter(|(_, value)| {
+        matches!(value, Value::Array(items) if items.iter().all(Value::is_object) && !items.is_empty())
+    });
+    match (lists.next(), lists.next()) {
+        (Some((key, _)), None) => Some(key.clone()),
+        _ => None,
+    }
+}
+
+fn descriptor_columns(
+    descriptor: &MessageDescriptor,
+    prefix: &str,
+    visiting: &mut Vec<String>,
+    columns: &mut Vec<String>,
+) {
+    visiting.push(descriptor.full_name().to_string());
+    for field in descriptor.fields() {
+        let name = format!("{}{}", prefix, field.json_name());
+        match field.kind() {
+            // Repeated messages and maps don't fit in a cell
+            Kind::Message(_) if field.is_list() || field.is_map() => {}
+            Kind::Message(message)
+                if !message.full_name().starts_with("google.protobuf.")
+                    && !visiting.iter().any(|v| v == message.full_name()) =>
+            {
+                descriptor_columns(&message, &format!("{}.", name), visiting, columns);
+            }
+            _ => columns.push(name),
+        }
+    }
+    visiting.pop();
+}
+
+fn flatten<'a>(value: &'a Value, prefix: &str, out: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let name = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(child, &name, out);
+            }
+        }
+        _ if !prefix.is_empty() => out.push((prefix.to_string(), value)),
+        _ => {}
+    }
+}
+
+/// Value at a dotted path; segments may use proto (`snake_case`) or JSON names
+fn lookup<'a>(row: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(row, |value, segment| {
+        value
+            .get(segment)
+            .or_else(|| value.get(snake_to_camel(segment)))
+    })
+}
+
+fn snake_to_camel(name: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        Value::Array(items) if items.iter().all(|v| !v.is_object() && !v.is_array()) => {
+            items.iter().map(cell).collect::<Vec<_>>().join(",")
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn users() -> Vec<Value> {
+        vec![
+            json!({"id": "1", "name": "John", "payment": {"card": "4111", "cvv": "123"}}),
+            json!({"id": "2", "name": "Jane, Jr.", "tags": ["a", "b"]}),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_table_from_stream() {
+        let formatter = TableFormatter::new(Vec::new());
+        for (i, user) in users().iter().enumerate() {
+            let out = formatter.format_stream_response(user, i + 1).await.unwrap();
+            assert!(out.is_empty());
+        }
+        let table = formatter.format_stream_complete(2).await.unwrap();
+
+        assert_eq!(
+            table,
+            "id  name       payment.card  payment.cvv  tags\n\
+             --  ---------  ------------  -----------  ----\n\
+             1   John       4111          123\n\
+             2   Jane, Jr.                             a,b"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_table_from_list_response() {
+        let formatter = TableFormatter::new(vec!["id".to_string(), "payment.card".to_string()]);
+        let response = json!({"users": users(), "nextPageToken": "abc"});
+        let table = formatter.format_response(&response).await.unwrap();
+
+        assert_eq!(table, "id  payment.card\n--  ------------\n1   4111\n2");
+    }
+
+    #[tokio::test]
+    async fn test_csv_streams_rows() {
+        let formatter = CsvFormatter::new(vec!["id".to_string(), "name".to_string()]);
+        assert_eq!(formatter.format_stream_start().await.unwrap(), "id,name");
+
+        let users = users();
+        assert_eq!(
+            formatter
+                .format_stream_response(&users[0], 1)
+                .await
+                .unwrap(),
+            "1,John"
+        );
+        assert_eq!(
+            formatter
+                .format_stream_response(&users[1], 2)
+                .await
+                .unwrap(),
+            "2,\"Jane, Jr.\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_csv_header_from_first_row() {
+        let formatter = CsvFormatter::new(Vec::new());
+        assert_eq!(formatter.format_stream_start().await.unwrap(), "");
+        let out = formatter
+            .format_stream_response(&json!({"id": 1, "quote": "say \"hi\""}), 1)
+            .await
+            .unwrap();
+        assert_eq!(out, "id,quote\n1,\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_lookup_accepts_proto_names() {
+        let row = json!({"paymentInfo": {"cardNumber": "4111"}});
+        assert_eq!(
+            lookup(&row, "payment_info.card_number"),
+            Some(&json!("4111"))
+        );
+        assert_eq!(lookup(&row, "paymentInfo.missing"), None);
+    }
+}
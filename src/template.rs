@@ -0,0 +1,239 @@
+use anyhow::{Context, Result, anyhow, bail};
+use rand::Rng;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Expands `{{ ... }}` placeholders in request bodies, headers and endpoints.
+///
+/// Supported expressions:
+/// - `{{env.NAME}}` - environment variable
+/// - `{{var.name}}` - user-provided variable (`--var` or vars file)
+/// - `{{uuid}}` - random v4 UUID
+/// - `{{now}}` - current time in RFC 3339 format
+/// - `{{randInt MIN MAX}}` - random integer in the inclusive range
+#[derive(Debug, Clone, Default)]
+pub struct TemplateEngine {
+    vars: HashMap<String, String>,
+    /// Reject `{{env.NAME}}`, for templates from untrusted callers
+    deny_env: bool,
+}
+
+impl TemplateEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_vars(vars: HashMap<String, String>) -> Self {
+        Self {
+            vars,
+            deny_env: false,
+        }
+    }
+
+    /// Disallow `{{env.NAME}}`, so templates sent by web API callers can't
+    /// read the server's environment
+    pub fn without_env(mut self) -> Self {
+        self.deny_env = true;
+        self
+    }
+
+    /// Build an engine from `--var k=v` pairs and an optional vars file.
+    /// Command-line variables override the ones from the file.
+    pub fn from_args(vars: &[String], vars_file: Option<&str>) -> Result<Self> {
+        let mut engine = match vars_file {
+            Some(path) => Self::with_vars(load_vars_file(path)?),
+            None => Self::new(),
+        };
+
+        for var in vars {
+            let (key, value) = var
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid variable format: {}. Expected 'key=value'", var))?;
+            engine.set(key.trim(), value);
+        }
+
+        Ok(engine)
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.vars.insert(key.into(), value.into());
+    }
+
+    pub fn vars(&self) -> &HashMap<String, String> {
+        &self.vars
+    }
+
+    /// Expand every placeholder in the input string
+    pub fn render(&self, input: &str) -> Result<String> {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+
+        while let Some(start) = rest.find("{{") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or_else(|| anyhow!("Unterminated template expression in: {}", input))?;
+            output.push_str(&self.evaluate(after[..end].trim())?);
+            rest = &after[end + 2..];
+        }
+        output.push_str(rest);
+
+        Ok(output)
+    }
+
+    /// Expand placeholders in every string (keys and values) of a JSON value
+    pub fn render_value(&self, value: Value) -> Result<Value> {
+        Ok(match value {
+            Value::String(s) => Value::String(self.render(&s)?),
+            Value::Array(arr) => Value::Array(
+                arr.into_iter()
+                    .map(|v| self.render_value(v))
+                    .collect::<Result<_>>()?,
+            ),
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(k, v)| Ok((self.render(&k)?, self.render_value(v)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            other => other,
+        })
+    }
+
+    /// Expand placeholders in header values
+    pub fn render_headers(&self, headers: &[(String, String)]) -> Result<Vec<(String, String)>> {
+        headers
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), self.render(v)?)))
+            .collect()
+    }
+
+    fn evaluate(&self, expr: &str) -> Result<String> {
+        let mut parts = expr.split_whitespace();
+        let head = parts
+            .next()
+            .ok_or_else(|| anyhow!("Empty template expression"))?;
+        let args: Vec<&str> = parts.collect();
+
+        if let Some(name) = head.strip_prefix("env.") {
+            if self.deny_env {
+                bail!("Environment variables are not available here: {}", name);
+            }
+            return std::env::var(name)
+                .with_context(|| format!("Environment variable not set: {}", name));
+        }
+
+        if let Some(name) = head.strip_prefix("var.") {
+            return self
+                .vars
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("Template variable not defined: {}", name));
+        }
+
+        match (head, args.as_slice()) {
+            ("uuid", []) => Ok(uuid::Uuid::new_v4().to_string()),
+            ("now", []) => Ok(chrono::Utc::now().to_rfc3339()),
+            ("randInt", [min, max]) => {
+                let min: i64 = min
+                    .parse()
+                    .with_context(|| format!("Invalid randInt minimum: {}", min))?;
+                let max: i64 = max
+                    .parse()
+                    .with_context(|| format!("Invalid randInt maximum: {}", max))?;
+                if min > max {
+                    bail!("randInt minimum {} is greater than maximum {}", min, max);
+                }
+                Ok(rand::rng().random_range(min..=max).to_string())
+            }
+            _ => bail!("Unknown template expression: {{{{{}}}}}", expr),
+        }
+    }
+}
+
+/// Load template variables from a YAML or JSON file of string key/value pairs
+pub fn load_vars_file(path: &str) -> Result<HashMap<String, String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read vars file: {}", path))?;
+    let raw: HashMap<String, serde_yaml::Value> = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse vars file: {}", path))?;
+
+    raw.into_iter()
+        .map(|(k, v)| {
+            let value = match v {
+                serde_yaml::Value::String(s) => s,
+                serde_yaml::Value::Number(n) => n.to_string(),
+                serde_yaml::Value::Bool(b) => b.to_string(),
+                serde_yaml::Value::Null => String::new(),
+                _ => bail!("Variable '{}' in {} must be a scalar value", k, path),
+            };
+            Ok((k, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_vars_and_env() {
+        let mut engine = TemplateEngine::new();
+        engine.set("user_id", "42");
+        // SAFETY: test-only environment mutation with a unique variable name
+        unsafe { std::env::set_var("GRPC_CLIENT_TEMPLATE_TEST", "secret") };
+
+        let rendered = engine
+            .render("Bearer {{env.GRPC_CLIENT_TEMPLATE_TEST}} for {{ var.user_id }}")
+            .unwrap();
+        assert_eq!(rendered, "Bearer secret for 42");
+    }
+
+    #[test]
+    fn test_render_without_env() {
+        let mut engine = TemplateEngine::new().without_env();
+        engine.set("user_id", "42");
+        assert!(engine.render("{{env.PATH}}").is_err());
+        assert!(engine.render_value(json!({"key": "{{env.PATH}}"})).is_err());
+        assert_eq!(engine.render("{{var.user_id}}").unwrap(), "42");
+    }
+
+    #[test]
+    fn test_render_helpers() {
+        let engine = TemplateEngine::new();
+
+        let id = engine.render("{{uuid}}").unwrap();
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+
+        let now = engine.render("{{now}}").unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(&now).is_ok());
+
+        let n: i64 = engine.render("{{randInt 1 3}}").unwrap().parse().unwrap();
+        assert!((1..=3).contains(&n));
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let engine = TemplateEngine::new();
+        assert!(engine.render("{{var.missing}}").is_err());
+        assert!(engine.render("{{unknown}}").is_err());
+        assert!(engine.render("{{randInt 5 1}}").is_err());
+        assert!(engine.render("{{uuid").is_err());
+        assert_eq!(engine.render("no templates").unwrap(), "no templates");
+    }
+
+    #[test]
+    fn test_render_value() {
+        let engine = TemplateEngine::from_args(&["id=7".to_string()], None).unwrap();
+        let value = engine
+            .render_value(json!({"user_id": "{{var.id}}", "tags": ["{{var.id}}"], "size": 1}))
+            .unwrap();
+        assert_eq!(value, json!({"user_id": "7", "tags": ["7"], "size": 1}));
+    }
+
+    #[test]
+    fn test_from_args_invalid() {
+        assert!(TemplateEngine::from_args(&["novalue".to_string()], None).is_err());
+    }
+}
@@ -0,0 +1,566 @@
+use crate::client::CallOutcome;
+use crate::domain::{duration_str, parse_status_code, status_code_name};
+use crate::jsonpath::JsonPath;
+use crate::profile::ServerResolver;
+use crate::reflection::StreamingType;
+use crate::template::TemplateEngine;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+use tonic::{Code, Status};
+
+/// A YAML file with a list of calls and assertions
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestFile {
+    /// Suite name, defaults to the file path
+    pub name: Option<String>,
+    /// Default server (context name or endpoint) for all tests
+    pub server: Option<String>,
+    /// Headers sent with every test in the file
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub tests: Vec<TestCase>,
+}
+
+/// A single call and the assertions on its result
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestCase {
+    pub name: String,
+    /// Server (context name or endpoint), overriding the file default
+    pub server: Option<String>,
+    /// Method to call (service/method)
+    pub method: String,
+    /// Request message, or an array of messages for client streaming
+    #[serde(default = "empty_request")]
+    pub request: Value,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+/// Assertions on a call result. Field paths are evaluated against the
+/// response message, or against the array of messages for methods that
+/// stream responses.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectations {
+    /// Expected status code name, defaults to OK
+    pub status: Option<String>,
+    /// Expected number of response messages
+    pub messages: Option<usize>,
+    /// Maximum call duration (e.g. "250ms")
+    #[serde(default, with = "duration_str::option")]
+    pub max_latency: Option<Duration>,
+    #[serde(default)]
+    pub fields: Vec<FieldAssertion>,
+}
+
+/// An assertion on the values selected by a JSONPath expression
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldAssertion {
+    pub path: String,
+    /// Expected value; compared with the array of matches when the path
+    /// selects several values
+    pub equals: Option<Value>,
+    /// Regex every selected value must match
+    pub matches: Option<String>,
+    /// Whether the path must select at least one value
+    pub exists: Option<bool>,
+}
+
+fn empty_request() -> Value {
+    Value::Object(Default::default())
+}
+
+/// Outcome of one test case
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub suite: String,
+    pub name: String,
+    pub duration: Duration,
+    /// Assertion failures; empty when the test passed
+    pub failures: Vec<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Results of all test files in a run
+#[derive(Debug, Clone, Default)]
+pub struct TestReport {
+    pub results: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.results.iter().map(|r| r.duration).sum()
+    }
+
+    /// Per-test results followed by a pass/fail summary
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for result in &self.results {
+            let _ = writeln!(
+                out,
+                "{} {} › {} ({} ms)",
+                if result.passed() { "PASS" } else { "FAIL" },
+                result.suite,
+                result.name,
+                result.duration.as_millis()
+            );
+            for failure in &result.failures {
+                let _ = writeln!(out, "     {}", failure);
+            }
+        }
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "{} passed, {} failed, {} total ({} ms)",
+            self.passed(),
+            self.failed(),
+            self.results.len(),
+            self.duration().as_millis()
+        );
+        out
+    }
+
+    /// JUnit XML report with one `<testsuite>` per test file
+    pub fn to_junit_xml(&self) -> String {
+        let mut suites: Vec<(&str, Vec<&TestResult>)> = Vec::new();
+        for result in &self.results {
+            match suites.iter_mut().find(|(name, _)| *name == result.suite) {
+                Some((_, results)) => results.push(result),
+                None => suites.push((&result.suite, vec![result])),
+            }
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+            self.results.len(),
+            self.failed(),
+            self.duration().as_secs_f64()
+        );
+        for (suite, results) in suites {
+            let failures = results.iter().filter(|r| !r.passed()).count();
+            let time: Duration = results.iter().map(|r| r.duration).sum();
+            let _ = writeln!(
+                xml,
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+                escape_xml(suite),
+                results.len(),
+                failures,
+                time.as_secs_f64()
+            );
+            for result in results {
+                let _ = write!(
+                    xml,
+                    "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                    escape_xml(&result.name),
+                    escape_xml(suite),
+                    result.duration.as_secs_f64()
+                );
+                match result.failures.first() {
+                    None => xml.push_str("/>\n"),
+                    Some(first) => {
+                        let _ = writeln!(
+                            xml,
+                            ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>",
+                            escape_xml(first),
+                            escape_xml(&result.failures.join("\n"))
+                        );
+                    }
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Load a test file, expanding templates (e.g. `{{var.endpoint}}`) in each
+/// of its strings
+pub fn load_test_file(path: &str, template: &TemplateEngine) -> Result<TestFile> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read test file: {}", path))?;
+    let value: Value =
+        serde_yaml::from_str(&content).with_context(|| format!("Invalid test file: {}", path))?;
+    let value = template
+        .render_value(value)
+        .with_context(|| format!("Failed to expand templates in test file: {}", path))?;
+    serde_json::from_value(value).with_context(|| format!("Invalid test file: {}", path))
+}
+
+/// Run every test in the given files
+pub async fn run_test_files(
+    paths: &[String],
+    resolver: &mut ServerResolver,
+    template: &TemplateEngine,
+) -> Result<TestReport> {
+    let mut report = TestReport::default();
+    for path in paths {
+        let file = load_test_file(path, template)?;
+        let suite = file.name.clone().unwrap_or_else(|| path.clone());
+        for case in &file.tests {
+            report
+                .results
+                .push(run_test_case(&suite, &file, case, resolver).await);
+        }
+    }
+    Ok(report)
+}
+
+async fn run_test_case(
+    suite: &str,
+    file: &TestFile,
+    case: &TestCase,
+    resolver: &mut ServerResolver,
+) -> TestResult {
+    let mut result = TestResult {
+        suite: suite.to_string(),
+        name: case.name.clone(),
+        duration: Duration::ZERO,
+        failures: Vec::new(),
+    };
+
+    let client = match resolver.client(case.server.as_deref().or(file.server.as_deref())) {
+        Ok(client) => client,
+        Err(e) => {
+            result
+                .failures
+                .push(format!("Failed to create client: {:#}", e));
+            return result;
+        }
+    };
+    let headers = file
+        .headers
+        .iter()
+        .chain(&case.headers)
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let client = client.with_headers(headers);
+
+    // Only the call itself counts towards the latency, not the reflection
+    // lookup before it
+    let resolved = match client.resolve_call(&case.method).await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            result.failures = case.expect.check(Err(e), Duration::ZERO).1;
+            return result;
+        }
+    };
+    let start = Instant::now();
+    let outcome = client
+        .execute_resolved(&resolved, case.request.clone())
+        .await;
+    result.duration = start.elapsed();
+    result.failures = case.expect.check(outcome, result.duration).1;
+
+    result
+}
+
+impl Expectations {
+    /// Expand templates in expected values, e.g. to compare with a variable
+    /// captured by an earlier workflow step
+    pub fn render(&self, template: &TemplateEngine) -> Result<Self> {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| {
+                Ok(FieldAssertion {
+                    path: field.path.clone(),
+                    equals: field
+                        .equals
+                        .clone()
+                        .map(|value| template.render_value(value))
+                        .transpose()?,
+                    matches: field
+                        .matches
+                        .as_deref()
+                        .map(|pattern| template.render(pattern))
+                        .transpose()?,
+                    exists: field.exists,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            fields,
+            ..self.clone()
+        })
+    }
+
+    /// Check a call result, returning the assertion root of a successful call
+    /// (see [`response_root`]) together with any failures
+    pub fn check(
+        &self,
+        outcome: Result<CallOutcome>,
+        duration: Duration,
+    ) -> (Option<Value>, Vec<String>) {
+        let mut failures = Vec::new();
+        let mut root = None;
+
+        let expected_status = self.status.as_deref().unwrap_or("OK");
+        let expected_code = match parse_status_code(expected_status) {
+            Ok(code) => code,
+            Err(e) => return (None, vec![e.to_string()]),
+        };
+
+        match outcome {
+            Ok(outcome) => {
+                if expected_code != Code::Ok {
+                    failures.push(format!(
+                        "Expected status {}, got OK",
+                        status_code_name(expected_code)
+                    ));
+                }
+                if let Some(expected) = self.messages
+                    && expected != outcome.responses.len()
+                {
+                    failures.push(format!(
+                        "Expected {} response messages, got {}",
+                        expected,
+                        outcome.responses.len()
+                    ));
+                }
+
+                let value = response_root(outcome);
+                for assertion in &self.fields {
+                    if let Err(failure) = check_field(assertion, &value) {
+                        failures.push(failure);
+                    }
+                }
+                root = Some(value);
+            }
+            Err(e) => match e.chain().find_map(|cause| cause.downcast_ref::<Status>()) {
+                Some(status) if status.code() == expected_code => {}
+                Some(status) => failures.push(format!(
+                    "Expected status {}, got {}: {}",
+                    status_code_name(expected_code),
+                    status_code_name(status.code()),
+                    status.message()
+                )),
+                None => failures.push(format!("Call failed: {:#}", e)),
+            },
+        }
+
+        if let Some(budget) = self.max_latency
+            && duration > budget
+        {
+            failures.push(format!(
+                "Latency {} ms exceeded budget of {} ms",
+                duration.as_millis(),
+                budget.as_millis()
+            ));
+        }
+
+        (root, failures)
+    }
+}
+
+/// The value JSONPath expressions are evaluated against: the response message,
+/// or the array of messages for methods that stream responses
+pub fn response_root(outcome: CallOutcome) -> Value {
+    match outcome.streaming_type {
+        StreamingType::ServerStream | StreamingType::BiDirectional => {
+            Value::Array(outcome.responses)
+        }
+        StreamingType::Unary | StreamingType::ClientStream => {
+            outcome.responses.into_iter().next().unwrap_or(Value::Null)
+        }
+    }
+}
+
+/// Check one field assertion, returning a failure message
+fn check_field(assertion: &FieldAssertion, root: &Value) -> Result<(), String> {
+    let path = JsonPath::parse(&assertion.path).map_err(|e| e.to_string())?;
+    let selected = path.select(root);
+
+    if let Some(exists) = assertion.exists
+        && exists == selected.is_empty()
+    {
+        return Err(if exists {
+            format!("{}: expected a value, found none", path)
+        } else {
+            format!("{}: expected no value, found {}", path, selected[0])
+        });
+    }
+
+    if let Some(expected) = &assertion.equals {
+        let actual = match selected.as_slice() {
+            [] => return Err(format!("{}: expected {}, found no value", path, expected)),
+            [value] => (*value).clone(),
+            values => Value::Array(values.iter().map(|v| (*v).clone()).collect()),
+        };
+        if &actual != expected {
+            return Err(format!("{}: expected {}, got {}", path, expected, actual));
+        }
+    }
+
+    if let Some(pattern) = &assertion.matches {
+        let regex = Regex::new(pattern)
+            .map_err(|e| format!("{}: invalid regex '{}': {}", path, pattern, e))?;
+        if selected.is_empty() {
+            return Err(format!(
+                "{}: expected a match for /{}/, found no value",
+                path, pattern
+            ));
+        }
+        for value in selected {
+            let text = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if !regex.is_match(&text) {
+                return Err(format!("{}: {} does not match /{}/", path, value, pattern));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn assertion(yaml: &str) -> FieldAssertion {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_field_assertions() {
+        let response = json!({"id": "1", "email": "john@example.com", "tags": ["a", "b"]});
+
+        assert!(check_field(&assertion("{path: '$.id', equals: '1'}"), &response).is_ok());
+        assert!(check_field(&assertion("{path: '$.id', equals: '2'}"), &response).is_err());
+        assert!(
+            check_field(
+                &assertion("{path: '$.email', matches: '@example\\.com$'}"),
+                &response
+            )
+            .is_ok()
+        );
+        assert!(check_field(&assertion("{path: '$.tags[*]', equals: [a, b]}"), &response).is_ok());
+        assert!(check_field(&assertion("{path: '$.missing', exists: false}"), &response).is_ok());
+
+        let failure = check_field(&assertion("{path: '$.missing', exists: true}"), &response);
+        assert_eq!(
+            failure.unwrap_err(),
+            "$.missing: expected a value, found none"
+        );
+    }
+
+    #[test]
+    fn test_parse_test_file() {
+        let file: TestFile = serde_yaml::from_str(
+            r#"
+server: localhost:3003
+tests:
+  - name: list users
+    method: example.UserService/ListUsers
+    expect:
+      messages: 2
+      max_latency: 500ms
+      fields:
+        - path: $[0].name
+          equals: John Doe
+"#,
+        )
+        .unwrap();
+
+        let case = &file.tests[0];
+        assert_eq!(case.request, json!({}));
+        assert_eq!(case.expect.messages, Some(2));
+        assert_eq!(case.expect.max_latency, Some(Duration::from_millis(500)));
+        assert_eq!(case.expect.fields.len(), 1);
+    }
+
+    #[test]
+    fn test_load_test_file_expands_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("users.yml");
+        std::fs::write(
+            &path,
+            r#"
+server: "{{var.endpoint}}"
+tests:
+  - name: create user
+    method: example.UserService/CreateUser
+    request: { name: "{{var.name}}" }
+    expect:
+      max_latency: 250ms
+"#,
+        )
+        .unwrap();
+        let mut template = TemplateEngine::new();
+        template.set("endpoint", "localhost:3003");
+        template.set("name", "Jane \"JD\" Doe\nadmin: true");
+
+        let file = load_test_file(&path.to_string_lossy(), &template).unwrap();
+        assert_eq!(file.server.as_deref(), Some("localhost:3003"));
+        let case = &file.tests[0];
+        assert_eq!(
+            case.request,
+            json!({"name": "Jane \"JD\" Doe\nadmin: true"})
+        );
+        assert_eq!(case.expect.max_latency, Some(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let report = TestReport {
+            results: vec![
+                TestResult {
+                    suite: "users".to_string(),
+                    name: "create".to_string(),
+                    duration: Duration::from_millis(12),
+                    failures: vec![],
+                },
+                TestResult {
+                    suite: "users".to_string(),
+                    name: "get <missing>".to_string(),
+                    duration: Duration::from_millis(3),
+                    failures: vec!["Expected status OK, got NOT_FOUND".to_string()],
+                },
+            ],
+        };
+
+        let xml = report.to_junit_xml();
+        assert!(xml.contains("<testsuites tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testcase name=\"create\" classname=\"users\" time=\"0.012\"/>"));
+        assert!(xml.contains("name=\"get &lt;missing&gt;\""));
+        assert!(xml.contains("<failure message=\"Expected status OK, got NOT_FOUND\">"));
+        assert!(report.to_text().contains("1 passed, 1 failed, 2 total"));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::client::GrpcClient;
+use crate::jsonpath::JsonPath;
+use crate::profile::ServerResolver;
+use crate::template::TemplateEngine;
+use crate::test_runner::Expectations;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// A YAML file describing a sequence of calls. Steps run in order and may
+/// capture values from their responses into variables that later steps
+/// reference as `{{var.name}}` in requests and headers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workflow {
+    pub name: Option<String>,
+    /// Default server (context name or endpoint) for all steps
+    pub server: Option<String>,
+    /// Headers sent with every step
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Initial variables, overridden by --var
+    #[serde(default)]
+    pub vars: BTreeMap<String, String>,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub name: String,
+    /// Server (context name or endpoint), overriding the workflow default
+    pub server: Option<String>,
+    pub method: String,
+    #[serde(default = "empty_request")]
+    pub request: Value,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Variables to capture from the response, as name -> JSONPath
+    #[serde(default)]
+    pub capture: BTreeMap<String, String>,
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+fn empty_request() -> Value {
+    Value::Object(Default::default())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepStatus {
+    Passed,
+    Failed(Vec<String>),
+    /// Not run because an earlier step failed
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub name: String,
+    pub method: String,
+    pub status: StepStatus,
+    pub duration: Duration,
+    /// Responses of the step, as used for assertions and captures
+    pub response: Option<Value>,
+    pub captured: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkflowReport {
+    pub name: String,
+    pub steps: Vec<StepResult>,
+    /// Variables at the end of the run, including captured ones
+    pub vars: BTreeMap<String, String>,
+}
+
+impl WorkflowReport {
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|s| s.status == StepStatus::Passed)
+    }
+
+    /// Per-step results with captured variables
+    pub fn to_text(&self, show_responses: bool) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Workflow: {}", self.name);
+        for (i, step) in self.steps.iter().enumerate() {
+            let label = match step.status {
+                StepStatus::Passed => "PASS",
+                StepStatus::Failed(_) => "FAIL",
+                StepStatus::Skipped => "SKIP",
+            };
+            let _ = write!(out, "{} {}. {} [{}]", label, i + 1, step.name, step.method);
+            if step.status != StepStatus::Skipped {
+                let _ = write!(out, " ({} ms)", step.duration.as_millis());
+            }
+            out.push('\n');
+
+            if let StepStatus::Failed(failures) = &step.status {
+                for failure in failures {
+                    let _ = writeln!(out, "     {}", failure);
+                }
+            }
+            for (name, value) in &step.captured {
+                let _ = writeln!(out, "     {} = {}", name, value);
+            }
+            if show_responses && let Some(response) = &step.response {
+                let json = serde_json::to_string_pretty(response).unwrap_or_default();
+                for line in json.lines() {
+                    let _ = writeln!(out, "     {}", line);
+                }
+            }
+        }
+
+        let passed = self
+            .steps
+            .iter()
+            .filter(|s| s.status == StepStatus::Passed)
+            .count();
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{} of {} steps passed", passed, self.steps.len());
+        out
+    }
+}
+
+/// Load a workflow file. Templates are expanded per step when it runs, so
+/// steps can use variables captured by earlier steps.
+pub fn load_workflow(path: &str) -> Result<Workflow> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read workflow file: {}", path))?;
+    serde_yaml::from_str(&content).with_context(|| format!("Invalid workflow file: {}", path))
+}
+
+/// Run the steps of a workflow in order, stopping at the first failure.
+/// `template` supplies command-line variables, which take precedence over the
+/// workflow's own `vars`.
+pub async fn run_workflow(
+    workflow: &Workflow,
+    resolver: &mut ServerResolver,
+    template: &TemplateEngine,
+) -> Result<WorkflowReport> {
+    let mut vars = workflow.vars.clone();
+    vars.extend(template.vars().clone());
+    let mut template = TemplateEngine::with_vars(vars.into_iter().collect());
+
+    let mut steps = Vec::new();
+    let mut failed = false;
+    for step in &workflow.steps {
+        let result = if failed {
+            StepResult {
+                name: step.name.clone(),
+                method: step.method.clone(),
+                status: StepStatus::Skipped,
+                duration: Duration::ZERO,
+                response: None,
+                captured: BTreeMap::new(),
+            }
+        } else {
+            run_step(workflow, step, resolver, &mut template).await
+        };
+        failed |= matches!(result.status, StepStatus::Failed(_));
+        steps.push(result);
+    }
+
+    Ok(WorkflowReport {
+        name: workflow
+            .name
+            .clone()
+            .unwrap_or_else(|| "workflow".to_string()),
+        steps,
+        vars: template.vars().clone().into_iter().collect(),
+    })
+}
+
+async fn run_step(
+    workflow: &Workflow,
+    step: &Step,
+    resolver: &mut ServerResolver,
+    template: &mut TemplateEngine,
+) -> StepResult {
+    let mut result = StepResult {
+        name: step.name.clone(),
+        method: step.method.clone(),
+        status: StepStatus::Passed,
+        duration: Duration::ZERO,
+        response: None,
+        captured: BTreeMap::new(),
+    };
+
+    let prepared = prepare_step(workflow, step, resolver, template);
+    let (client, method, request, expect) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            result.status = StepStatus::Failed(vec![format!("{:#}", e)]);
+            return result;
+        }
+    };
+
+    let start = Instant::now();
+    let outcome = client.execute_call(&method, request).await;
+    result.duration = start.elapsed();
+
+    let (response, mut failures) = expect.check(outcome, result.duration);
+    if let Some(response) = &response {
+        for (name, path) in &step.capture {
+            match capture(response, path) {
+                Ok(value) => {
+                    template.set(name, &value);
+                    result.captured.insert(name.clone(), value);
+                }
+                Err(e) => failures.push(format!("Failed to capture '{}': {:#}", name, e)),
+            }
+        }
+    }
+
+    result.response = response;
+    if !failures.is_empty() {
+        result.status = StepStatus::Failed(failures);
+    }
+    result
+}
+
+/// Resolve the client and expand templates in the method, request, headers
+/// and expectations
+fn prepare_step(
+    workflow: &Workflow,
+    step: &Step,
+    resolver: &mut ServerResolver,
+    template: &TemplateEngine,
+) -> Result<(GrpcClient, String, Value, Expectations)> {
+    let server = step
+        .server
+        .as_deref()
+        .or(workflow.server.as_deref())
+        .map(|server| template.render(server))
+        .transpose()?;
+    let client = resolver.client(server.as_deref())?;
+
+    let headers: Vec<(String, String)> = workflow
+        .headers
+        .iter()
+        .chain(&step.headers)
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    let headers = template
+        .render_headers(&headers)
+        .context("Failed to expand header templates")?;
+    let method = template.render(&step.method)?;
+    let request = template
+        .render_value(step.request.clone())
+        .context("Failed to expand request templates")?;
+
+    let expect = step
+        .expect
+        .render(template)
+        .context("Failed to expand expectation templates")?;
+
+    Ok((client.with_headers(headers), method, request, expect))
+}
+
+/// Extract a value for a variable; strings are captured as-is, other values
+/// as JSON
+fn capture(response: &Value, path: &str) -> Result<String> {
+    let path = JsonPath::parse(path)?;
+    let value = path
+        .select_first(response)
+        .with_context(|| format!("{} did not match any value", path))?;
+    Ok(match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_capture() {
+        let response = json!({"id": "42", "count": 3, "tags": ["a"]});
+        assert_eq!(capture(&response, "$.id").unwrap(), "42");
+        assert_eq!(capture(&response, "$.count").unwrap(), "3");
+        assert_eq!(capture(&response, "$.tags").unwrap(), r#"["a"]"#);
+        assert!(capture(&response, "$.missing").is_err());
+    }
+
+    #[test]
+    fn test_parse_workflow() {
+        let workflow: Workflow = serde_yaml::from_str(
+            r#"
+server: localhost:3003
+vars:
+  name: Jane
+steps:
+  - name: create
+    method: example.UserService/CreateUser
+    request: { user: { name: "{{var.name}}" } }
+    capture:
+      user_id: $.id
+  - name: update
+    method: example.UserService/UpdateUser
+    request: { user_id: "{{var.user_id}}" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(workflow.steps.len(), 2);
+        assert_eq!(workflow.steps[0].capture["user_id"], "$.id");
+        assert_eq!(
+            workflow.steps[1].request,
+            json!({"user_id": "{{var.user_id}}"})
+        );
+    }
+}
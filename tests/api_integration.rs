@@ -155,6 +155,26 @@ async fn test_call_method_valid_server_connection_failure() {
     assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn test_call_method_invalid_query() {
+    let server = create_test_server().await;
+
+    let call_request = json!({
+        "method": "example.UserService/CreateUser",
+        "data": {},
+        "query": ".users["
+    });
+
+    let response = server
+        .post("/api/servers/user-service/call")
+        .json(&call_request)
+        .await;
+
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json();
+    assert_eq!(body["error"], "Invalid query");
+}
+
 #[tokio::test]
 async fn test_openapi_spec_structure() {
     let server = create_test_server().await;
@@ -263,3 +283,52 @@ async fn test_method_not_allowed() {
 
     assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
 }
+
+#[tokio::test]
+async fn test_server_crud() {
+    let server = create_test_server().await;
+    let conf = json!({
+        "name": "Inventory",
+        "endpoint": "localhost:9292",
+        "plaintext": true,
+        "ca_cert": null,
+        "description": null
+    });
+
+    let response = server.post("/api/servers/inventory").json(&conf).await;
+    assert_eq!(response.status_code(), StatusCode::CREATED);
+    let body: Value = response.json();
+    assert_eq!(body["id"], "inventory");
+    assert_eq!(body["endpoint"], "localhost:9292");
+
+    let response = server.post("/api/servers/inventory").json(&conf).await;
+    assert_eq!(response.status_code(), StatusCode::CONFLICT);
+
+    let mut invalid = conf.clone();
+    invalid["endpoint"] = json!("localhost:grpc");
+    let response = server.put("/api/servers/inventory").json(&invalid).await;
+    assert_eq!(response.status_code(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json();
+    assert_eq!(body["error"], "Invalid server configuration");
+
+    let mut updated = conf.clone();
+    updated["endpoint"] = json!("localhost:9393");
+    let response = server.put("/api/servers/inventory").json(&updated).await;
+    assert_eq!(response.status_code(), StatusCode::OK);
+
+    let servers: Value = server.get("/api/servers").await.json();
+    let inventory = servers
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["id"] == "inventory")
+        .unwrap();
+    assert_eq!(inventory["endpoint"], "localhost:9393");
+
+    let response = server.delete("/api/servers/inventory").await;
+    assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    let response = server.delete("/api/servers/inventory").await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    let response = server.put("/api/servers/inventory").json(&conf).await;
+    assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+}
@@ -14,6 +14,39 @@ pub async fn start_grpc_server() {
     });
 }
 
+/// Start the plaintext sample server on a free port in its own runtime and
+/// wait until it accepts connections. Returns the endpoint to connect to.
+#[allow(dead_code)]
+pub fn start_plaintext_sample_server() -> String {
+    start_plaintext_sample_server_with_health(sample_server::HealthReporter::default())
+}
+
+/// Like [`start_plaintext_sample_server`], reporting health through `health`
+#[allow(dead_code)]
+pub fn start_plaintext_sample_server_with_health(health: sample_server::HealthReporter) -> String {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .expect("Failed to find a free port");
+
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to build runtime");
+        let server = sample_server::start_plaintext_grpc_server_with_health(port, health);
+        if let Err(e) = runtime.block_on(server) {
+            eprintln!("Error starting server: {}", e);
+        }
+    });
+
+    let addr = format!("127.0.0.1:{}", port);
+    for _ in 0..100 {
+        if std::net::TcpStream::connect(&addr).is_ok() {
+            return addr;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    panic!("Sample server did not start on {}", addr);
+}
+
 /// Async test helper macros and utilities
 #[macro_export]
 macro_rules! test_async {
@@ -63,6 +63,11 @@ async fn test_grpc_client_from_config() {
         ca_cert: None,
         headers: HashMap::new(),
         description: Some("Test server".to_string()),
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config);
@@ -89,6 +94,11 @@ async fn test_grpc_client_with_headers() {
         ca_cert: None,
         headers,
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config);
@@ -119,6 +129,11 @@ async fn test_grpc_client_connection_failure() {
         ca_cert: None,
         headers: HashMap::new(),
         description: Some("Test server for connection failure".to_string()),
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -160,6 +175,11 @@ async fn test_grpc_client_tls_configuration() {
         ca_cert: Some("/path/to/ca.pem".to_string()),
         headers: HashMap::new(),
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let tls_client = GrpcClient::from_config(&tls_config);
@@ -183,6 +203,11 @@ async fn test_reflection_service_discovery() {
         ca_cert: None,
         headers: HashMap::new(),
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -211,6 +236,11 @@ async fn test_error_handling_patterns() {
         ca_cert: None,
         headers: HashMap::new(),
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -238,6 +268,11 @@ async fn test_concurrent_client_operations() {
         ca_cert: None,
         headers: HashMap::new(),
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -310,6 +345,11 @@ async fn test_client_state_management() {
         ca_cert: None,
         headers,
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -337,6 +377,11 @@ async fn test_method_call_parameter_validation() {
         ca_cert: None,
         headers: HashMap::new(),
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -365,6 +410,11 @@ async fn test_client_output_format() {
         ca_cert: None,
         headers: HashMap::new(),
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -393,6 +443,11 @@ async fn test_client_performance_cache() {
         ca_cert: None,
         headers: HashMap::new(),
         description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
     };
 
     let client = GrpcClient::from_config(&config).unwrap();
@@ -0,0 +1,1898 @@
+mod common;
+
+use assert_cmd::Command;
+use clap::Parser;
+use common::{start_plaintext_sample_server, start_plaintext_sample_server_with_health};
+use grpc_client::bench::{BenchConfig, run_bench};
+use grpc_client::health::ServingStatus;
+use grpc_client::profile::ServerResolver;
+use grpc_client::redact::{RedactionConfig, Redactor};
+use grpc_client::retry::RetryPolicy;
+use grpc_client::server::AppState;
+use grpc_client::server::config::{Environment, GrpcServerConfig, ServerConfig};
+use grpc_client::server::probe::{HealthState, probe_all};
+use grpc_client::server::routes::create_router_with_swagger;
+use grpc_client::template::TemplateEngine;
+use grpc_client::test_runner::run_test_files;
+use grpc_client::workflow::{StepStatus, Workflow, load_workflow, run_workflow};
+use grpc_client::{Cli, GrpcClient};
+use prost::Message as _;
+use sample_server::pb::{
+    CreateUserRequest, GetUserRequest, ListUsersRequest, UpdateUserRequest, User,
+    user_service_server::{UserService, UserServiceServer},
+};
+use sample_server::{ExampleService, HealthReporter, RawReflectionService};
+use serde_json::{Value, json};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tonic::{Code, Request, Response, Status, Streaming};
+
+fn sample_config(endpoint: &str) -> GrpcServerConfig {
+    GrpcServerConfig {
+        name: "Sample Server".to_string(),
+        endpoint: endpoint.to_string(),
+        plaintext: true,
+        ca_cert: None,
+        headers: HashMap::new(),
+        description: None,
+        auth: None,
+        retry: None,
+        redaction: None,
+        reflect_headers: HashMap::new(),
+        access: None,
+    }
+}
+
+fn sample_client(endpoint: &str) -> GrpcClient {
+    GrpcClient::from_config(&sample_config(endpoint)).unwrap()
+}
+
+#[tokio::test]
+async fn test_bench_unary_requests() {
+    let endpoint = start_plaintext_sample_server();
+    let client = sample_client(&endpoint);
+
+    let config = BenchConfig {
+        total: Some(40),
+        concurrency: 4,
+        connections: 2,
+        ..Default::default()
+    };
+    let report = run_bench(
+        &client,
+        "example.UserService/CreateUser",
+        json!({"user": {"name": "Jane", "email": "jane@example.com"}}),
+        &config,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.total_requests, 40);
+    assert_eq!(report.succeeded, 40);
+    assert_eq!(report.failed, 0);
+    assert_eq!(report.status_codes.get("OK"), Some(&40));
+    assert!(report.latency.p50_ms <= report.latency.p99_ms);
+    assert!(report.latency.p99_ms <= report.latency.max_ms);
+    assert!(report.requests_per_second > 0.0);
+}
+
+#[tokio::test]
+async fn test_bench_reports_status_code_names() {
+    let (endpoint, _) = start_flaky_server(vec![
+        Status::unavailable("down"),
+        Status::resource_exhausted("busy"),
+    ])
+    .await;
+    let client = sample_client(&endpoint);
+
+    let config = BenchConfig {
+        total: Some(4),
+        concurrency: 1,
+        ..Default::default()
+    };
+    let report = run_bench(
+        &client,
+        "example.UserService/CreateUser",
+        json!({}),
+        &config,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.failed, 2);
+    assert_eq!(report.status_codes.get("OK"), Some(&2));
+    assert_eq!(report.status_codes.get("UNAVAILABLE"), Some(&1));
+    assert_eq!(report.status_codes.get("RESOURCE_EXHAUSTED"), Some(&1));
+}
+
+#[tokio::test]
+async fn test_bench_duration_with_rate_limit() {
+    let endpoint = start_plaintext_sample_server();
+    let client = sample_client(&endpoint);
+
+    let config = BenchConfig {
+        duration: Some(Duration::from_millis(500)),
+        rps: Some(20.0),
+        concurrency: 2,
+        ..Default::default()
+    };
+    let report = run_bench(&client, "example.UserService/ListUsers", json!({}), &config)
+        .await
+        .unwrap();
+
+    // 20 rps over half a second allows roughly 10 requests
+    assert!(report.total_requests > 0);
+    assert!(
+        report.total_requests <= 12,
+        "sent {}",
+        report.total_requests
+    );
+    assert_eq!(report.failed, 0);
+}
+
+#[tokio::test]
+async fn test_bench_reports_unknown_method() {
+    let endpoint = start_plaintext_sample_server();
+    let client = sample_client(&endpoint);
+
+    let result = run_bench(
+        &client,
+        "example.UserService/Missing",
+        json!({}),
+        &BenchConfig::default(),
+    )
+    .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_execute_call_reports_attempts() {
+    let endpoint = start_plaintext_sample_server();
+    let mut client = sample_client(&endpoint);
+    client.retry = Some(Default::default());
+
+    let outcome = client
+        .execute_call("example.UserService/CreateUser", json!({}))
+        .await
+        .unwrap();
+    assert_eq!(outcome.attempts, 1);
+    assert_eq!(outcome.responses.len(), 1);
+    assert_eq!(outcome.responses[0]["name"], "John Doe");
+}
+
+/// `authorization` header and message of a `CreateUser` call
+type ReceivedCall = (Option<String>, CreateUserRequest);
+
+/// User service that fails `CreateUser` with queued statuses before
+/// answering like the sample server
+#[derive(Clone, Default)]
+struct FlakyUserService {
+    failures: Arc<Mutex<VecDeque<Status>>>,
+    calls: Arc<AtomicU32>,
+    received: Arc<Mutex<Vec<ReceivedCall>>>,
+}
+
+#[tonic::async_trait]
+impl UserService for FlakyUserService {
+    type GetUserStream = <ExampleService as UserService>::GetUserStream;
+    type ListUsersStream = <ExampleService as UserService>::ListUsersStream;
+
+    async fn get_user(
+        &self,
+        request: Request<Streaming<GetUserRequest>>,
+    ) -> Result<Response<Self::GetUserStream>, Status> {
+        ExampleService.get_user(request).await
+    }
+
+    async fn create_user(
+        &self,
+        request: Request<CreateUserRequest>,
+    ) -> Result<Response<User>, Status> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .map(|value| value.to_str().unwrap().to_string());
+        self.received
+            .lock()
+            .unwrap()
+            .push((authorization, request.get_ref().clone()));
+        let failure = self.failures.lock().unwrap().pop_front();
+        match failure {
+            Some(status) => Err(status),
+            None => ExampleService.create_user(request).await,
+        }
+    }
+
+    async fn update_user(
+        &self,
+        request: Request<UpdateUserRequest>,
+    ) -> Result<Response<User>, Status> {
+        ExampleService.update_user(request).await
+    }
+
+    async fn list_users(
+        &self,
+        request: Request<ListUsersRequest>,
+    ) -> Result<Response<Self::ListUsersStream>, Status> {
+        ExampleService.list_users(request).await
+    }
+}
+
+/// Serve a [`FlakyUserService`] failing with `failures` first. Returns the
+/// endpoint and the number of `CreateUser` calls received.
+async fn start_flaky_server(failures: Vec<Status>) -> (String, Arc<AtomicU32>) {
+    let service = FlakyUserService {
+        failures: Arc::new(Mutex::new(failures.into())),
+        ..Default::default()
+    };
+    let calls = service.calls.clone();
+    (serve_user_service(service).await, calls)
+}
+
+/// Serve a user service with reflection on a free port
+async fn serve_user_service(service: FlakyUserService) -> String {
+    let reflection =
+        RawReflectionService::new(include_bytes!("../sample-server/src/pb/example.bin")).unwrap();
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .unwrap();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(reflection.into_server())
+            .add_service(UserServiceServer::new(service))
+            .serve(addr),
+    );
+    for _ in 0..100 {
+        if TcpStream::connect(addr).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    addr.to_string()
+}
+
+/// `google.rpc.Status`, as sent in `grpc-status-details-bin`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<prost_types::Any>,
+}
+
+/// `google.rpc.RetryInfo`
+#[derive(Clone, PartialEq, prost::Message)]
+struct RetryInfo {
+    #[prost(message, optional, tag = "1")]
+    retry_delay: Option<prost_types::Duration>,
+}
+
+fn unavailable_with_retry_info(delay: Duration) -> Status {
+    let retry_info = RetryInfo {
+        retry_delay: Some(delay.try_into().unwrap()),
+    };
+    let details = RpcStatus {
+        code: Code::Unavailable as i32,
+        message: "overloaded".to_string(),
+        details: vec![prost_types::Any {
+            type_url: "type.googleapis.com/google.rpc.RetryInfo".to_string(),
+            value: retry_info.encode_to_vec(),
+        }],
+    };
+    Status::with_details(
+        Code::Unavailable,
+        "overloaded",
+        details.encode_to_vec().into(),
+    )
+}
+
+#[tokio::test]
+async fn test_execute_call_retries_unavailable() {
+    let (endpoint, calls) = start_flaky_server(vec![
+        Status::unavailable("restarting"),
+        Status::unavailable("restarting"),
+    ])
+    .await;
+    let mut client = sample_client(&endpoint);
+    client.retry = Some(RetryPolicy {
+        initial_backoff: Duration::from_millis(10),
+        ..Default::default()
+    });
+
+    let outcome = client
+        .execute_call("example.UserService/CreateUser", json!({}))
+        .await
+        .unwrap();
+    assert_eq!(outcome.attempts, 3);
+    assert_eq!(outcome.responses[0]["name"], "John Doe");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // Without a retry policy the first failure is returned
+    let (endpoint, calls) = start_flaky_server(vec![Status::unavailable("restarting")]).await;
+    let error = sample_client(&endpoint)
+        .execute_call("example.UserService/CreateUser", json!({}))
+        .await
+        .unwrap_err();
+    let status = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<Status>());
+    assert_eq!(status.map(Status::code), Some(Code::Unavailable));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_execute_call_honours_retry_info() {
+    let delay = Duration::from_millis(400);
+    let (endpoint, calls) = start_flaky_server(vec![unavailable_with_retry_info(delay)]).await;
+    let mut client = sample_client(&endpoint);
+    // The first computed backoff is at most 1ms; max_backoff only caps the
+    // server's delay
+    client.retry = Some(RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_secs(1),
+        ..Default::default()
+    });
+
+    let start = Instant::now();
+    let outcome = client
+        .execute_call("example.UserService/CreateUser", json!({}))
+        .await
+        .unwrap();
+    assert!(
+        start.elapsed() >= delay,
+        "retried after {:?}",
+        start.elapsed()
+    );
+    assert_eq!(outcome.attempts, 2);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_runner_user_service_fixture() {
+    let endpoint = start_plaintext_sample_server();
+    let cli = Cli::parse_from([
+        "grpc-client",
+        "--plaintext",
+        "--var",
+        &format!("endpoint={}", endpoint),
+        "test",
+        "fixtures/tests/user_service.yml",
+    ]);
+    let template = TemplateEngine::from_args(&cli.vars, None).unwrap();
+    let mut resolver = ServerResolver::new(cli).unwrap();
+
+    let report = run_test_files(
+        &["fixtures/tests/user_service.yml".to_string()],
+        &mut resolver,
+        &template,
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(report.results.len(), 3);
+    assert_eq!(report.failed(), 0, "{}", report.to_text());
+}
+
+#[tokio::test]
+async fn test_runner_reports_failures() {
+    let endpoint = start_plaintext_sample_server();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("failing.yml");
+    std::fs::write(
+        &path,
+        format!(
+            r#"
+server: {endpoint}
+tests:
+  - name: wrong name
+    method: example.UserService/CreateUser
+    expect:
+      messages: 2
+      fields:
+        - path: $.name
+          equals: Nobody
+  - name: missing method
+    method: example.UserService/DeleteUser
+"#
+        ),
+    )
+    .unwrap();
+    let path = path.to_string_lossy().to_string();
+
+    let cli = Cli::parse_from(["grpc-client", "--plaintext", "test", &path]);
+    let mut resolver = ServerResolver::new(cli).unwrap();
+    let report = run_test_files(&[path], &mut resolver, &TemplateEngine::new())
+        .await
+        .unwrap();
+
+    assert_eq!(report.failed(), 2);
+    assert_eq!(report.results[0].failures.len(), 2);
+    assert!(report.results[0].failures[1].contains("expected \"Nobody\""));
+    assert!(report.results[1].failures[0].contains("DeleteUser not found"));
+    assert!(report.to_junit_xml().contains("failures=\"2\""));
+}
+
+#[tokio::test]
+async fn test_workflow_captures_variables() {
+    let endpoint = start_plaintext_sample_server();
+    let cli = Cli::parse_from([
+        "grpc-client",
+        "--plaintext",
+        "--var",
+        &format!("endpoint={}", endpoint),
+        "run",
+        "fixtures/workflows/user_lifecycle.yml",
+    ]);
+    let template = TemplateEngine::from_args(&cli.vars, None).unwrap();
+    let mut resolver = ServerResolver::new(cli).unwrap();
+
+    let workflow = load_workflow("fixtures/workflows/user_lifecycle.yml").unwrap();
+    let report = run_workflow(&workflow, &mut resolver, &template)
+        .await
+        .unwrap();
+
+    assert!(report.passed(), "{}", report.to_text(true));
+    assert_eq!(report.steps.len(), 3);
+    assert_eq!(report.steps[0].captured.get("user_id").unwrap(), "1");
+    assert_eq!(report.vars.get("user_id").unwrap(), "1");
+    assert_eq!(report.steps[2].response.as_ref().unwrap()[0]["id"], "1");
+}
+
+#[tokio::test]
+async fn test_workflow_skips_steps_after_failure() {
+    let endpoint = start_plaintext_sample_server();
+    let workflow: Workflow = serde_yaml::from_str(&format!(
+        r#"
+server: {endpoint}
+steps:
+  - name: create
+    method: example.UserService/CreateUser
+    capture:
+      user_id: $.missing
+  - name: get
+    method: example.UserService/GetUser
+    request: [{{ user_id: "{{{{var.user_id}}}}" }}]
+"#
+    ))
+    .unwrap();
+
+    let cli = Cli::parse_from(["grpc-client", "--plaintext", "run", "unused.yml"]);
+    let mut resolver = ServerResolver::new(cli).unwrap();
+    let report = run_workflow(&workflow, &mut resolver, &TemplateEngine::new())
+        .await
+        .unwrap();
+
+    assert!(!report.passed());
+    assert!(matches!(report.steps[0].status, StepStatus::Failed(_)));
+    assert_eq!(report.steps[1].status, StepStatus::Skipped);
+}
+
+#[tokio::test]
+async fn test_redaction_of_responses() {
+    let endpoint = start_plaintext_sample_server();
+    let request = json!({"user": {"name": "Jane"}});
+
+    let mut config = RedactionConfig {
+        fields: vec!["^(cardNumber|cvv)$".to_string()],
+        paths: vec!["$.identityInfo.ssn".to_string()],
+        ..Default::default()
+    };
+    let mut client = sample_client(&endpoint);
+    client.redactor = Redactor::from_config(&config).unwrap().map(Arc::new);
+    let responses = client
+        .handle_call("example.UserService/CreateUser", request.clone())
+        .await
+        .unwrap();
+    assert_eq!(responses[0]["paymentInfo"]["cardNumber"], "[REDACTED]");
+    assert_eq!(responses[0]["paymentInfo"]["cvv"], "[REDACTED]");
+    assert_eq!(responses[0]["paymentInfo"]["expirationDate"], "12/2025");
+    assert_eq!(responses[0]["identityInfo"]["ssn"], "[REDACTED]");
+
+    config.enabled = false;
+    client.redactor = Redactor::from_config(&config).unwrap().map(Arc::new);
+    let responses = client
+        .handle_call("example.UserService/CreateUser", request)
+        .await
+        .unwrap();
+    assert_eq!(responses[0]["paymentInfo"]["cvv"], "123");
+}
+
+#[tokio::test]
+async fn test_default_redaction_of_debug_redact_fields() {
+    let endpoint = start_plaintext_sample_server();
+
+    let mut client = sample_client(&endpoint);
+    client.redactor = Redactor::from_config(&RedactionConfig::default())
+        .unwrap()
+        .map(Arc::new);
+    let responses = client
+        .handle_call("example.UserService/CreateUser", json!({}))
+        .await
+        .unwrap();
+    assert_eq!(responses[0]["paymentInfo"]["cardNumber"], "[REDACTED]");
+    assert_eq!(responses[0]["paymentInfo"]["cvv"], "[REDACTED]");
+    assert_eq!(responses[0]["paymentInfo"]["expirationDate"], "12/2025");
+    assert_eq!(responses[0]["identityInfo"]["ssn"], "[REDACTED]");
+
+    let output = Command::cargo_bin("grpc-client")
+        .unwrap()
+        .args(["--plaintext", "--format", "json", "call", &endpoint])
+        .args(["example.UserService/CreateUser", "-d", "{}"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8_lossy(&output);
+    assert_eq!(output.matches("[REDACTED]").count(), 3, "{}", output);
+    assert!(!output.contains("124-56-1234"), "{}", output);
+    assert!(!output.contains("2580 1234"), "{}", output);
+}
+
+#[test]
+fn test_repeated_call_prints_diffs() {
+    let endpoint = start_plaintext_sample_server();
+    let call = |args: &[&str]| {
+        Command::cargo_bin("grpc-client")
+            .unwrap()
+            .args(["--plaintext", "--format", "json", "call", &endpoint])
+            .args([
+                "example.UserService/ListUsers",
+                "-d",
+                "{}",
+                "--interval",
+                "10ms",
+            ])
+            .args(args)
+            .assert()
+    };
+
+    let output = call(&["--repeat", "3", "--diff"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8_lossy(&output);
+    for iteration in ["] #1\n", "] #2\n", "] #3\n"] {
+        assert_eq!(output.matches(iteration).count(), 1, "{}", output);
+    }
+    // The first response is printed in full, the others only as changes
+    assert_eq!(output.matches("Jane Doe").count(), 1, "{}", output);
+    assert_eq!(output.matches("(no changes)").count(), 2, "{}", output);
+
+    let output = call(&["--repeat", "2"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8_lossy(&output);
+    assert_eq!(output.matches("Jane Doe").count(), 2, "{}", output);
+
+    // --diff needs calls to compare
+    call(&["--diff"]).code(2);
+    call(&["--repeat", "2", "--watch"]).code(2);
+}
+
+#[tokio::test]
+async fn test_probe_reports_server_health() {
+    let endpoint = start_plaintext_sample_server();
+    // A port nothing listens on
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed_endpoint = closed.local_addr().unwrap().to_string();
+    drop(closed);
+
+    let config = ServerConfig {
+        servers: HashMap::from([
+            ("sample".to_string(), sample_config(&endpoint)),
+            ("down".to_string(), sample_config(&closed_endpoint)),
+        ]),
+        ..ServerConfig::default()
+    };
+    let state = AppState::new(config).await.unwrap();
+    probe_all(&state).await;
+
+    let sample = state.get_server_status("sample").unwrap();
+    assert_eq!(sample.state, HealthState::Serving);
+    assert!(sample.connected);
+    assert!(sample.last_checked.is_some());
+    assert!(sample.latency_ms.unwrap() > 0.0);
+    assert_eq!(sample.last_error, None);
+
+    let down = state.get_server_status("down").unwrap();
+    assert_eq!(down.state, HealthState::Unreachable);
+    assert!(!down.connected);
+    assert!(down.last_error.is_some());
+}
+
+/// Serve only the health service, without reflection
+async fn start_health_only_server(health: &HealthReporter) -> String {
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .unwrap();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(health.service())
+            .serve(addr),
+    );
+
+    for _ in 0..100 {
+        if tokio::net::TcpStream::connect(addr).await.is_ok() {
+            return addr.to_string();
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("Health server did not start on {}", addr);
+}
+
+#[tokio::test]
+async fn test_health_check_and_watch_without_reflection() {
+    let health = HealthReporter::default();
+    let endpoint = start_health_only_server(&health).await;
+    let client = sample_client(&endpoint);
+
+    assert_eq!(
+        client.health_check("").await.unwrap(),
+        ServingStatus::Serving
+    );
+    let error = client.health_check("missing").await.unwrap_err();
+    assert_eq!(error.code(), tonic::Code::NotFound);
+
+    let mut stream = client.health_watch("example.UserService").await.unwrap();
+    let next =
+        |response: Option<grpc_client::health::HealthCheckResponse>| response.unwrap().status();
+    assert_eq!(
+        next(stream.message().await.unwrap()),
+        ServingStatus::Serving
+    );
+
+    health.set_serving_status(
+        "example.UserService",
+        sample_server::ServingStatus::NotServing,
+    );
+    assert_eq!(
+        next(stream.message().await.unwrap()),
+        ServingStatus::NotServing
+    );
+}
+
+#[test]
+fn test_health_command_exit_codes() {
+    let health = HealthReporter::default();
+    health.set_serving_status(
+        "example.UserService",
+        sample_server::ServingStatus::NotServing,
+    );
+    let endpoint = start_plaintext_sample_server_with_health(health);
+    let health = |args: &[&str]| {
+        Command::cargo_bin("grpc-client")
+            .unwrap()
+            .args(["--plaintext", "--format", "text", "health", &endpoint])
+            .args(args)
+            .assert()
+    };
+
+    health(&[]).success().stdout("SERVING\n");
+    health(&["example.UserService"])
+        .code(2)
+        .stdout("NOT_SERVING\n");
+    health(&["missing"]).code(3).stdout("SERVICE_UNKNOWN\n");
+}
+
+/// Serve the web API for the sample server on a free port
+async fn start_web_server(endpoint: &str) -> String {
+    serve_web_config(ServerConfig {
+        servers: HashMap::from([("sample".to_string(), sample_config(endpoint))]),
+        ..ServerConfig::default()
+    })
+    .await
+}
+
+/// Serve the web API for a configuration on a free port
+async fn serve_web_config(config: ServerConfig) -> String {
+    let state = AppState::new(config).await.unwrap();
+    let app = create_router_with_swagger(state, "ui/dist", false);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    addr.to_string()
+}
+
+/// Send a masked text frame, as browsers do
+async fn ws_send(stream: &mut TcpStream, message: Value) {
+    let payload = message.to_string().into_bytes();
+    let mask = [1u8, 2, 3, 4];
+    let mut frame = vec![0x81, 0x80 | payload.len() as u8];
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+    stream.write_all(&frame).await.unwrap();
+}
+
+/// Read a frame from the server: the JSON of a text frame, or `None` for a
+/// close frame
+async fn ws_recv(stream: &mut TcpStream) -> Option<Value> {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).await.unwrap();
+    let len = match head[1] {
+        126 => stream.read_u16().await.unwrap() as usize,
+        len => len as usize,
+    };
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await.unwrap();
+    match head[0] & 0x0F {
+        0x1 => Some(serde_json::from_slice(&payload).unwrap()),
+        0x8 => None,
+        opcode => panic!("Unexpected opcode {}", opcode),
+    }
+}
+
+#[tokio::test]
+async fn test_streaming_call_over_websocket() {
+    let endpoint = start_plaintext_sample_server();
+    let web = start_web_server(&endpoint).await;
+
+    let mut stream = TcpStream::connect(&web).await.unwrap();
+    let handshake = format!(
+        "GET /api/servers/sample/call/ws HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\n\
+         Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\
+         Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+        web
+    );
+    stream.write_all(handshake.as_bytes()).await.unwrap();
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        response.push(stream.read_u8().await.unwrap());
+    }
+    let response = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 101"), "{}", response);
+    assert!(response.contains("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+
+    // GetUser is bidirectional: each response arrives before the half-close
+    ws_send(
+        &mut stream,
+        json!({"type": "start", "method": "example.UserService/GetUser", "query": "{id, name}", "callId": "ws-1"}),
+    )
+    .await;
+    assert_eq!(
+        ws_recv(&mut stream).await.unwrap(),
+        json!({"type": "started", "callId": "ws-1"})
+    );
+    ws_send(
+        &mut stream,
+        json!({"type": "message", "data": {"userId": "7"}}),
+    )
+    .await;
+    assert_eq!(ws_recv(&mut stream).await.unwrap()["type"], "headers");
+    assert_eq!(
+        ws_recv(&mut stream).await.unwrap(),
+        json!({"type": "message", "data": {"id": "7", "name": "John Doe"}})
+    );
+
+    ws_send(
+        &mut stream,
+        json!({"type": "message", "data": {"userId": 8}}),
+    )
+    .await;
+    assert_eq!(ws_recv(&mut stream).await.unwrap()["type"], "error");
+    ws_send(
+        &mut stream,
+        json!({"type": "message", "data": {"userId": "8"}}),
+    )
+    .await;
+    assert_eq!(ws_recv(&mut stream).await.unwrap()["data"]["id"], "8");
+
+    ws_send(&mut stream, json!({"type": "end"})).await;
+    let status = ws_recv(&mut stream).await.unwrap();
+    assert_eq!(status["type"], "status");
+    assert_eq!(status["code"], "OK");
+    assert_eq!(ws_recv(&mut stream).await, None);
+}
+
+#[tokio::test]
+async fn test_server_streaming_call_over_sse() {
+    let endpoint = start_plaintext_sample_server();
+    let web = start_web_server(&endpoint).await;
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/api/servers/sample/call/stream", web);
+
+    let response = client
+        .post(&url)
+        .json(&json!({"method": "example.UserService/ListUsers", "data": {}, "query": ".name"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response.text().await.unwrap();
+    let events: Vec<Value> = body
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+    assert_eq!(events[0]["type"], "started");
+    assert_eq!(events[1]["type"], "headers");
+    assert_eq!(events[2], json!({"type": "message", "data": "John Doe"}));
+    assert_eq!(events.last().unwrap()["code"], "OK");
+    assert!(body.contains("event: status"));
+
+    let response = client
+        .post(&url)
+        .json(&json!({"method": "example.UserService/GetUser", "data": {}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_cancel_running_call() {
+    let endpoint = start_plaintext_sample_server();
+    let web = start_web_server(&endpoint).await;
+    let client = reqwest::Client::new();
+
+    // Watch keeps streaming until the call is cancelled
+    let call = tokio::spawn({
+        let client = client.clone();
+        let url = format!("http://{}/api/servers/sample/call", web);
+        async move {
+            client
+                .post(&url)
+                .json(&json!({
+                    "method": "grpc.health.v1.Health/Watch",
+                    "data": {"service": ""},
+                    "callId": "watch-1"
+                }))
+                .send()
+                .await
+                .unwrap()
+        }
+    });
+
+    let calls_url = format!("http://{}/api/calls", web);
+    let mut calls = Value::Null;
+    for _ in 0..50 {
+        calls = client
+            .get(&calls_url)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if calls[0]["messagesReceived"] == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(calls[0]["id"], "watch-1");
+    assert_eq!(calls[0]["serverId"], "sample");
+    assert_eq!(calls[0]["method"], "grpc.health.v1.Health/Watch");
+    assert_eq!(calls[0]["messagesReceived"], 1);
+
+    let response = client
+        .delete(format!("{}/watch-1", calls_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+
+    let response = tokio::time::timeout(Duration::from_secs(5), call)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.status(), 499);
+
+    let calls: Value = client
+        .get(&calls_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(calls, json!([]));
+    let response = client
+        .delete(format!("{}/watch-1", calls_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_call_history_and_replay() {
+    let endpoint = start_plaintext_sample_server();
+    let web = start_web_server(&endpoint).await;
+    let client = reqwest::Client::new();
+    let call_url = format!("http://{}/api/servers/sample/call", web);
+    let history_url = format!("http://{}/api/history", web);
+
+    let response = client
+        .post(&call_url)
+        .json(&json!({
+            "method": "example.UserService/ListUsers",
+            "data": {},
+            "headers": {"x-tenant": "acme"}
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let response = client
+        .post(&call_url)
+        .json(&json!({"method": "grpc.health.v1.Health/Check", "data": {"service": "missing"}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 500);
+
+    let page: Value = client
+        .get(&history_url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["total"], 2);
+    let failed = &page["items"][0];
+    assert_eq!(failed["method"], "grpc.health.v1.Health/Check");
+    assert_eq!(failed["status"], "NOT_FOUND");
+    assert!(failed["error"].as_str().is_some());
+    let listed = &page["items"][1];
+    assert_eq!(listed["status"], "OK");
+    assert_eq!(listed["serverId"], "sample");
+    assert_eq!(listed["headers"], json!({"x-tenant": "acme"}));
+    assert!(listed["responses"].as_array().unwrap().len() > 1);
+    assert!(listed["responseSize"].as_u64().unwrap() > 0);
+
+    let page: Value = client
+        .get(&history_url)
+        .query(&[("q", "john doe"), ("perPage", "1")])
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["total"], 1);
+    assert_eq!(page["items"][0]["id"], listed["id"]);
+
+    let id = listed["id"].as_str().unwrap();
+    let record: Value = client
+        .get(format!("{}/{}", history_url, id))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(record, *listed);
+
+    let replayed: Value = client
+        .post(format!("{}/{}/replay", history_url, id))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_ne!(replayed["id"], listed["id"]);
+    assert_eq!(replayed["status"], "OK");
+    assert_eq!(replayed["request"], listed["request"]);
+    assert_eq!(replayed["responses"], listed["responses"]);
+
+    let page: Value = client
+        .get(&history_url)
+        .query(&[("status", "OK")])
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["total"], 2);
+    assert_eq!(page["items"][0]["id"], replayed["id"]);
+
+    let response = client
+        .post(format!("{}/missing/replay", history_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_replay_refuses_masked_records() {
+    let service = FlakyUserService::default();
+    let endpoint = serve_user_service(service.clone()).await;
+    let web = serve_web_config(ServerConfig {
+        servers: HashMap::from([("users".to_string(), sample_config(&endpoint))]),
+        ..ServerConfig::default()
+    })
+    .await;
+    let (client, web) = (&reqwest::Client::new(), &web);
+    let call = |data: Value, headers: Value| {
+        let request = client
+            .post(format!("http://{}/api/servers/users/call", web))
+            .json(&json!({
+                "method": "example.UserService/CreateUser",
+                "data": data,
+                "headers": headers
+            }))
+            .send();
+        async move {
+            let response = request.await.unwrap();
+            assert_eq!(response.status(), 200);
+            let history: Value = client
+                .get(format!("http://{}/api/history", web))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            history["items"][0]["id"].as_str().unwrap().to_string()
+        }
+    };
+    let replay = |id: String| {
+        client
+            .post(format!("http://{}/api/history/{}/replay", web, id))
+            .send()
+    };
+
+    // The card number and credential are masked in the record, so
+    // replaying it would send "[REDACTED]" for both
+    let id = call(
+        json!({"user": {"name": "Jane", "paymentInfo": {"cardNumber": "4111 1111"}}}),
+        json!({"authorization": "Bearer caller-token"}),
+    )
+    .await;
+    let response = replay(id).await.unwrap();
+    assert_eq!(response.status(), 409);
+    {
+        let received = service.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (authorization, request) = &received[0];
+        assert_eq!(authorization.as_deref(), Some("Bearer caller-token"));
+        let card = &request
+            .user
+            .as_ref()
+            .unwrap()
+            .payment_info
+            .as_ref()
+            .unwrap();
+        assert_eq!(card.card_number, "4111 1111");
+    }
+
+    // Records without masked values are replayed as they were sent
+    let id = call(
+        json!({"user": {"name": "John"}}),
+        json!({"x-tenant": "acme"}),
+    )
+    .await;
+    let response = replay(id).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let received = service.received.lock().unwrap();
+    assert_eq!(received.len(), 3);
+    assert_eq!(received[1], received[2]);
+    assert_eq!(received[2].0, None);
+    assert_eq!(received[2].1.user.as_ref().unwrap().name, "John");
+}
+
+#[tokio::test]
+async fn test_saved_request_collections() {
+    let endpoint = start_plaintext_sample_server();
+    let web = start_web_server(&endpoint).await;
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/api/collections", web);
+
+    let response = client
+        .post(format!("{}/users", url))
+        .json(&json!({"description": "User calls"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let response = client
+        .post(format!("{}/users", url))
+        .json(&json!({}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+
+    let response = client
+        .put(format!("{}/users/requests/list-users", url))
+        .json(&json!({
+            "server": "sample",
+            "method": "example.UserService/ListUsers",
+            "headers": {"x-tenant": "{{var.tenant}}"}
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let collection: Value = response.json().await.unwrap();
+    assert_eq!(collection["requests"][0]["name"], "list-users");
+    let response = client
+        .put(format!("{}/users/requests/bad", url))
+        .json(&json!({"method": "ListUsers"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .post(format!("{}/users/requests/list-users/run", url))
+        .json(&json!({"variables": {"tenant": "acme"}, "query": "{id, name}", "callId": "saved-1"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["x-call-id"], "saved-1");
+    let users: Vec<Value> = response.json().await.unwrap();
+    assert!(users.len() > 1);
+    assert!(users.iter().all(|user| user["name"].is_string()));
+    let history: Value = client
+        .get(format!("http://{}/api/history", web))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(history["items"][0]["headers"], json!({"x-tenant": "acme"}));
+
+    // Export, remove and import the collection file
+    let response = client
+        .get(format!("{}/users/export", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/yaml");
+    let file = response.text().await.unwrap();
+    assert!(file.contains("name: users"));
+
+    let response = client
+        .post(format!("{}/import", url))
+        .body(file.clone())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+    let response = client
+        .delete(format!("{}/users/requests/list-users", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    let response = client
+        .post(format!("{}/import?overwrite=true", url))
+        .body(file)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+
+    let collections: Value = client.get(&url).send().await.unwrap().json().await.unwrap();
+    assert_eq!(collections.as_array().unwrap().len(), 1);
+    assert_eq!(collections[0]["requests"].as_array().unwrap().len(), 1);
+
+    let response = client
+        .delete(format!("{}/users", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+    let response = client
+        .post(format!("{}/users/requests/list-users/run", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[test]
+fn test_run_saved_command() {
+    let endpoint = start_plaintext_sample_server();
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("users.yml"),
+        format!(
+            r#"
+requests:
+  - name: list-users
+    server: {endpoint}
+    method: example.UserService/ListUsers
+  - name: missing-server
+    method: example.UserService/ListUsers
+"#
+        ),
+    )
+    .unwrap();
+    let run_saved = |saved: &str| {
+        Command::cargo_bin("grpc-client")
+            .unwrap()
+            .args(["--plaintext", "--format", "json", "run-saved", saved])
+            .arg("--collections")
+            .arg(dir.path())
+            .args(["--query", "$.name"])
+            .assert()
+    };
+
+    let output = run_saved("users/list-users").success().get_output().clone();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("John Doe"));
+    run_saved("users/missing-server").failure();
+    run_saved("users/unknown").failure();
+}
+
+#[tokio::test]
+async fn test_session_environments() {
+    let endpoint = start_plaintext_sample_server();
+    let environment = |name: &str| Environment {
+        description: None,
+        variables: HashMap::from([
+            ("host".to_string(), endpoint.clone()),
+            ("name".to_string(), name.to_string()),
+        ]),
+        access: None,
+    };
+    let web = serve_web_config(ServerConfig {
+        servers: HashMap::from([("sample".to_string(), sample_config("{{var.host}}"))]),
+        environments: HashMap::from([
+            ("dev".to_string(), environment("Dev User")),
+            ("staging".to_string(), environment("Staging User")),
+        ]),
+        ..ServerConfig::default()
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let environments_url = format!("http://{}/api/environments", web);
+    let call = |session: &'static str, data: Value| {
+        client
+            .post(format!("http://{}/api/servers/sample/call", web))
+            .header("x-session-id", session)
+            .json(&json!({"method": "example.UserService/CreateUser", "data": data}))
+            .send()
+    };
+    let create = json!({"user": {"name": "{{var.name}}"}});
+
+    let list: Value = client
+        .get(&environments_url)
+        .header("x-session-id", "a")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["active"], Value::Null);
+    assert_eq!(list["environments"][0]["name"], "dev");
+    assert_eq!(
+        list["environments"][0]["variables"],
+        json!(["host", "name"])
+    );
+    // Without an environment the endpoint can't be expanded
+    assert_eq!(call("a", create.clone()).await.unwrap().status(), 400);
+
+    let select = |session: &'static str, name: Value| {
+        client
+            .put(format!("{}/active", environments_url))
+            .header("x-session-id", session)
+            .json(&json!({"name": name}))
+            .send()
+    };
+    assert_eq!(select("a", json!("prod")).await.unwrap().status(), 404);
+    let list: Value = select("a", json!("dev"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(list["active"], "dev");
+    select("b", json!("staging")).await.unwrap();
+
+    // The history records requests as they were sent
+    let last_request = || async {
+        let page: Value = client
+            .get(format!("http://{}/api/history", web))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        page["items"][0]["request"]["user"]["name"].clone()
+    };
+    assert_eq!(call("a", create.clone()).await.unwrap().status(), 200);
+    assert_eq!(last_request().await, "Dev User");
+    assert_eq!(call("b", create.clone()).await.unwrap().status(), 200);
+    assert_eq!(last_request().await, "Staging User");
+
+    // Variables sent with the call take precedence
+    let response = client
+        .post(format!("http://{}/api/servers/sample/call", web))
+        .header("x-session-id", "a")
+        .json(&json!({
+            "method": "example.UserService/CreateUser",
+            "data": create,
+            "variables": {"name": "Jane"}
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(last_request().await, "Jane");
+
+    // Callers can't read the web server's environment
+    let leak = call("a", json!({"user": {"name": "{{env.PATH}}"}}));
+    assert_eq!(leak.await.unwrap().status(), 400);
+    let response = client
+        .post(format!("http://{}/api/servers/sample/call", web))
+        .header("x-session-id", "a")
+        .json(&json!({
+            "method": "example.UserService/CreateUser",
+            "data": {},
+            "headers": {"x-leak": "{{env.PATH}}"}
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(last_request().await, "Jane");
+
+    let describe = |session: &'static str| {
+        client
+            .get(format!(
+                "http://{}/api/servers/sample/services/example.UserService/methods/CreateUser",
+                web
+            ))
+            .header("x-session-id", session)
+            .send()
+    };
+    assert_eq!(describe("a").await.unwrap().status(), 200);
+    select("a", Value::Null).await.unwrap();
+    assert_eq!(describe("a").await.unwrap().status(), 400);
+}
+
+#[tokio::test]
+async fn test_web_auth_and_access_control() {
+    let endpoint = start_plaintext_sample_server();
+    let config: ServerConfig = serde_yaml::from_str(&format!(
+        r#"
+servers:
+  sample:
+    name: Sample Server
+    endpoint: {endpoint}
+    plaintext: true
+    access:
+      allow:
+        - roles: [admin]
+        - users: [alice]
+          methods: ["example.UserService/List*"]
+  frozen:
+    name: Frozen Server
+    endpoint: {endpoint}
+    plaintext: true
+    access:
+      read_only: true
+web:
+  cors_origins: ["https://ui.example.com"]
+  admins:
+    roles: [admin]
+  auth:
+    type: bearer
+    tokens:
+      - token: root-token
+        user: root
+        roles: [admin]
+      - token: alice-token
+        user: alice
+      - token: bob-token
+        user: bob
+"#
+    ))
+    .unwrap();
+    let web = serve_web_config(config).await;
+    let client = reqwest::Client::new();
+    let call = |token: &'static str, server: &str, method: &str| {
+        client
+            .post(format!("http://{}/api/servers/{}/call", web, server))
+            .bearer_auth(token)
+            .json(&json!({"method": method, "data": {}}))
+            .send()
+    };
+    let get = |token: &'static str, path: &str| {
+        client
+            .get(format!("http://{}/api/{}", web, path))
+            .bearer_auth(token)
+            .send()
+    };
+    let server_ids = |servers: Value| {
+        let mut ids: Vec<String> = servers
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|server| server["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+
+    let response = client
+        .get(format!("http://{}/api/servers", web))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(response.headers()["www-authenticate"], "Bearer");
+    assert_eq!(
+        call("wrong", "sample", "x.Y/Z").await.unwrap().status(),
+        401
+    );
+    let health = client.get(format!("http://{}/api/health", web)).send();
+    assert_eq!(health.await.unwrap().status(), 200);
+
+    let servers = get("root-token", "servers").await.unwrap().json().await;
+    assert_eq!(server_ids(servers.unwrap()), ["frozen", "sample"]);
+    let servers = get("bob-token", "servers").await.unwrap().json().await;
+    assert_eq!(server_ids(servers.unwrap()), ["frozen"]);
+
+    let list_users = "example.UserService/ListUsers";
+    let create_user = "example.UserService/CreateUser";
+    assert_eq!(
+        call("alice-token", "sample", list_users)
+            .await
+            .unwrap()
+            .status(),
+        200
+    );
+    assert_eq!(
+        call("root-token", "sample", create_user)
+            .await
+            .unwrap()
+            .status(),
+        200
+    );
+    let denied = call("alice-token", "sample", create_user).await.unwrap();
+    assert_eq!(denied.status(), 403);
+    let error: Value = denied.json().await.unwrap();
+    assert!(error["details"].as_str().unwrap().contains("User 'alice'"));
+    // Methods named with a dot are held to the same rules
+    let status = |response: reqwest::Response| response.status();
+    assert_eq!(
+        status(
+            call("alice-token", "sample", "example.UserService.ListUsers")
+                .await
+                .unwrap()
+        ),
+        200
+    );
+    assert_eq!(
+        status(
+            call("alice-token", "sample", "example.UserService.CreateUser")
+                .await
+                .unwrap()
+        ),
+        403
+    );
+    assert_eq!(
+        call("bob-token", "sample", list_users)
+            .await
+            .unwrap()
+            .status(),
+        403
+    );
+    assert_eq!(
+        call("root-token", "frozen", list_users)
+            .await
+            .unwrap()
+            .status(),
+        403
+    );
+
+    // History only shows calls to servers the caller can use
+    let page: Value = get("root-token", "history")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["total"], 3);
+    let page: Value = get("bob-token", "history")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["total"], 0);
+
+    let remove = |token: &'static str| {
+        client
+            .delete(format!("http://{}/api/servers/frozen", web))
+            .bearer_auth(token)
+            .send()
+    };
+    assert_eq!(remove("alice-token").await.unwrap().status(), 403);
+    assert_eq!(remove("root-token").await.unwrap().status(), 204);
+
+    // Only configured origins may call the API from a browser
+    let preflight = |origin: &'static str| {
+        client
+            .request(
+                reqwest::Method::OPTIONS,
+                format!("http://{}/api/servers", web),
+            )
+            .header("origin", origin)
+            .header("access-control-request-method", "GET")
+            .header("access-control-request-headers", "authorization")
+            .send()
+    };
+    let allowed = preflight("https://ui.example.com").await.unwrap();
+    assert_eq!(
+        allowed.headers()["access-control-allow-origin"],
+        "https://ui.example.com"
+    );
+    assert_eq!(
+        allowed.headers()["access-control-allow-credentials"],
+        "true"
+    );
+    let other = preflight("https://evil.example.com").await.unwrap();
+    assert!(!other.headers().contains_key("access-control-allow-origin"));
+}
+
+#[tokio::test]
+async fn test_collection_and_environment_access() {
+    let endpoint = start_plaintext_sample_server();
+    let config: ServerConfig = serde_yaml::from_str(&format!(
+        r#"
+servers:
+  private:
+    name: Private Server
+    endpoint: {endpoint}
+    plaintext: true
+    access:
+      allow:
+        - users: [alice]
+  open:
+    name: Open Server
+    endpoint: {endpoint}
+    plaintext: true
+environments:
+  dev:
+    variables: {{ name: dev }}
+  prod:
+    variables: {{ name: prod }}
+    access:
+      users: [alice]
+web:
+  admins:
+    users: [alice]
+  auth:
+    type: bearer
+    tokens:
+      - token: alice-token
+        user: alice
+      - token: bob-token
+        user: bob
+"#
+    ))
+    .unwrap();
+    let web = serve_web_config(config).await;
+    let client = reqwest::Client::new();
+    let request = |method: reqwest::Method, token: &'static str, path: &str| {
+        client
+            .request(method, format!("http://{}/api/{}", web, path))
+            .bearer_auth(token)
+    };
+    let collection = |server: &str| {
+        json!({"requests": [
+            {"name": "list", "server": server, "method": "example.UserService/ListUsers"}
+        ]})
+    };
+
+    // Only admins change collections
+    for (name, server) in [("private-calls", "private"), ("open-calls", "open")] {
+        let response = request(
+            reqwest::Method::POST,
+            "bob-token",
+            &format!("collections/{}", name),
+        )
+        .json(&collection(server))
+        .send()
+        .await
+        .unwrap();
+        assert_eq!(response.status(), 403);
+        let response = request(
+            reqwest::Method::POST,
+            "alice-token",
+            &format!("collections/{}", name),
+        )
+        .json(&collection(server))
+        .send()
+        .await
+        .unwrap();
+        assert_eq!(response.status(), 201);
+    }
+    let response = request(
+        reqwest::Method::DELETE,
+        "bob-token",
+        "collections/open-calls",
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 403);
+
+    // Collections with requests for servers bob may not use are hidden
+    let names = |token: &'static str| {
+        let response = request(reqwest::Method::GET, token, "collections").send();
+        async move {
+            let collections: Value = response.await.unwrap().json().await.unwrap();
+            collections
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|c| c["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(names("alice-token").await, ["open-calls", "private-calls"]);
+    assert_eq!(names("bob-token").await, ["open-calls"]);
+    for path in [
+        "collections/private-calls",
+        "collections/private-calls/export",
+    ] {
+        let response = request(reqwest::Method::GET, "bob-token", path).send();
+        assert_eq!(response.await.unwrap().status(), 404);
+    }
+    let response = request(
+        reqwest::Method::POST,
+        "bob-token",
+        "collections/private-calls/requests/list/run",
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 404);
+    let response = request(
+        reqwest::Method::POST,
+        "bob-token",
+        "collections/open-calls/requests/list/run",
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Templated servers and methods are authorized as they are called
+    let response = request(
+        reqwest::Method::POST,
+        "alice-token",
+        "collections/templated-calls",
+    )
+    .json(&json!({"requests": [{
+        "name": "list",
+        "server": "{{var.server}}",
+        "method": "example.UserService/List{{var.what}}"
+    }]}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 201);
+    for (server, status) in [("private", 403), ("open", 200)] {
+        let response = request(
+            reqwest::Method::POST,
+            "bob-token",
+            "collections/templated-calls/requests/list/run",
+        )
+        .json(&json!({"variables": {"server": server, "what": "Users"}}))
+        .send()
+        .await
+        .unwrap();
+        assert_eq!(response.status(), status, "{}", server);
+    }
+
+    // Environments are limited by their access, and sessions by user
+    let select = |token: &'static str, name: &str| {
+        request(reqwest::Method::PUT, token, "environments/active")
+            .header("x-session-id", "shared")
+            .json(&json!({ "name": name }))
+            .send()
+    };
+    assert_eq!(select("bob-token", "prod").await.unwrap().status(), 403);
+    assert_eq!(select("alice-token", "prod").await.unwrap().status(), 200);
+    let environments: Value = request(reqwest::Method::GET, "bob-token", "environments")
+        .header("x-session-id", "shared")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(environments["active"], Value::Null);
+    assert_eq!(environments["environments"].as_array().unwrap().len(), 1);
+    assert_eq!(environments["environments"][0]["name"], "dev");
+}
+
+#[tokio::test]
+async fn test_server_changes_need_admins_with_auth() {
+    let endpoint = start_plaintext_sample_server();
+    let config: ServerConfig = serde_yaml::from_str(&format!(
+        r#"
+servers:
+  frozen:
+    name: Frozen Server
+    endpoint: {endpoint}
+    plaintext: true
+    access:
+      read_only: true
+web:
+  auth:
+    type: bearer
+    tokens:
+      - token: bob-token
+        user: bob
+"#
+    ))
+    .unwrap();
+    let web = serve_web_config(config).await;
+    let client = reqwest::Client::new();
+
+    // Without admins, rewriting the server would lift its access policy
+    let response = client
+        .put(format!("http://{}/api/servers/frozen", web))
+        .bearer_auth("bob-token")
+        .json(&json!({"name": "Frozen Server", "endpoint": endpoint, "plaintext": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = client
+        .post(format!("http://{}/api/servers/frozen", web))
+        .bearer_auth("bob-token")
+        .json(&json!({"name": "Other", "endpoint": endpoint, "plaintext": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let call = client
+        .post(format!("http://{}/api/servers/frozen/call", web))
+        .bearer_auth("bob-token")
+        .json(&json!({"method": "example.UserService/ListUsers", "data": {}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(call.status(), 403);
+}
+
+#[tokio::test]
+async fn test_websocket_rejects_foreign_origins() {
+    let endpoint = start_plaintext_sample_server();
+    let web = serve_web_config(ServerConfig {
+        servers: HashMap::from([("sample".to_string(), sample_config(&endpoint))]),
+        web: serde_yaml::from_str("cors_origins: [\"https://ui.example.com/\"]").unwrap(),
+        ..ServerConfig::default()
+    })
+    .await;
+    let handshake = |origin: Option<String>| {
+        let web = web.clone();
+        async move {
+            let mut stream = TcpStream::connect(&web).await.unwrap();
+            let origin = origin
+                .map(|origin| format!("Origin: {}\r\n", origin))
+                .unwrap_or_default();
+            let request = format!(
+                "GET /api/servers/sample/call/ws HTTP/1.1\r\nHost: {}\r\n{}Connection: Upgrade\r\n\
+                 Upgrade: websocket\r\nSec-WebSocket-Version: 13\r\n\
+                 Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+                web, origin
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = Vec::new();
+            while !response.ends_with(b"\r\n") {
+                response.push(stream.read_u8().await.unwrap());
+            }
+            String::from_utf8(response).unwrap()
+        }
+    };
+
+    let status = handshake(Some("https://evil.example.com".to_string())).await;
+    assert!(status.starts_with("HTTP/1.1 403"), "{}", status);
+    for origin in [
+        None,
+        Some(format!("http://{}", web)),
+        Some("https://ui.example.com".to_string()),
+    ] {
+        let status = handshake(origin.clone()).await;
+        assert!(
+            status.starts_with("HTTP/1.1 101"),
+            "{:?}: {}",
+            origin,
+            status
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_api_servers_cannot_reach_the_host() {
+    let endpoint = start_plaintext_sample_server();
+    let key_dir = tempfile::tempdir().unwrap();
+    let key_file = key_dir.path().join("jwt.key");
+    std::fs::write(&key_file, "secret").unwrap();
+    let server =
+        |auth: Value| json!({"name": "New", "endpoint": endpoint, "plaintext": true, "auth": auth});
+    let exec = server(json!({"type": "exec", "command": "touch", "args": ["/tmp/pwned"]}));
+    let jwt = server(json!({
+        "type": "jwt",
+        "key_file": key_file.to_str().unwrap(),
+        "algorithm": "HS256"
+    }));
+
+    // The open API takes neither exec nor JWT auth
+    let web = start_web_server(&endpoint).await;
+    let client = reqwest::Client::new();
+    for conf in [&exec, &jwt] {
+        let response = client
+            .post(format!("http://{}/api/servers/new", web))
+            .json(conf)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+        let response = client
+            .put(format!("http://{}/api/servers/sample", web))
+            .json(conf)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+    }
+
+    // Nor templates reading the server's environment
+    let response = client
+        .post(format!("http://{}/api/servers/new", web))
+        .json(&json!({
+            "name": "New",
+            "endpoint": endpoint,
+            "plaintext": true,
+            "headers": {"x-home": "{{env.HOME}}"}
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // Admins may set up JWT auth
+    let config: ServerConfig = serde_yaml::from_str(
+        r#"
+servers: {}
+web:
+  admins:
+    users: [alice]
+  auth:
+    type: bearer
+    tokens:
+      - token: alice-token
+        user: alice
+"#,
+    )
+    .unwrap();
+    let web = serve_web_config(config).await;
+    let response = client
+        .post(format!("http://{}/api/servers/new", web))
+        .bearer_auth("alice-token")
+        .json(&jwt)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+}
@@ -4,7 +4,7 @@ import { Badge } from '@/components/ui/badge';
 import { Button } from '@/components/ui/button';
 import { RefreshCw, Server, AlertCircle, CheckCircle, Loader2 } from 'lucide-react';
 import { useAppStore } from '@/lib/store';
-import { apiClient } from '@/lib/api';
+import { apiClient, HealthState } from '@/lib/api';
 
 export function ServerSelector() {
   const {
@@ -35,23 +35,25 @@ export function ServerSelector() {
     loadServers();
   }, []);
 
-  const getStatusIcon = (connected: boolean, error?: string) => {
-    if (error) {
+  const getStatusIcon = (state: HealthState) => {
+    if (state === 'unreachable' || state === 'not_serving') {
       return <AlertCircle className="w-4 h-4 text-red-500" />;
-    } else if (connected) {
+    } else if (state === 'serving') {
       return <CheckCircle className="w-4 h-4 text-green-500" />;
     } else {
       return <AlertCircle className="w-4 h-4 text-gray-400" />;
     }
   };
 
-  const getStatusBadge = (connected: boolean, error?: string) => {
-    if (error) {
-      return <Badge variant="destructive">Error</Badge>;
-    } else if (connected) {
-      return <Badge variant="default" className="text-green-700 bg-green-100">Connected</Badge>;
+  const getStatusBadge = (state: HealthState) => {
+    if (state === 'unreachable') {
+      return <Badge variant="destructive">Unreachable</Badge>;
+    } else if (state === 'not_serving') {
+      return <Badge variant="destructive">Not Serving</Badge>;
+    } else if (state === 'serving') {
+      return <Badge variant="default" className="text-green-700 bg-green-100">Serving</Badge>;
     } else {
-      return <Badge variant="secondary">Disconnected</Badge>;
+      return <Badge variant="secondary">Unknown</Badge>;
     }
   };
 
@@ -94,7 +96,7 @@ export function ServerSelector() {
             {servers.map((server) => (
               <SelectItem key={server.id} value={server.id}>
                 <div className="flex items-center space-x-2 w-full">
-                  {getStatusIcon(server.connected, server.error)}
+                  {getStatusIcon(server.state)}
                   <span className="flex-1">{server.name}</span>
                   <span className="text-xs text-gray-500">{server.endpoint}</span>
                 </div>
@@ -111,7 +113,7 @@ export function ServerSelector() {
                 <div key={server.id} className="p-3 bg-gray-50 rounded-md">
                   <div className="flex justify-between items-center mb-2">
                     <h4 className="font-medium">{server.name}</h4>
-                    {getStatusBadge(server.connected, server.error)}
+                    {getStatusBadge(server.state)}
                   </div>
                   <p className="mb-1 text-sm text-gray-600">
                     <strong>Endpoint:</strong> {server.endpoint}
@@ -121,14 +123,15 @@ export function ServerSelector() {
                       <strong>Description:</strong> {server.description}
                     </p>
                   )}
-                  {server.lastConnected && (
+                  {server.lastChecked && (
                     <p className="mb-1 text-sm text-gray-600">
-                      <strong>Last Connected:</strong> {new Date(server.lastConnected).toLocaleString()}
+                      <strong>Last Checked:</strong> {new Date(server.lastChecked).toLocaleString()}
+                      {server.latencyMs != null && ` (${server.latencyMs.toFixed(1)} ms)`}
                     </p>
                   )}
-                  {server.error && (
+                  {server.lastError && (
                     <p className="text-sm text-red-600">
-                      <strong>Error:</strong> {server.error}
+                      <strong>Last Error:</strong> {server.lastError}
                     </p>
                   )}
                 </div>
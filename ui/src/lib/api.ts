@@ -4,10 +4,14 @@ export interface ServerStatus {
   endpoint: string;
   connected: boolean;
   description?: string;
-  lastConnected?: string;
-  error?: string;
+  state: HealthState;
+  lastChecked?: string;
+  latencyMs?: number;
+  lastError?: string;
 }
 
+export type HealthState = 'unknown' | 'serving' | 'not_serving' | 'unreachable';
+
 export interface ServiceInfo {
   name: string;
   description?: string;
@@ -29,6 +33,16 @@ export interface CallRequest {
   data: any;
   headers?: Record<string, string>;
   emitDefaults?: boolean;
+  // ID for cancelling the call with cancelCall; generated by the server when absent
+  callId?: string;
+}
+
+export interface ActiveCall {
+  id: string;
+  serverId: string;
+  method: string;
+  startedAt: string;
+  messagesReceived: number;
 }
 
 // Updated to handle Vec<Value> response structure
@@ -63,6 +77,102 @@ export interface CallHistoryEntry {
   headers?: Record<string, string>;
 }
 
+export interface HistoryRecord {
+  id: string;
+  timestamp: string;
+  serverId: string;
+  method: string;
+  request: any;
+  headers: Record<string, string>;
+  responses: any[];
+  status: string;
+  error?: string;
+  durationMs: number;
+  requestSize: number;
+  responseSize: number;
+  redacted: boolean;
+}
+
+export interface HistoryPage {
+  items: HistoryRecord[];
+  total: number;
+  page: number;
+  perPage: number;
+}
+
+export interface HistoryQuery {
+  q?: string;
+  serverId?: string;
+  method?: string;
+  status?: string;
+  page?: number;
+  perPage?: number;
+}
+
+export interface SavedRequest {
+  name: string;
+  description?: string;
+  server?: string;
+  method: string;
+  request: any;
+  headers?: Record<string, string>;
+}
+
+export interface Collection {
+  name: string;
+  description?: string;
+  requests: SavedRequest[];
+}
+
+export interface RunSavedRequest {
+  variables?: Record<string, string>;
+  query?: string;
+  callId?: string;
+}
+
+export interface EnvironmentInfo {
+  name: string;
+  description?: string;
+  variables: string[];
+}
+
+export interface EnvironmentList {
+  active?: string;
+  default?: string;
+  environments: EnvironmentInfo[];
+}
+
+// Identifies this tab's session, whose selected environment applies to calls
+function sessionId(): string {
+  const key = 'grpc-client-session';
+  let id = sessionStorage.getItem(key);
+  if (!id) {
+    id = crypto.randomUUID();
+    sessionStorage.setItem(key, id);
+  }
+  return id;
+}
+
+const TOKEN_KEY = 'grpc-client-token';
+
+// Credentials for a web server with authentication enabled: a bearer token,
+// or `Basic <base64>` for basic auth
+export function setAuthToken(token: string | null) {
+  if (token) {
+    localStorage.setItem(TOKEN_KEY, token);
+  } else {
+    localStorage.removeItem(TOKEN_KEY);
+  }
+}
+
+function authHeaders(): Record<string, string> {
+  const token = localStorage.getItem(TOKEN_KEY);
+  if (!token) {
+    return {};
+  }
+  return { Authorization: /^(Bearer|Basic) /i.test(token) ? token : `Bearer ${token}` };
+}
+
 class ApiClient {
   private baseUrl: string;
 
@@ -78,11 +188,13 @@ class ApiClient {
 
     try {
       const response = await fetch(url, {
+        ...options,
         headers: {
           'Content-Type': 'application/json',
+          'x-session-id': sessionId(),
+          ...authHeaders(),
           ...options.headers,
         },
-        ...options,
       });
 
       if (!response.ok) {
@@ -92,6 +204,9 @@ class ApiClient {
         throw new Error(errorData.details || errorData.error);
       }
 
+      if (response.status === 204) {
+        return undefined as T;
+      }
       return await response.json();
     } catch (error) {
       if (error instanceof Error) {
@@ -142,6 +257,124 @@ class ApiClient {
       body: JSON.stringify(request),
     });
   }
+
+  // Call history
+  async listHistory(query: HistoryQuery = {}): Promise<HistoryPage> {
+    const params = new URLSearchParams();
+    for (const [key, value] of Object.entries(query)) {
+      if (value !== undefined && value !== '') {
+        params.set(key, String(value));
+      }
+    }
+    const search = params.toString();
+    return this.request(`/api/history${search ? `?${search}` : ''}`);
+  }
+
+  async getHistoryRecord(id: string): Promise<HistoryRecord> {
+    return this.request(`/api/history/${encodeURIComponent(id)}`);
+  }
+
+  async replayHistoryRecord(id: string): Promise<HistoryRecord> {
+    return this.request(`/api/history/${encodeURIComponent(id)}/replay`, {
+      method: 'POST',
+    });
+  }
+
+  // Environments
+  async listEnvironments(): Promise<EnvironmentList> {
+    return this.request('/api/environments');
+  }
+
+  async selectEnvironment(name: string | null): Promise<EnvironmentList> {
+    return this.request('/api/environments/active', {
+      method: 'PUT',
+      body: JSON.stringify({ name }),
+    });
+  }
+
+  // Saved request collections
+  async listCollections(): Promise<Collection[]> {
+    return this.request('/api/collections');
+  }
+
+  async getCollection(name: string): Promise<Collection> {
+    return this.request(`/api/collections/${encodeURIComponent(name)}`);
+  }
+
+  async createCollection(collection: Collection): Promise<Collection> {
+    return this.request(`/api/collections/${encodeURIComponent(collection.name)}`, {
+      method: 'POST',
+      body: JSON.stringify(collection),
+    });
+  }
+
+  async updateCollection(collection: Collection): Promise<Collection> {
+    return this.request(`/api/collections/${encodeURIComponent(collection.name)}`, {
+      method: 'PUT',
+      body: JSON.stringify(collection),
+    });
+  }
+
+  async deleteCollection(name: string): Promise<void> {
+    return this.request(`/api/collections/${encodeURIComponent(name)}`, {
+      method: 'DELETE',
+    });
+  }
+
+  async saveRequest(collection: string, request: SavedRequest): Promise<Collection> {
+    return this.request(
+      `/api/collections/${encodeURIComponent(collection)}/requests/${encodeURIComponent(request.name)}`,
+      { method: 'PUT', body: JSON.stringify(request) }
+    );
+  }
+
+  async deleteSavedRequest(collection: string, name: string): Promise<void> {
+    return this.request(
+      `/api/collections/${encodeURIComponent(collection)}/requests/${encodeURIComponent(name)}`,
+      { method: 'DELETE' }
+    );
+  }
+
+  async runSavedRequest(
+    collection: string,
+    name: string,
+    options: RunSavedRequest = {}
+  ): Promise<any[]> {
+    return this.request(
+      `/api/collections/${encodeURIComponent(collection)}/requests/${encodeURIComponent(name)}/run`,
+      { method: 'POST', body: JSON.stringify(options) }
+    );
+  }
+
+  async exportCollection(name: string): Promise<string> {
+    const response = await fetch(
+      `${this.baseUrl}/api/collections/${encodeURIComponent(name)}/export`,
+      { headers: authHeaders() }
+    );
+    if (!response.ok) {
+      throw new Error(`HTTP ${response.status}: ${response.statusText}`);
+    }
+    return response.text();
+  }
+
+  async importCollection(file: string, overwrite = false): Promise<Collection> {
+    return this.request(`/api/collections/import${overwrite ? '?overwrite=true' : ''}`, {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/yaml' },
+      body: file,
+    });
+  }
+
+  // Running calls
+  async listCalls(): Promise<ActiveCall[]> {
+    return this.request('/api/calls');
+  }
+
+  async cancelCall(callId: string): Promise<void> {
+    return this.request(`/api/calls/${encodeURIComponent(callId)}`, {
+      method: 'DELETE',
+    });
+  }
 }
 
 export const apiClient = new ApiClient();